@@ -5,11 +5,13 @@ use std::sync::Arc;
 
 use bytes::Bytes;
 use roaring::RoaringTreemap;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{DeltaResult, Error, FileSystemClient};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeletionVectorDescriptor {
     /// A single character to indicate how to access the DV. Legal options are: ['u', 'i', 'p'].
     pub storage_type: String,
@@ -32,6 +34,7 @@ pub struct DeletionVectorDescriptor {
 
     /// Start of the data for this DV in number of bytes from the beginning of the file it is stored in.
     /// Always None (absent in JSON) when `storageType = 'i'`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub offset: Option<i32>,
 
     /// Size of the serialized DV in bytes (raw data size, i.e. before base85 encoding, if inline).
@@ -43,10 +46,19 @@ pub struct DeletionVectorDescriptor {
 
 impl DeletionVectorDescriptor {
     pub fn unique_id(&self) -> String {
-        if let Some(offset) = self.offset {
-            format!("{}{}@{offset}", self.storage_type, self.path_or_inline_dv)
+        Self::unique_id_from_parts(&self.storage_type, &self.path_or_inline_dv, self.offset)
+    }
+
+    /// Build the [unique id](Self::unique_id) of a deletion vector from its individual fields.
+    pub(crate) fn unique_id_from_parts(
+        storage_type: &str,
+        path_or_inline_dv: &str,
+        offset: Option<i32>,
+    ) -> String {
+        if let Some(offset) = offset {
+            format!("{storage_type}{path_or_inline_dv}@{offset}")
         } else {
-            format!("{}{}", self.storage_type, self.path_or_inline_dv)
+            format!("{storage_type}{path_or_inline_dv}")
         }
     }
 
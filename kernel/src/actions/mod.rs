@@ -4,17 +4,20 @@ pub(crate) mod schemas;
 pub(crate) mod visitors;
 
 use std::{collections::HashMap, sync::Arc};
-use visitors::{AddVisitor, MetadataVisitor, ProtocolVisitor};
+
+use serde::{Deserialize, Serialize};
+use visitors::{AddVisitor, CommitInfoVisitor, MetadataVisitor, ProtocolVisitor};
 
 use crate::{schema::StructType, DeltaResult, EngineData};
 
 use self::deletion_vector::DeletionVectorDescriptor;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Format {
     /// Name of the encoding for files in this table
     pub provider: String,
     /// A map containingconfiguration options for the format
+    #[serde(default)]
     pub options: HashMap<String, String>,
 }
 
@@ -27,7 +30,8 @@ impl Default for Format {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Metadata {
     /// Unique identifier for this table
     pub id: String,
@@ -44,6 +48,7 @@ pub struct Metadata {
     /// The time when this metadata action is created, in milliseconds since the Unix epoch
     pub created_time: Option<i64>,
     /// Configuration options for the metadata action
+    #[serde(default)]
     pub configuration: HashMap<String, Option<String>>,
 }
 
@@ -60,7 +65,8 @@ impl Metadata {
     }
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Protocol {
     /// The minimum version of the Delta read protocol that a client must implement
     /// in order to correctly read this table
@@ -70,9 +76,11 @@ pub struct Protocol {
     pub min_writer_version: i32,
     /// A collection of features that a client must implement in order to correctly
     /// read this table (exist only when minReaderVersion is set to 3)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reader_features: Option<Vec<String>>,
     /// A collection of features that a client must implement in order to correctly
     /// write this table (exist only when minWriterVersion is set to 7)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub writer_features: Option<Vec<String>>,
 }
 
@@ -85,7 +93,8 @@ impl Protocol {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Add {
     /// A relative path to a data file from the root of the table or an absolute path to a file
     /// that should be added to the table. The path is a URI as specified by
@@ -110,23 +119,28 @@ pub struct Add {
     /// Contains [statistics] (e.g., count, min/max values for columns) about the data in this logical file.
     ///
     /// [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<String>,
 
     /// Map containing metadata about this logical file.
     pub tags: HashMap<String, Option<String>>,
 
     /// Information about deletion vector (DV) associated with this add action
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub deletion_vector: Option<DeletionVectorDescriptor>,
 
     /// Default generated Row ID of the first row in the file. The default generated Row IDs
     /// of the other rows in the file can be reconstructed by adding the physical index of the
     /// row within the file to the base Row ID
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub base_row_id: Option<i64>,
 
     /// First commit version in which an add action with the same path was committed to the table.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_row_commit_version: Option<i64>,
 
     /// The name of the clustering implementation
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub clustering_provider: Option<String>,
 }
 
@@ -144,6 +158,68 @@ impl Add {
     }
 }
 
+/// Provenance information about a commit, stored in its `commitInfo` action.
+///
+/// Apart from the `timestamp` and `kernelVersion`, which are set when committing, all fields are
+/// optional and may be filled in by the engine.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    /// The time of the commit, as milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// The time of the commit according to the writer, if in-commit timestamps are enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_commit_timestamp: Option<i64>,
+    /// The name of the operation, e.g. `WRITE`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// Parameters of the operation, e.g. the write mode
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub operation_parameters: HashMap<String, String>,
+    /// Metrics of the operation, e.g. the number of files written
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub operation_metrics: HashMap<String, String>,
+    /// The isolation level the commit was made at, e.g. `Serializable`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isolation_level: Option<String>,
+    /// The version of the table the commit is based on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_version: Option<i64>,
+    /// Whether the commit only adds files, without reading the table
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_blind_append: Option<bool>,
+    /// Information about the engine that made the commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_info: Option<String>,
+    /// The version of the kernel that made the commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kernel_version: Option<String>,
+}
+
+impl CommitInfo {
+    pub fn try_new_from_data(data: &dyn EngineData) -> DeltaResult<Option<CommitInfo>> {
+        let mut visitor = CommitInfoVisitor::default();
+        let schema = StructType::new(vec![crate::actions::schemas::COMMIT_INFO_FIELD.clone()]);
+        data.extract(Arc::new(schema), &mut visitor)?;
+        Ok(visitor.commit_info)
+    }
+}
+
+/// An application-specific transaction identifier, used by writers to make their commits
+/// idempotent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetTransaction {
+    /// A unique identifier for the application performing the transaction
+    pub app_id: String,
+    /// An application-specific numeric identifier for this transaction
+    pub version: i64,
+    /// The time when this transaction action was created, in milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<i64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Remove {
     /// A relative path to a data file from the root of the table or an absolute path to a file
@@ -184,6 +260,48 @@ pub(crate) struct Remove {
     pub(crate) default_row_commit_version: Option<i64>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cdc {
+    /// A relative path to a change data file from the root of the table or an absolute path to a
+    /// change data file that should be added to the table. The path is a URI as specified by
+    /// [RFC 2396 URI Generic Syntax], which needs to be decoded to get the file path.
+    ///
+    /// [RFC 2396 URI Generic Syntax]: https://www.ietf.org/rfc/rfc2396.txt
+    pub(crate) path: String,
+
+    /// A map from partition column to value for this file.
+    pub(crate) partition_values: HashMap<String, Option<String>>,
+
+    /// The size of this cdc file in bytes
+    pub(crate) size: i64,
+
+    /// Should always be set to `false` for `cdc` actions because they *do not* change the
+    /// underlying data of the table
+    pub(crate) data_change: bool,
+
+    /// Map containing metadata about this file
+    pub(crate) tags: Option<HashMap<String, Option<String>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Sidecar {
+    /// A path to the sidecar file, relative to the `_delta_log/_sidecars` directory or absolute.
+    /// The path is a URI as specified by [RFC 2396 URI Generic Syntax], which needs to be decoded
+    /// to get the file path.
+    ///
+    /// [RFC 2396 URI Generic Syntax]: https://www.ietf.org/rfc/rfc2396.txt
+    pub(crate) path: String,
+
+    /// The size of the sidecar file in bytes
+    pub(crate) size_in_bytes: i64,
+
+    /// The time this sidecar file was created, as milliseconds since the epoch
+    pub(crate) modification_time: i64,
+
+    /// Map containing metadata about this sidecar file
+    pub(crate) tags: Option<HashMap<String, Option<String>>>,
+}
+
 impl Remove {
     // _try_new_from_data for now, to avoid warning, probably will need at some point
     // pub(crate) fn _try_new_from_data(
@@ -196,8 +314,4 @@ impl Remove {
     //         .extracted
     //         .unwrap_or_else(|| Err(Error::generic("Didn't get expected remove")))
     // }
-
-    pub(crate) fn dv_unique_id(&self) -> Option<String> {
-        self.deletion_vector.as_ref().map(|dv| dv.unique_id())
-    }
 }
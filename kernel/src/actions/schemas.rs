@@ -67,11 +67,13 @@ lazy_static! {
         true,
     );
     // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#commit-provenance-information
-    static ref COMMIT_INFO_FIELD: StructField = StructField::new(
+    pub(crate) static ref COMMIT_INFO_FIELD: StructField = StructField::new(
         "commitInfo",
         StructType::new(vec![
-            StructField::new("timestamp", DataType::LONG, false),
-            StructField::new("operation", DataType::STRING, false),
+            // commitInfo is free-form, so even these are optional
+            StructField::new("timestamp", DataType::LONG, true),
+            StructField::new("inCommitTimestamp", DataType::LONG, true),
+            StructField::new("operation", DataType::STRING, true),
             StructField::new("isolationLevel", DataType::STRING, true),
             StructField::new("isBlindAppend", DataType::BOOLEAN, true),
             StructField::new("txnId", DataType::STRING, true),
@@ -94,6 +96,16 @@ lazy_static! {
                 ),
                 true,
             ),
+            StructField::new("engineInfo", DataType::STRING, true),
+            StructField::new("kernelVersion", DataType::STRING, true),
+        ]),
+        true,
+    );
+    // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps
+    pub(crate) static ref IN_COMMIT_TIMESTAMP_FIELD: StructField = StructField::new(
+        "commitInfo",
+        StructType::new(vec![
+            StructField::new("inCommitTimestamp", DataType::LONG, true),
         ]),
         true,
     );
@@ -143,7 +155,7 @@ lazy_static! {
         true,
     );
     // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#add-cdc-file
-    static ref CDC_FIELD: StructField = StructField::new(
+    pub(crate) static ref CDC_FIELD: StructField = StructField::new(
         "cdc",
         StructType::new(vec![
             StructField::new("path", DataType::STRING, false),
@@ -155,7 +167,7 @@ lazy_static! {
         true,
     );
     // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#transaction-identifiers
-    static ref TXN_FIELD: StructField = StructField::new(
+    pub(crate) static ref TXN_FIELD: StructField = StructField::new(
         "txn",
         StructType::new(vec![
             StructField::new("appId", DataType::STRING, false),
@@ -165,7 +177,7 @@ lazy_static! {
         true,
     );
     // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata
-    static ref DOMAIN_METADATA_FIELD: StructField = StructField::new(
+    pub(crate) static ref DOMAIN_METADATA_FIELD: StructField = StructField::new(
         "domainMetadata",
         StructType::new(vec![
             StructField::new("domain", DataType::STRING, false),
@@ -192,18 +204,29 @@ lazy_static! {
         true,
     );
     // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#sidecar-file-information
-    static ref SIDECAR_FIELD: StructField = StructField::new(
+    pub(crate) static ref SIDECAR_FIELD: StructField = StructField::new(
         "sidecar",
         StructType::new(vec![
             StructField::new("path", DataType::STRING, false),
             StructField::new("sizeInBytes", DataType::LONG, false),
             StructField::new("modificationTime", DataType::LONG, false),
-            StructField::new("type", DataType::STRING, false),
             tags_field(),
         ]),
         true,
     );
 
+    // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#checkpoint-schema
+    pub(crate) static ref CHECKPOINT_SCHEMA: StructType = StructType::new(
+        vec![
+            ADD_FIELD.clone(),
+            REMOVE_FIELD.clone(),
+            METADATA_FIELD.clone(),
+            PROTOCOL_FIELD.clone(),
+            TXN_FIELD.clone(),
+            DOMAIN_METADATA_FIELD.clone(),
+        ]
+    );
+
     static ref LOG_SCHEMA: StructType = StructType::new(
         vec![
             ADD_FIELD.clone(),
@@ -8,7 +8,10 @@ use crate::{
     DataVisitor, DeltaResult,
 };
 
-use super::{deletion_vector::DeletionVectorDescriptor, Add, Format, Metadata, Protocol, Remove};
+use super::{
+    deletion_vector::DeletionVectorDescriptor, Add, Cdc, CommitInfo, Format, Metadata, Protocol,
+    Remove, Sidecar,
+};
 
 #[derive(Default)]
 pub(crate) struct MetadataVisitor {
@@ -16,7 +19,7 @@ pub(crate) struct MetadataVisitor {
 }
 
 impl MetadataVisitor {
-    fn visit_metadata<'a>(
+    pub(crate) fn visit_metadata<'a>(
         row_index: usize,
         id: String,
         getters: &[&'a dyn GetData<'a>],
@@ -207,7 +210,8 @@ impl RemoveVisitor {
         let extended_file_metadata: Option<bool> =
             getters[3].get_opt(row_index, "remove.extendedFileMetadata")?;
 
-        // TODO(nick) handle partition values in getters[4]
+        let partition_values: Option<HashMap<_, _>> =
+            getters[4].get_opt(row_index, "remove.partitionValues")?;
 
         let size: Option<i64> = getters[5].get_opt(row_index, "remove.size")?;
 
@@ -245,7 +249,7 @@ impl RemoveVisitor {
             data_change,
             deletion_timestamp,
             extended_file_metadata,
-            partition_values: None,
+            partition_values,
             size,
             tags: None,
             deletion_vector,
@@ -268,6 +272,177 @@ impl DataVisitor for RemoveVisitor {
     }
 }
 
+#[derive(Default)]
+pub(crate) struct CdcVisitor {
+    pub(crate) cdcs: Vec<Cdc>,
+}
+
+impl CdcVisitor {
+    pub(crate) fn visit_cdc<'a>(
+        row_index: usize,
+        path: String,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<Cdc> {
+        let partition_values: HashMap<_, _> = getters[1].get(row_index, "cdc.partitionValues")?;
+        let size: i64 = getters[2].get(row_index, "cdc.size")?;
+        let data_change: bool = getters[3].get(row_index, "cdc.dataChange")?;
+        let tags: Option<HashMap<_, _>> = getters[4].get_opt(row_index, "cdc.tags")?;
+
+        Ok(Cdc {
+            path,
+            partition_values,
+            size,
+            data_change,
+            tags,
+        })
+    }
+}
+
+impl DataVisitor for CdcVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            // Since path column is required, use it to detect presence of a Cdc action
+            if let Some(path) = getters[0].get_opt(i, "cdc.path")? {
+                self.cdcs.push(Self::visit_cdc(i, path, getters)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SidecarVisitor {
+    pub(crate) sidecars: Vec<Sidecar>,
+}
+
+impl SidecarVisitor {
+    fn visit_sidecar<'a>(
+        row_index: usize,
+        path: String,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<Sidecar> {
+        let size_in_bytes: i64 = getters[1].get(row_index, "sidecar.sizeInBytes")?;
+        let modification_time: i64 = getters[2].get(row_index, "sidecar.modificationTime")?;
+        let tags: Option<HashMap<_, _>> = getters[3].get_opt(row_index, "sidecar.tags")?;
+
+        Ok(Sidecar {
+            path,
+            size_in_bytes,
+            modification_time,
+            tags,
+        })
+    }
+}
+
+impl DataVisitor for SidecarVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            // Since path column is required, use it to detect presence of a sidecar action
+            if let Some(path) = getters[0].get_opt(i, "sidecar.path")? {
+                self.sidecars.push(Self::visit_sidecar(i, path, getters)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct InCommitTimestampVisitor {
+    pub(crate) in_commit_timestamp: Option<i64>,
+}
+
+impl DataVisitor for InCommitTimestampVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            if let Some(ts) = getters[0].get_opt(i, "commitInfo.inCommitTimestamp")? {
+                self.in_commit_timestamp = Some(ts);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct CommitInfoVisitor {
+    pub(crate) commit_info: Option<CommitInfo>,
+}
+
+impl CommitInfoVisitor {
+    /// Read the commitInfo action of the row, or `None` if all of its fields are null, since none
+    /// of them are required.
+    fn visit_commit_info<'a>(
+        row_index: usize,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<Option<CommitInfo>> {
+        let timestamp: Option<i64> = getters[0].get_opt(row_index, "commitInfo.timestamp")?;
+        let in_commit_timestamp: Option<i64> =
+            getters[1].get_opt(row_index, "commitInfo.inCommitTimestamp")?;
+        let operation: Option<String> = getters[2].get_opt(row_index, "commitInfo.operation")?;
+        let isolation_level: Option<String> =
+            getters[3].get_opt(row_index, "commitInfo.isolationLevel")?;
+        let is_blind_append: Option<bool> =
+            getters[4].get_opt(row_index, "commitInfo.isBlindAppend")?;
+        // txnId is not part of CommitInfo, but shows that the action is present
+        let txn_id: Option<String> = getters[5].get_opt(row_index, "commitInfo.txnId")?;
+        let read_version: Option<i64> = getters[6].get_opt(row_index, "commitInfo.readVersion")?;
+        let operation_parameters: Option<HashMap<_, _>> =
+            getters[7].get_opt(row_index, "commitInfo.operationParameters")?;
+        let operation_metrics: Option<HashMap<_, _>> =
+            getters[8].get_opt(row_index, "commitInfo.operationMetrics")?;
+        let engine_info: Option<String> = getters[9].get_opt(row_index, "commitInfo.engineInfo")?;
+        let kernel_version: Option<String> =
+            getters[10].get_opt(row_index, "commitInfo.kernelVersion")?;
+
+        if timestamp.is_none()
+            && in_commit_timestamp.is_none()
+            && operation.is_none()
+            && isolation_level.is_none()
+            && is_blind_append.is_none()
+            && txn_id.is_none()
+            && read_version.is_none()
+            && operation_parameters.is_none()
+            && operation_metrics.is_none()
+            && engine_info.is_none()
+            && kernel_version.is_none()
+        {
+            return Ok(None);
+        }
+
+        // null values carry no information, so they are left out of the maps
+        let non_null = |map: Option<HashMap<String, Option<String>>>| {
+            map.unwrap_or_default()
+                .into_iter()
+                .filter_map(|(key, value)| Some((key, value?)))
+                .collect()
+        };
+        Ok(Some(CommitInfo {
+            timestamp,
+            in_commit_timestamp,
+            operation,
+            operation_parameters: non_null(operation_parameters),
+            operation_metrics: non_null(operation_metrics),
+            isolation_level,
+            read_version,
+            is_blind_append,
+            engine_info,
+            kernel_version,
+        }))
+    }
+}
+
+impl DataVisitor for CommitInfoVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            if let Some(commit_info) = Self::visit_commit_info(i, getters)? {
+                self.commit_info = Some(commit_info);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -354,6 +529,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_commit_info() -> DeltaResult<()> {
+        let data = action_batch();
+        let parsed = CommitInfo::try_new_from_data(data.as_ref())?.unwrap();
+        let expected = CommitInfo {
+            timestamp: Some(1677811178585),
+            operation: Some("WRITE".into()),
+            operation_parameters: HashMap::from([
+                ("mode".into(), "ErrorIfExists".into()),
+                ("partitionBy".into(), "[]".into()),
+            ]),
+            operation_metrics: HashMap::from([
+                ("numFiles".into(), "1".into()),
+                ("numOutputRows".into(), "10".into()),
+                ("numOutputBytes".into(), "635".into()),
+            ]),
+            isolation_level: Some("WriteSerializable".into()),
+            is_blind_append: Some(true),
+            engine_info: Some("Databricks-Runtime/<unknown>".into()),
+            ..Default::default()
+        };
+        assert_eq!(parsed, expected);
+        Ok(())
+    }
+
     #[test]
     fn test_parse_add_partitioned() {
         let client = SimpleClient::new();
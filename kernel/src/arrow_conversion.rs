@@ -138,7 +138,7 @@ impl TryFrom<&DataType> for ArrowDataType {
                         ]
                         .into(),
                     ),
-                    true,
+                    false, // always non-null
                 )),
                 false,
             )),
@@ -0,0 +1,467 @@
+//! Writing checkpoints of a [`Snapshot`].
+//!
+//! A checkpoint contains the reconciled state of a table at a given version, so that readers don't
+//! have to replay all commits that lead up to it.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use md5::{Digest, Md5};
+use tracing::debug;
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::schemas::CHECKPOINT_SCHEMA;
+use crate::engine_data::{GetData, TypedGetData};
+use crate::path::LogPath;
+use crate::schema::{DataType, SchemaRef, StructField, StructType};
+use crate::snapshot::{
+    read_last_checkpoint, CheckpointMetadata, Snapshot, LAST_CHECKPOINT_FILE_NAME,
+};
+use crate::{DataVisitor, DeltaResult, EngineInterface, Error};
+
+/// How long tombstones of removed files are retained in checkpoints, unless the table sets
+/// `delta.deletedFileRetentionDuration`.
+const DEFAULT_DELETED_FILE_RETENTION_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+lazy_static! {
+    /// The columns needed to decide which actions belong in a checkpoint.
+    static ref SELECTION_SCHEMA: SchemaRef = Arc::new(StructType::new(vec![
+        StructField::new(
+            "add",
+            StructType::new(vec![
+                StructField::new("path", DataType::STRING, true),
+                deletion_vector_id_field(),
+            ]),
+            true,
+        ),
+        StructField::new(
+            "remove",
+            StructType::new(vec![
+                StructField::new("path", DataType::STRING, true),
+                StructField::new("deletionTimestamp", DataType::LONG, true),
+                deletion_vector_id_field(),
+            ]),
+            true,
+        ),
+        StructField::new(
+            "metaData",
+            StructType::new(vec![StructField::new("id", DataType::STRING, true)]),
+            true,
+        ),
+        StructField::new(
+            "protocol",
+            StructType::new(vec![StructField::new(
+                "minReaderVersion",
+                DataType::INTEGER,
+                true,
+            )]),
+            true,
+        ),
+        StructField::new(
+            "txn",
+            StructType::new(vec![StructField::new("appId", DataType::STRING, true)]),
+            true,
+        ),
+        StructField::new(
+            "domainMetadata",
+            StructType::new(vec![
+                StructField::new("domain", DataType::STRING, true),
+                StructField::new("removed", DataType::BOOLEAN, true),
+            ]),
+            true,
+        ),
+    ]));
+}
+
+/// The fields of a deletion vector that make up its unique id.
+fn deletion_vector_id_field() -> StructField {
+    StructField::new(
+        "deletionVector",
+        StructType::new(vec![
+            StructField::new("storageType", DataType::STRING, true),
+            StructField::new("pathOrInlineDv", DataType::STRING, true),
+            StructField::new("offset", DataType::INTEGER, true),
+        ]),
+        true,
+    )
+}
+
+/// Write a single-part checkpoint for the version of `snapshot`, and point `_last_checkpoint` at
+/// it unless that already refers to a more recent checkpoint.
+pub(crate) fn write_checkpoint(
+    snapshot: &Snapshot,
+    engine_interface: &dyn EngineInterface,
+) -> DeltaResult<()> {
+    let log_root = &snapshot.log_segment.log_root;
+    let retention = snapshot
+        .table_properties()
+        .deleted_file_retention_duration()?
+        .unwrap_or(DEFAULT_DELETED_FILE_RETENTION_DURATION);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(Error::generic_err)?;
+    let mut visitor = CheckpointVisitor::new(now.saturating_sub(retention).as_millis() as i64);
+
+    let schema: SchemaRef = Arc::new(CHECKPOINT_SCHEMA.clone());
+    let actions =
+        snapshot
+            .log_segment
+            .replay(engine_interface, schema.clone(), schema.clone(), None)?;
+    let data = actions.map(|actions| {
+        let (actions, _) = actions?;
+        actions.extract(SELECTION_SCHEMA.clone(), &mut visitor)?;
+        Ok((actions, std::mem::take(&mut visitor.selection_vector)))
+    });
+    let location =
+        LogPath(log_root).child(format!("{:020}.checkpoint.parquet", snapshot.version()))?;
+    debug!("Writing checkpoint {}", location);
+    let checkpoint_file = engine_interface.get_parquet_handler().write_parquet_file(
+        location,
+        schema,
+        Box::new(data),
+    )?;
+
+    let fs_client = engine_interface.get_file_system_client();
+    if let Some(last_checkpoint) = read_last_checkpoint(fs_client.as_ref(), log_root)? {
+        if last_checkpoint.version > snapshot.version() {
+            return Ok(());
+        }
+    }
+    let mut last_checkpoint = CheckpointMetadata {
+        version: snapshot.version(),
+        size: visitor.num_actions,
+        parts: None,
+        size_in_bytes: Some(checkpoint_file.size as i64),
+        num_of_add_files: Some(visitor.num_add_files),
+        checkpoint_schema: None,
+        checksum: None,
+    };
+    last_checkpoint.checksum = Some(checksum(&serde_json::to_value(&last_checkpoint)?));
+    fs_client.write_file(
+        &LogPath(log_root).child(LAST_CHECKPOINT_FILE_NAME)?,
+        serde_json::to_vec(&last_checkpoint)?.into(),
+        true,
+    )
+}
+
+/// Selects the actions that belong in a checkpoint from batches of actions, which must be visited
+/// from the most recent to the oldest.
+///
+/// The checkpoint keeps the most recent protocol, metadata and transaction of each application,
+/// the most recent domain metadata of each domain unless it was removed, all files that are still
+/// part of the table, and the tombstones of files that were removed within the retention period.
+struct CheckpointVisitor {
+    /// (path, deletion vector unique id) of all files seen so far
+    seen_files: HashSet<(String, Option<String>)>,
+    seen_txns: HashSet<String>,
+    seen_domains: HashSet<String>,
+    seen_protocol: bool,
+    seen_metadata: bool,
+    /// Tombstones of files removed before this timestamp have expired
+    minimum_file_retention_timestamp: i64,
+    /// Whether each of the rows visited since this was last taken belongs in the checkpoint
+    selection_vector: Vec<bool>,
+    num_actions: i64,
+    num_add_files: i64,
+}
+
+impl CheckpointVisitor {
+    fn new(minimum_file_retention_timestamp: i64) -> Self {
+        Self {
+            seen_files: Default::default(),
+            seen_txns: Default::default(),
+            seen_domains: Default::default(),
+            seen_protocol: false,
+            seen_metadata: false,
+            minimum_file_retention_timestamp,
+            selection_vector: vec![],
+            num_actions: 0,
+            num_add_files: 0,
+        }
+    }
+
+    fn visit_deletion_vector_id<'a>(
+        row_index: usize,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<Option<String>> {
+        let storage_type: Option<String> =
+            getters[0].get_opt(row_index, "deletionVector.storageType")?;
+        let Some(storage_type) = storage_type else {
+            return Ok(None);
+        };
+        let path_or_inline_dv: String =
+            getters[1].get(row_index, "deletionVector.pathOrInlineDv")?;
+        let offset: Option<i32> = getters[2].get_opt(row_index, "deletionVector.offset")?;
+        Ok(Some(DeletionVectorDescriptor::unique_id_from_parts(
+            &storage_type,
+            &path_or_inline_dv,
+            offset,
+        )))
+    }
+
+    fn is_selected<'a>(
+        &mut self,
+        row_index: usize,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<bool> {
+        if let Some(path) = getters[0].get_opt(row_index, "add.path")? {
+            let dv_id = Self::visit_deletion_vector_id(row_index, &getters[1..4])?;
+            let selected = self.seen_files.insert((path, dv_id));
+            if selected {
+                self.num_add_files += 1;
+            }
+            return Ok(selected);
+        }
+        if let Some(path) = getters[4].get_opt(row_index, "remove.path")? {
+            let deletion_timestamp: Option<i64> =
+                getters[5].get_opt(row_index, "remove.deletionTimestamp")?;
+            let dv_id = Self::visit_deletion_vector_id(row_index, &getters[6..9])?;
+            // the tombstone must shadow older actions for the file, even if it has expired
+            let selected = self.seen_files.insert((path, dv_id))
+                && deletion_timestamp.unwrap_or(0) > self.minimum_file_retention_timestamp;
+            return Ok(selected);
+        }
+        let metadata_id: Option<String> = getters[9].get_opt(row_index, "metaData.id")?;
+        if metadata_id.is_some() {
+            return Ok(!std::mem::replace(&mut self.seen_metadata, true));
+        }
+        let min_reader_version: Option<i32> =
+            getters[10].get_opt(row_index, "protocol.minReaderVersion")?;
+        if min_reader_version.is_some() {
+            return Ok(!std::mem::replace(&mut self.seen_protocol, true));
+        }
+        if let Some(app_id) = getters[11].get_opt(row_index, "txn.appId")? {
+            return Ok(self.seen_txns.insert(app_id));
+        }
+        if let Some(domain) = getters[12].get_opt(row_index, "domainMetadata.domain")? {
+            let removed: Option<bool> = getters[13].get_opt(row_index, "domainMetadata.removed")?;
+            return Ok(self.seen_domains.insert(domain) && !removed.unwrap_or(false));
+        }
+        Ok(false)
+    }
+}
+
+impl DataVisitor for CheckpointVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            let selected = self.is_selected(i, getters)?;
+            if selected {
+                self.num_actions += 1;
+            }
+            self.selection_vector.push(selected);
+        }
+        Ok(())
+    }
+}
+
+/// Compute the checksum of the `_last_checkpoint` json, as specified by the Delta protocol.
+///
+/// The checksum is the hex encoded MD5 digest of the canonical form of the json (without the
+/// checksum itself), which lists all leaf values along with their path, sorted by path. Keys and
+/// string values are url encoded and quoted, array elements are identified by their index.
+fn checksum(value: &serde_json::Value) -> String {
+    fn flatten(value: &serde_json::Value, path: Option<String>, pairs: &mut Vec<(String, String)>) {
+        let child_path = |key: String| match &path {
+            Some(path) => format!("{path}+{key}"),
+            None => key,
+        };
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    if path.is_none() && key == "checksum" {
+                        continue;
+                    }
+                    flatten(value, Some(child_path(quote(key))), pairs);
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    flatten(value, Some(child_path(index.to_string())), pairs);
+                }
+            }
+            serde_json::Value::String(string) => {
+                pairs.push((path.unwrap_or_default(), quote(string)));
+            }
+            value => pairs.push((path.unwrap_or_default(), value.to_string())),
+        }
+    }
+
+    /// Url encode `value` like java's `URLEncoder`, but with spaces encoded as `%20`, and quote it.
+    fn quote(value: &str) -> String {
+        let encoded: String = value
+            .bytes()
+            .map(|byte| match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'*' | b'_' => {
+                    (byte as char).to_string()
+                }
+                byte => format!("%{byte:02X}"),
+            })
+            .collect();
+        format!("\"{encoded}\"")
+    }
+
+    let mut pairs = vec![];
+    flatten(value, None, &mut pairs);
+    pairs.sort();
+    let canonical = pairs
+        .into_iter()
+        .map(|(path, value)| format!("{path}={value}"))
+        .join(",");
+    format!("{:x}", Md5::digest(canonical.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use url::Url;
+
+    use super::*;
+    use crate::client::DefaultTableClient;
+    use crate::executor::tokio::TokioBackgroundExecutor;
+    use crate::scan::ScanBuilder;
+    use crate::simple_client::SimpleClient;
+    use crate::test_utils::{self, add, write_commit, METADATA, PROTOCOL};
+
+    fn remove(path: &str, deletion_timestamp: i64) -> String {
+        format!(
+            r#"{{"remove":{{"path":"{path}","deletionTimestamp":{deletion_timestamp},"dataChange":true,"partitionValues":{{}}}}}}"#
+        )
+    }
+
+    fn txn(app_id: &str, version: i64) -> String {
+        format!(r#"{{"txn":{{"appId":"{app_id}","version":{version}}}}}"#)
+    }
+
+    fn write_test_table(table_root: &Path) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        test_utils::write_test_table(
+            table_root,
+            &[
+                PROTOCOL.into(),
+                METADATA.into(),
+                add("a"),
+                add("b"),
+                txn("app", 1),
+            ],
+        );
+        write_commit(table_root, 1, &[remove("a", now), txn("app", 2)]);
+        write_commit(
+            table_root,
+            2,
+            &[
+                // this tombstone has expired, so it must not be part of the checkpoint
+                remove("b", 0),
+                add("c"),
+                r#"{"domainMetadata":{"domain":"d","configuration":{},"removed":false}}"#.into(),
+            ],
+        );
+    }
+
+    fn test_checkpoint(engine_interface: &dyn EngineInterface, table_root: &Path) {
+        let location = Url::from_directory_path(table_root).unwrap();
+        let snapshot = Snapshot::try_new(location.clone(), engine_interface, None).unwrap();
+        snapshot.checkpoint(engine_interface).unwrap();
+
+        let last_checkpoint =
+            std::fs::read(table_root.join("_delta_log/_last_checkpoint")).unwrap();
+        let last_checkpoint: CheckpointMetadata = serde_json::from_slice(&last_checkpoint).unwrap();
+        assert_eq!(last_checkpoint.version, 2);
+        // protocol, metadata, txn, domain metadata, add c and remove a
+        assert_eq!(last_checkpoint.size, 6);
+        assert_eq!(last_checkpoint.num_of_add_files, Some(1));
+        assert_eq!(last_checkpoint.parts, None);
+        let checkpoint_size = std::fs::metadata(
+            table_root.join("_delta_log/00000000000000000002.checkpoint.parquet"),
+        )
+        .unwrap()
+        .len();
+        assert_eq!(last_checkpoint.size_in_bytes, Some(checkpoint_size as i64));
+        let checksum = last_checkpoint.checksum.clone().unwrap();
+        assert_eq!(
+            super::checksum(&serde_json::to_value(&last_checkpoint).unwrap()),
+            checksum
+        );
+
+        // reading the table no longer needs the commits
+        for version in 0..=2 {
+            std::fs::remove_file(table_root.join(format!("_delta_log/{version:020}.json")))
+                .unwrap();
+        }
+        let snapshot = Snapshot::try_new(location, engine_interface, None).unwrap();
+        assert_eq!(snapshot.version(), 2);
+        assert_eq!(
+            snapshot.metadata().id,
+            "5fba94ed-9794-4965-ba6e-6ee3c0d22af9"
+        );
+        let paths: Vec<_> = ScanBuilder::new(snapshot)
+            .build()
+            .files(engine_interface)
+            .unwrap()
+            .map(|add| add.unwrap().path)
+            .collect();
+        assert_eq!(paths, vec!["c"]);
+    }
+
+    #[test]
+    fn test_write_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_table(dir.path());
+        test_checkpoint(&SimpleClient::new(), dir.path());
+    }
+
+    #[test]
+    fn test_write_checkpoint_invalid_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = METADATA.replace(
+            r#""configuration":{}"#,
+            r#""configuration":{"delta.deletedFileRetentionDuration":"7 days"}"#,
+        );
+        let location = test_utils::write_test_table(dir.path(), &[PROTOCOL.into(), metadata]);
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        assert!(matches!(
+            snapshot.checkpoint(&engine_interface),
+            Err(Error::Generic(_))
+        ));
+        assert!(!dir
+            .path()
+            .join("_delta_log/00000000000000000000.checkpoint.parquet")
+            .exists());
+    }
+
+    #[test]
+    fn test_write_checkpoint_default_client() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_table(dir.path());
+        let location = Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = DefaultTableClient::try_new(
+            &location,
+            std::iter::empty::<(&str, &str)>(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+        .unwrap();
+        test_checkpoint(&engine_interface, dir.path());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let value = serde_json::json!({
+            "k0": "'v 0'",
+            "checksum": "adsaskfljadfkjadfkj",
+            "k1": {"k2": 2, "k3": ["v3", [1, 2], {"k4": "v4", "k5": ["v5", "v6", "v7"]}]}
+        });
+        let canonical = r#""k0"="%27v%200%27","k1"+"k2"=2,"k1"+"k3"+0="v3","k1"+"k3"+1+0=1,"k1"+"k3"+1+1=2,"k1"+"k3"+2+"k4"="v4","k1"+"k3"+2+"k5"+0="v5","k1"+"k3"+2+"k5"+1="v6","k1"+"k3"+2+"k5"+2="v7""#;
+        assert_eq!(
+            checksum(&value),
+            format!("{:x}", Md5::digest(canonical.as_bytes()))
+        );
+    }
+}
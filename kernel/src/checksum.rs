@@ -0,0 +1,234 @@
+//! Version checksum files, which summarize the state of a table at a version.
+//!
+//! Writers may store a `NNNNN.crc` file next to the commit of a version, holding the protocol,
+//! metadata, size and number of files of the table at that version. When the checksum of a
+//! snapshot's version exists, the protocol and metadata are read from it instead of replaying the
+//! log, and the other statistics are available without log replay.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{Metadata, Protocol};
+use crate::scan::ScanBuilder;
+use crate::snapshot::Snapshot;
+use crate::{DeltaResult, EngineInterface, Error, FileMeta, FileSystemClient};
+
+/// The contents of the version checksum file of a table version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChecksum {
+    /// The id of the transaction that committed the version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txn_id: Option<String>,
+    /// The total size of the files in the table, in bytes
+    pub table_size_bytes: i64,
+    /// The number of files in the table
+    pub num_files: i64,
+    /// The number of metadata actions, which must be 1
+    pub num_metadata: i64,
+    /// The number of protocol actions, which must be 1
+    pub num_protocol: i64,
+    /// The in-commit timestamp of the version, if in-commit timestamps are enabled
+    #[serde(
+        rename = "inCommitTimestampOpt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub in_commit_timestamp: Option<i64>,
+    /// The table metadata at the version
+    pub metadata: Metadata,
+    /// The table protocol at the version
+    pub protocol: Protocol,
+    /// The distribution of the sizes of the files in the table
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_size_histogram: Option<FileSizeHistogram>,
+    /// The number of rows deleted by the deletion vectors of the files in the table
+    #[serde(
+        rename = "numDeletedRecordsOpt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub num_deleted_records: Option<i64>,
+    /// The number of deletion vectors of the files in the table
+    #[serde(
+        rename = "numDeletionVectorsOpt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub num_deletion_vectors: Option<i64>,
+    /// The distribution of the number of rows deleted by the deletion vectors of the files
+    #[serde(
+        rename = "deletedRecordCountsHistogramOpt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deleted_record_counts_histogram: Option<DeletedRecordCountsHistogram>,
+}
+
+/// The number of files, and their total size, in each of a range of file size bins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSizeHistogram {
+    /// The inclusive lower bound of each bin in bytes, starting at 0
+    pub sorted_bin_boundaries: Vec<i64>,
+    /// The number of files in each bin
+    pub file_counts: Vec<i64>,
+    /// The total size in bytes of the files in each bin
+    pub total_bytes: Vec<i64>,
+}
+
+impl FileSizeHistogram {
+    /// The index of the bin holding files of the given size.
+    fn bin(&self, size: i64) -> usize {
+        self.sorted_bin_boundaries
+            .partition_point(|boundary| *boundary <= size)
+            .saturating_sub(1)
+    }
+}
+
+/// The number of files whose deletion vectors delete a number of rows in each of the bins `0`,
+/// `1-9`, `10-99`, `100-999`, `1000-9999`, `10000-99999`, `100000-999999`, `1000000-9999999`,
+/// `10000000-2147483646` and `2147483647` or more.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedRecordCountsHistogram {
+    /// The number of files in each bin
+    pub deleted_record_counts: Vec<i64>,
+}
+
+/// Read the version checksum in the given `.crc` file.
+pub(crate) fn read_version_checksum(
+    fs_client: &dyn FileSystemClient,
+    file: &FileMeta,
+) -> DeltaResult<VersionChecksum> {
+    let data = fs_client
+        .read_files(vec![(file.location.clone(), None)])?
+        .next()
+        .ok_or_else(|| Error::generic(format!("Failed to read {}", file.location)))??;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+impl Snapshot {
+    /// Check that the version checksum this snapshot was loaded with matches the state of the
+    /// table found by replaying the log, failing with [`Error::ChecksumMismatch`] if it does not.
+    /// Snapshots without a version checksum trivially pass.
+    ///
+    /// The protocol, metadata, number of files, table size and file size histogram are checked.
+    /// This replays the whole log, so it is only done when called explicitly.
+    pub fn verify_checksum(
+        self: &Arc<Self>,
+        engine_interface: &dyn EngineInterface,
+    ) -> DeltaResult<()> {
+        let Some(checksum) = self.checksum() else {
+            return Ok(());
+        };
+        let mismatch = |what: &str| Err(Error::ChecksumMismatch(self.version(), what.to_string()));
+
+        let (metadata, protocol) = self
+            .log_segment
+            .read_metadata(engine_interface)?
+            .ok_or(Error::MissingMetadata)?;
+        if metadata != checksum.metadata {
+            return mismatch("metadata");
+        }
+        if protocol != checksum.protocol {
+            return mismatch("protocol");
+        }
+
+        let mut num_files = 0;
+        let mut table_size_bytes = 0;
+        let mut histogram =
+            checksum
+                .file_size_histogram
+                .as_ref()
+                .map(|histogram| FileSizeHistogram {
+                    sorted_bin_boundaries: histogram.sorted_bin_boundaries.clone(),
+                    file_counts: vec![0; histogram.file_counts.len()],
+                    total_bytes: vec![0; histogram.total_bytes.len()],
+                });
+        let scan = ScanBuilder::new(self.clone()).build();
+        for add in scan.files(engine_interface)? {
+            let add = add?;
+            num_files += 1;
+            table_size_bytes += add.size;
+            if let Some(histogram) = histogram.as_mut() {
+                let bin = histogram.bin(add.size);
+                if let (Some(count), Some(bytes)) = (
+                    histogram.file_counts.get_mut(bin),
+                    histogram.total_bytes.get_mut(bin),
+                ) {
+                    *count += 1;
+                    *bytes += add.size;
+                }
+            }
+        }
+        if num_files != checksum.num_files {
+            return mismatch("numFiles");
+        }
+        if table_size_bytes != checksum.table_size_bytes {
+            return mismatch("tableSizeBytes");
+        }
+        if histogram != checksum.file_size_histogram {
+            return mismatch("fileSizeHistogram");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version_checksum() {
+        let crc = r#"{
+            "txnId": "d87e63fb-7388-4b1c-9afc-750a561012b7",
+            "tableSizeBytes": 1975,
+            "numFiles": 2,
+            "numMetadata": 1,
+            "numProtocol": 1,
+            "setTransactions": [],
+            "domainMetadata": [],
+            "metadata": {
+                "id": "testId",
+                "format": {"provider": "parquet", "options": {}},
+                "schemaString": "{\"type\":\"struct\",\"fields\":[]}",
+                "partitionColumns": [],
+                "configuration": {"delta.enableDeletionVectors": "true"},
+                "createdTime": 1677811175819
+            },
+            "protocol": {
+                "minReaderVersion": 3,
+                "minWriterVersion": 7,
+                "readerFeatures": ["deletionVectors"],
+                "writerFeatures": ["deletionVectors"]
+            },
+            "fileSizeHistogram": {
+                "sortedBinBoundaries": [0, 8192, 16384],
+                "fileCounts": [2, 0, 0],
+                "totalBytes": [1975, 0, 0]
+            },
+            "numDeletedRecordsOpt": 0,
+            "numDeletionVectorsOpt": 0
+        }"#;
+        let checksum: VersionChecksum = serde_json::from_str(crc).unwrap();
+        assert_eq!(checksum.table_size_bytes, 1975);
+        assert_eq!(checksum.num_files, 2);
+        assert_eq!(checksum.in_commit_timestamp, None);
+        assert_eq!(checksum.metadata.id, "testId");
+        assert_eq!(
+            checksum.metadata.configuration["delta.enableDeletionVectors"],
+            Some("true".to_string())
+        );
+        assert_eq!(checksum.protocol.min_reader_version, 3);
+        assert_eq!(
+            checksum.protocol.reader_features,
+            Some(vec!["deletionVectors".to_string()])
+        );
+        assert_eq!(checksum.num_deleted_records, Some(0));
+        assert_eq!(checksum.deleted_record_counts_histogram, None);
+
+        let histogram = checksum.file_size_histogram.unwrap();
+        assert_eq!(histogram.bin(0), 0);
+        assert_eq!(histogram.bin(8191), 0);
+        assert_eq!(histogram.bin(8192), 1);
+        assert_eq!(histogram.bin(1 << 20), 2);
+    }
+}
@@ -3,23 +3,28 @@
 //! Expression handling based on arrow-rs compute kernels.
 use std::sync::Arc;
 
-use arrow_arith::boolean::{and, is_null, not, or};
+use arrow_arith::boolean::{and_kleene, is_null, not, or_kleene};
 use arrow_arith::numeric::{add, div, mul, sub};
 use arrow_array::cast::AsArray;
 use arrow_array::{
     Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Datum, Decimal128Array, Float32Array,
-    Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, RecordBatch, StringArray,
-    StructArray, TimestampMicrosecondArray,
+    Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, ListArray, MapArray, RecordBatch,
+    StringArray, StructArray, TimestampMicrosecondArray,
 };
+use arrow_cast::cast::{cast_with_options, CastOptions};
 use arrow_ord::cmp::{distinct, eq, gt, gt_eq, lt, lt_eq, neq};
 use arrow_schema::{
     ArrowError, DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema,
 };
+use arrow_string::like::starts_with;
 use itertools::Itertools;
 
 use crate::error::{DeltaResult, Error};
-use crate::expressions::{BinaryOperator, Expression, Scalar, UnaryOperator, VariadicOperator};
-use crate::schema::{DataType, PrimitiveType, SchemaRef};
+use crate::expressions::coercion::common_type;
+use crate::expressions::{
+    BinaryOperator, Expression, LikePattern, Scalar, UnaryOperator, VariadicOperator,
+};
+use crate::schema::{DataType, PrimitiveType, SchemaRef, StructField, StructType};
 use crate::simple_client::data::SimpleData;
 use crate::{EngineData, ExpressionEvaluator, ExpressionHandler};
 
@@ -80,6 +85,44 @@ impl Scalar {
     }
 }
 
+/// Cast `array` to `data_type`, failing on values which do not fit the type rather than making
+/// them null.
+fn cast_array(array: &ArrayRef, data_type: &ArrowDataType) -> DeltaResult<ArrayRef> {
+    if array.data_type() == data_type {
+        return Ok(array.clone());
+    }
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    Ok(cast_with_options(array, data_type, &options)?)
+}
+
+/// Implicitly cast two arrays to their common type if they differ in type. Arrays which cannot be
+/// combined are left as they are, for the operation on them to fail.
+fn coerce_arrays(left: ArrayRef, right: ArrayRef) -> DeltaResult<(ArrayRef, ArrayRef)> {
+    if left.data_type() == right.data_type() {
+        return Ok((left, right));
+    }
+    let common = match (
+        DataType::try_from(left.data_type()),
+        DataType::try_from(right.data_type()),
+    ) {
+        (Ok(left_type), Ok(right_type)) => common_type(&left_type, &right_type),
+        _ => None,
+    };
+    match common {
+        Some(common) => {
+            let arrow_type = ArrowDataType::try_from(&common)?;
+            Ok((
+                cast_array(&left, &arrow_type)?,
+                cast_array(&right, &arrow_type)?,
+            ))
+        }
+        None => Ok((left, right)),
+    }
+}
+
 fn wrap_comparison_result(arr: BooleanArray) -> ArrayRef {
     Arc::new(arr) as Arc<dyn Array>
 }
@@ -135,6 +178,97 @@ fn column_as_struct<'a>(
         .ok_or(ArrowError::SchemaError(format!("{} is not a struct", name)))
 }
 
+/// Rename the nested fields of `array` to the names of the corresponding fields in `data_type`.
+///
+/// This is used to produce data with the logical (nested) field names of a table from data read
+/// with physical names. Only names are changed, types and nullability are retained.
+fn apply_field_names(array: ArrayRef, data_type: &DataType) -> DeltaResult<ArrayRef> {
+    match data_type {
+        DataType::Primitive(_) => Ok(array),
+        DataType::Struct(schema) => {
+            let (fields, columns, nulls) = array
+                .as_struct_opt()
+                .ok_or(Error::unexpected_column_type("Expected a struct array"))?
+                .clone()
+                .into_parts();
+            if fields.len() != schema.fields().count() {
+                return Err(Error::generic(format!(
+                    "Expected a struct with {} fields, got {}",
+                    schema.fields().count(),
+                    fields.len()
+                )));
+            }
+            let (fields, columns): (Vec<_>, Vec<_>) = fields
+                .iter()
+                .zip(columns)
+                .zip(schema.fields())
+                .map(|((arrow_field, column), field)| {
+                    let column = apply_field_names(column, field.data_type())?;
+                    let arrow_field = ArrowField::new(
+                        field.name(),
+                        column.data_type().clone(),
+                        arrow_field.is_nullable(),
+                    )
+                    .with_metadata(arrow_field.metadata().clone());
+                    Ok::<_, Error>((arrow_field, column))
+                })
+                .process_results(|iter| iter.unzip())?;
+            Ok(Arc::new(StructArray::try_new(
+                fields.into(),
+                columns,
+                nulls,
+            )?))
+        }
+        DataType::Array(array_type) => {
+            let (field, offsets, values, nulls) = array
+                .as_list_opt::<i32>()
+                .ok_or(Error::unexpected_column_type("Expected a list array"))?
+                .clone()
+                .into_parts();
+            let values = apply_field_names(values, array_type.element_type())?;
+            let field = ArrowField::new(
+                field.name(),
+                values.data_type().clone(),
+                field.is_nullable(),
+            );
+            Ok(Arc::new(ListArray::try_new(
+                Arc::new(field),
+                offsets,
+                values,
+                nulls,
+            )?))
+        }
+        DataType::Map(map_type) => {
+            let (field, offsets, entries, nulls, ordered) = array
+                .as_map_opt()
+                .ok_or(Error::unexpected_column_type("Expected a map array"))?
+                .clone()
+                .into_parts();
+            // keep the names of the entries struct, but rename within the keys and values
+            let entries_type = match entries.fields().iter().collect_tuple() {
+                Some((key, value)) => StructType::new(vec![
+                    StructField::new(key.name(), map_type.key_type().clone(), false),
+                    StructField::new(value.name(), map_type.value_type().clone(), true),
+                ]),
+                None => return Err(Error::generic("Expected map entries with a key and value")),
+            };
+            let entries = apply_field_names(Arc::new(entries), &entries_type.into())?;
+            let field = ArrowField::new(
+                field.name(),
+                entries.data_type().clone(),
+                field.is_nullable(),
+            );
+            Ok(Arc::new(MapArray::try_new(
+                Arc::new(field),
+                offsets,
+                entries.as_struct().clone(),
+                nulls,
+                ordered,
+            )?))
+        }
+    }
+}
+
 fn evaluate_expression(
     expression: &Expression,
     batch: &RecordBatch,
@@ -146,27 +280,36 @@ fn evaluate_expression(
     match (expression, result_type) {
         (Literal(scalar), _) => Ok(scalar.to_array(batch.num_rows())?),
         (Column(name), _) => {
-            // TODO properly handle nested columns
-            // https://github.com/delta-incubator/delta-kernel-rs/issues/86
-            if name.contains('.') {
-                let mut path = name.split('.');
-                // Safety: we know that the first path step exists, because we checked for '.'
-                Ok(extract_column(batch, path.next().unwrap(), &mut path).cloned()?)
-            } else {
-                batch
-                    .column_by_name(name)
-                    .ok_or(Error::missing_column(name))
-                    .cloned()
+            let mut path = name.path().iter().map(String::as_str);
+            let column = match path.next() {
+                Some(first) => extract_column(batch, first, &mut path)
+                    .map_err(|_| Error::missing_column(name))?
+                    .clone(),
+                None => return Err(Error::missing_column(name)),
+            };
+            // the column may have been read with different (i.e. physical) nested field names
+            match result_type {
+                Some(data_type) => apply_field_names(column, data_type),
+                None => Ok(column),
             }
         }
         (Struct(fields), Some(DataType::Struct(schema))) => {
-            let output_schema: ArrowSchema = schema.as_ref().try_into()?;
-            let columns = fields
+            let (output_fields, columns): (Vec<_>, Vec<_>) = fields
                 .iter()
                 .zip(schema.fields())
-                .map(|(expr, field)| evaluate_expression(expr, batch, Some(field.data_type())));
-            let result =
-                StructArray::try_new(output_schema.fields().clone(), columns.try_collect()?, None)?;
+                .map(|(expr, field)| {
+                    let column = evaluate_expression(expr, batch, Some(field.data_type()))?;
+                    // take the type from the column, as the names of nested list and map fields
+                    // depend on the writer of the data
+                    let output_field = ArrowField::new(
+                        field.name(),
+                        column.data_type().clone(),
+                        field.is_nullable(),
+                    );
+                    Ok::<_, Error>((output_field, column))
+                })
+                .process_results(|iter| iter.unzip())?;
+            let result = StructArray::try_new(output_fields.into(), columns, None)?;
             Ok(Arc::new(result))
         }
         (Struct(_), _) => Err(Error::generic(
@@ -182,6 +325,7 @@ fn evaluate_expression(
         (BinaryOperation { op, left, right }, _) => {
             let left_arr = evaluate_expression(left.as_ref(), batch, None)?;
             let right_arr = evaluate_expression(right.as_ref(), batch, None)?;
+            let (left_arr, right_arr) = coerce_arrays(left_arr, right_arr)?;
 
             type Operation = fn(&dyn Datum, &dyn Datum) -> Result<Arc<dyn Array>, ArrowError>;
             let eval: Operation = match op {
@@ -202,9 +346,10 @@ fn evaluate_expression(
         }
         (VariadicOperation { op, exprs }, None | Some(&DataType::BOOLEAN)) => {
             type Operation = fn(&BooleanArray, &BooleanArray) -> Result<BooleanArray, ArrowError>;
+            // null is unknown, so e.g. `false AND null` is false
             let (reducer, default): (Operation, _) = match op {
-                VariadicOperator::And => (and, true),
-                VariadicOperator::Or => (or, false),
+                VariadicOperator::And => (and_kleene, true),
+                VariadicOperator::Or => (or_kleene, false),
             };
             exprs
                 .iter()
@@ -223,6 +368,57 @@ fn evaluate_expression(
                 "Variadic {expression:?} is expected to return boolean results, got {result_type:?}"
             )))
         }
+        (
+            InList {
+                expr,
+                list,
+                negated,
+            },
+            _,
+        ) => {
+            let arr = evaluate_expression(expr.as_ref(), batch, None)?;
+            // null is unknown, so e.g. `1 IN (2, null)` is null
+            let any_equal = list
+                .iter()
+                .map(|value| {
+                    let value_arr = evaluate_expression(value, batch, None)?;
+                    let (arr, value_arr) = coerce_arrays(arr.clone(), value_arr)?;
+                    Ok::<_, Error>(eq(&arr, &value_arr)?)
+                })
+                .reduce(|l, r| Ok(or_kleene(&l?, &r?)?))
+                .unwrap_or_else(|| Ok(BooleanArray::from(vec![false; batch.num_rows()])))?;
+            if *negated {
+                Ok(Arc::new(not(&any_equal)?))
+            } else {
+                Ok(Arc::new(any_equal))
+            }
+        }
+        (
+            Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            },
+            _,
+        ) => {
+            let arr = evaluate_expression(expr.as_ref(), batch, None)?;
+            let strings = arr
+                .as_string_opt::<i32>()
+                .ok_or(Error::unexpected_column_type("Expected a string array"))?;
+            let like = LikePattern::try_new(pattern, *escape)?;
+            let result = BooleanArray::from_unary(strings, |value| like.matches(value) != *negated);
+            Ok(Arc::new(result))
+        }
+        (StartsWith { expr, prefix }, _) => {
+            let arr = evaluate_expression(expr.as_ref(), batch, None)?;
+            let prefix_arr = evaluate_expression(prefix.as_ref(), batch, None)?;
+            Ok(Arc::new(starts_with(&arr, &prefix_arr)?))
+        }
+        (Cast { expr, data_type }, _) => {
+            let arr = evaluate_expression(expr.as_ref(), batch, None)?;
+            cast_array(&arr, &ArrowDataType::try_from(data_type)?)
+        }
     }
 }
 
@@ -320,6 +516,15 @@ mod tests {
         let column = Expression::column("b.a");
         let results = evaluate_expression(&column, &batch, None).unwrap();
         assert_eq!(results.as_ref(), &values);
+
+        // a field name containing a dot is not a nested path
+        let schema = Schema::new(vec![Field::new("b.a", DataType::Int32, false)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values.clone())]).unwrap();
+        let column = Expression::column("`b.a`");
+        let results = evaluate_expression(&column, &batch, None).unwrap();
+        assert_eq!(results.as_ref(), &values);
+        let column = Expression::column("b.a");
+        assert!(evaluate_expression(&column, &batch, None).is_err());
     }
 
     #[test]
@@ -344,13 +549,66 @@ mod tests {
         let expected = Arc::new(Int32Array::from(vec![2, 4, 6]));
         assert_eq!(results.as_ref(), expected.as_ref());
 
-        // TODO handle type casting
-        let expression = Box::new(column.div(Expression::Literal(Scalar::Integer(1))));
+        let expression = Box::new(column.clone().div(Expression::Literal(Scalar::Integer(1))));
         let results = evaluate_expression(&expression, &batch, None).unwrap();
         let expected = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+
+        // the column is widened to the type of the literal
+        let expression = Box::new(column.clone().add(Expression::Literal(Scalar::Long(1))));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Arc::new(Int64Array::from(vec![2, 3, 4]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+
+        let expression = Box::new(column.gt(Expression::Literal(Scalar::Double(1.5))));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Arc::new(BooleanArray::from(vec![false, true, true]));
         assert_eq!(results.as_ref(), expected.as_ref())
     }
 
+    #[test]
+    fn test_cast() {
+        use crate::schema::DataType;
+
+        let schema = Schema::new(vec![
+            Field::new("a", ArrowDataType::Int32, true),
+            Field::new("d", ArrowDataType::Date32, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(300), None])),
+                Arc::new(Date32Array::from(vec![Some(0), Some(1), None])),
+            ],
+        )
+        .unwrap();
+        let column_a = Expression::column("a");
+        let column_d = Expression::column("d");
+
+        let expression = column_a.clone().cast(DataType::LONG);
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Arc::new(Int64Array::from(vec![Some(1), Some(300), None]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+
+        let expression = column_a
+            .clone()
+            .cast(DataType::decimal(5, 2))
+            .gt(Expression::literal(Scalar::Decimal(150, 3, 2)));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Arc::new(BooleanArray::from(vec![Some(false), Some(true), None]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+
+        // dates are widened to timestamps
+        let expression = column_d.gt_eq(Expression::literal(Scalar::Timestamp(1)));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Arc::new(BooleanArray::from(vec![Some(false), Some(true), None]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+
+        // values which do not fit the type are an error rather than null
+        let expression = column_a.cast(DataType::BYTE);
+        assert!(evaluate_expression(&expression, &batch, None).is_err());
+    }
+
     #[test]
     fn test_binary_op() {
         let schema = Schema::new(vec![
@@ -469,5 +727,158 @@ mod tests {
                 .unwrap();
         let expected = Arc::new(BooleanArray::from(vec![true, false]));
         assert_eq!(results.as_ref(), expected.as_ref());
+
+        let null = Expression::literal(Scalar::Null(crate::schema::DataType::BOOLEAN));
+        let expression = Box::new(column_a.clone().and(null.clone()));
+        let results =
+            evaluate_expression(&expression, &batch, Some(&crate::schema::DataType::BOOLEAN))
+                .unwrap();
+        let expected = Arc::new(BooleanArray::from(vec![None, Some(false)]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+
+        let expression = Box::new(column_a.or(null));
+        let results =
+            evaluate_expression(&expression, &batch, Some(&crate::schema::DataType::BOOLEAN))
+                .unwrap();
+        let expected = Arc::new(BooleanArray::from(vec![Some(true), None]));
+        assert_eq!(results.as_ref(), expected.as_ref());
+    }
+
+    #[test]
+    fn test_in_list_and_like() {
+        let schema = Schema::new(vec![
+            Field::new("n", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), None])),
+                Arc::new(StringArray::from(vec![Some("abc"), Some("a%c"), None])),
+            ],
+        )
+        .unwrap();
+        let column_n = Expression::column("n");
+        let column_s = Expression::column("s");
+        let null = Expression::literal(Scalar::Null(crate::schema::DataType::INTEGER));
+
+        let cases = [
+            (
+                column_n
+                    .clone()
+                    .in_list([Expression::literal(1), Expression::literal(3)]),
+                vec![Some(true), Some(false), None],
+            ),
+            (
+                column_n
+                    .clone()
+                    .in_list([Expression::literal(1), null.clone()]),
+                vec![Some(true), None, None],
+            ),
+            (
+                column_n.clone().not_in_list([Expression::literal(1)]),
+                vec![Some(false), Some(true), None],
+            ),
+            (
+                column_n.clone().in_list([
+                    Expression::literal(2i64),
+                    Expression::literal(Scalar::Double(3.5)),
+                ]),
+                vec![Some(false), Some(true), None],
+            ),
+            (
+                column_n.in_list([]),
+                vec![Some(false), Some(false), Some(false)],
+            ),
+            (
+                column_s.clone().like("a_c"),
+                vec![Some(true), Some(true), None],
+            ),
+            (
+                column_s.clone().like_with_escape("a\\%%", '\\'),
+                vec![Some(false), Some(true), None],
+            ),
+            (
+                column_s.clone().not_like("%b%"),
+                vec![Some(false), Some(true), None],
+            ),
+            (
+                column_s.starts_with(Expression::literal("ab")),
+                vec![Some(true), Some(false), None],
+            ),
+        ];
+        for (expression, expected) in cases {
+            let results =
+                evaluate_expression(&expression, &batch, Some(&crate::schema::DataType::BOOLEAN))
+                    .unwrap();
+            let expected = Arc::new(BooleanArray::from(expected));
+            assert_eq!(results.as_ref(), expected.as_ref(), "{expression}");
+        }
+    }
+
+    #[test]
+    fn test_column_with_renamed_nested_fields() {
+        use crate::schema::{ArrayType, DataType, MapType, StructField, StructType};
+        use arrow::buffer::OffsetBuffer;
+        use arrow_array::builder::{Int32Builder, MapBuilder, StringBuilder};
+
+        // struct<col-1: int>
+        let physical_struct = StructArray::from(vec![(
+            Arc::new(Field::new("col-1", arrow_schema::DataType::Int32, true)),
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )]);
+        // array<struct<col-1: int>>, with the rows [[1, 2], [3]]
+        let physical_list = ListArray::new(
+            Arc::new(Field::new(
+                "element",
+                physical_struct.data_type().clone(),
+                true,
+            )),
+            OffsetBuffer::from_lengths([2, 1]),
+            Arc::new(physical_struct.clone()),
+            None,
+        );
+        // map<string, int>
+        let mut map_builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        map_builder.keys().append_value("k");
+        map_builder.values().append_value(1);
+        map_builder.append(true).unwrap();
+        map_builder.append(true).unwrap();
+        let physical_map = map_builder.finish();
+
+        let schema = Schema::new(vec![
+            Field::new("list", physical_list.data_type().clone(), true),
+            Field::new("map", physical_map.data_type().clone(), true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![Arc::new(physical_list), Arc::new(physical_map.clone())],
+        )
+        .unwrap();
+
+        let logical_struct = StructType::new(vec![StructField::new("a", DataType::INTEGER, true)]);
+        let output_type = StructType::new(vec![
+            StructField::new(
+                "list",
+                ArrayType::new(logical_struct.clone().into(), true),
+                true,
+            ),
+            StructField::new(
+                "map",
+                MapType::new(DataType::STRING, DataType::INTEGER, true),
+                true,
+            ),
+        ]);
+        let expression =
+            Expression::Struct(vec![Expression::column("list"), Expression::column("map")]);
+        let results = evaluate_expression(&expression, &batch, Some(&output_type.into())).unwrap();
+        let results = results.as_struct();
+
+        let list = results.column(0).as_list::<i32>();
+        assert_eq!(list.value_offsets(), &[0, 2, 3]);
+        let values = list.values().as_struct();
+        assert_eq!(values.column_names(), vec!["a"]);
+        assert_eq!(values.column(0).as_ref(), &Int32Array::from(vec![1, 2, 3]));
+        assert_eq!(results.column(1).as_ref(), &physical_map);
     }
 }
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use bytes::Bytes;
 use futures::stream::StreamExt;
 use object_store::path::Path;
-use object_store::DynObjectStore;
+use object_store::{DynObjectStore, PutMode};
 use url::Url;
 
 use crate::{executor::TaskExecutor, DeltaResult, Error, FileMeta, FileSlice, FileSystemClient};
@@ -59,7 +59,7 @@ impl<E: TaskExecutor> FileSystemClient for ObjectStoreFileSystemClient<E> {
                         sender
                             .send(Ok(FileMeta {
                                 location,
-                                last_modified: meta.last_modified.timestamp(),
+                                last_modified: meta.last_modified.timestamp_millis(),
                                 size: meta.size,
                             }))
                             .ok();
@@ -126,6 +126,26 @@ impl<E: TaskExecutor> FileSystemClient for ObjectStoreFileSystemClient<E> {
 
         Ok(Box::new(receiver.into_iter()))
     }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        let path = if path.scheme() == "file" {
+            let file_path = path
+                .to_file_path()
+                .map_err(|_| Error::generic(format!("Invalid file path: {path}")))?;
+            Path::from_absolute_path(file_path).map_err(Error::generic_err)?
+        } else {
+            Path::from(path.path())
+        };
+        let mode = if overwrite {
+            PutMode::Overwrite
+        } else {
+            PutMode::Create
+        };
+        let store = self.inner.clone();
+        self.task_executor
+            .block_on(async move { store.put_opts(&path, data, mode.into()).await })?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -177,4 +197,30 @@ mod tests {
         assert_eq!(data[1], Bytes::from("data"));
         assert_eq!(data[2], Bytes::from("el-da"));
     }
+
+    #[test]
+    fn test_write_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let url = Url::from_directory_path(tmp.path()).unwrap();
+        let client = ObjectStoreFileSystemClient::new(
+            Arc::new(LocalFileSystem::new()),
+            Path::from(url.path()),
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+        let file = url.join("a").unwrap();
+
+        client.write_file(&file, "a".into(), false).unwrap();
+        assert!(matches!(
+            client.write_file(&file, "b".into(), false),
+            Err(Error::FileAlreadyExists(_))
+        ));
+        client.write_file(&file, "c".into(), true).unwrap();
+
+        let data: Vec<Bytes> = client
+            .read_files(vec![(file, None)])
+            .unwrap()
+            .try_collect()
+            .unwrap();
+        assert_eq!(data, vec![Bytes::from("c")]);
+    }
 }
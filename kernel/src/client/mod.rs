@@ -82,6 +82,19 @@ impl<E: TaskExecutor> DefaultTableClient<E> {
             expression: Arc::new(DefaultExpressionHandler {}),
         }
     }
+
+    /// Set the number of data files to read at the same time when scanning a table with
+    /// [`Scan::execute_iter`]. Each file buffers up to 10 batches while it waits to be consumed,
+    /// so this bounds the memory used by reading ahead.
+    ///
+    /// Defaults to 2.
+    ///
+    /// [`Scan::execute_iter`]: crate::scan::Scan::execute_iter
+    pub fn with_file_concurrency(mut self, file_concurrency: usize) -> Self {
+        let parquet = self.parquet.as_ref().clone();
+        self.parquet = Arc::new(parquet.with_file_concurrency(file_concurrency));
+        self
+    }
 }
 
 impl<E: TaskExecutor> DefaultTableClient<E> {
@@ -9,19 +9,26 @@ use object_store::path::Path;
 use object_store::DynObjectStore;
 use parquet::arrow::arrow_reader::ArrowReaderOptions;
 use parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStreamBuilder};
+use url::Url;
 
 use super::file_handler::{FileOpenFuture, FileOpener};
 use crate::executor::TaskExecutor;
 use crate::file_handler::FileStream;
+use crate::parquet_utils::skipping::skip_row_groups_and_pages;
+use crate::parquet_utils::{projection_mask, reorder_columns, write_parquet};
 use crate::schema::SchemaRef;
 use crate::simple_client::data::SimpleData;
-use crate::{DeltaResult, Error, Expression, FileDataReadResultIterator, FileMeta, ParquetHandler};
+use crate::{
+    DeltaResult, Error, Expression, FileDataReadResultIterator, FileMeta, FilteredEngineData,
+    ParquetHandler,
+};
 
 #[derive(Debug)]
 pub struct DefaultParquetHandler<E: TaskExecutor> {
     store: Arc<DynObjectStore>,
     task_executor: Arc<E>,
     readahead: usize,
+    file_concurrency: usize,
 }
 
 impl<E: TaskExecutor> DefaultParquetHandler<E> {
@@ -30,6 +37,7 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
             store,
             task_executor,
             readahead: 10,
+            file_concurrency: 2,
         }
     }
 
@@ -40,6 +48,27 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         self.readahead = readahead;
         self
     }
+
+    /// Number of files the kernel may read at the same time, each with up to `readahead`
+    /// batches buffered. See [ParquetHandler::file_concurrency()].
+    ///
+    /// Defaults to 2.
+    pub fn with_file_concurrency(mut self, file_concurrency: usize) -> Self {
+        self.file_concurrency = file_concurrency.max(1);
+        self
+    }
+}
+
+// Not derived, since that would require the executor to be `Clone`
+impl<E: TaskExecutor> Clone for DefaultParquetHandler<E> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            task_executor: self.task_executor.clone(),
+            readahead: self.readahead,
+            file_concurrency: self.file_concurrency,
+        }
+    }
 }
 
 impl<E: TaskExecutor> ParquetHandler for DefaultParquetHandler<E> {
@@ -47,15 +76,14 @@ impl<E: TaskExecutor> ParquetHandler for DefaultParquetHandler<E> {
         &self,
         files: &[FileMeta],
         physical_schema: SchemaRef,
-        _predicate: Option<Expression>,
+        predicate: Option<Expression>,
     ) -> DeltaResult<FileDataReadResultIterator> {
-        // TODO at the very least load only required columns ...
         if files.is_empty() {
             return Ok(Box::new(std::iter::empty()));
         }
 
         let schema: ArrowSchemaRef = Arc::new(physical_schema.as_ref().try_into()?);
-        let file_reader = ParquetOpener::new(1024, schema.clone(), self.store.clone());
+        let file_reader = ParquetOpener::new(1024, schema.clone(), predicate, self.store.clone());
         let mut stream = FileStream::new(files.to_vec(), schema, file_reader)?;
 
         // This channel will become the output iterator.
@@ -87,14 +115,43 @@ impl<E: TaskExecutor> ParquetHandler for DefaultParquetHandler<E> {
             rbr.map(|rb| Box::new(SimpleData::new(rb)) as _)
         })))
     }
+
+    fn file_concurrency(&self) -> usize {
+        self.file_concurrency
+    }
+
+    fn write_parquet_file(
+        &self,
+        location: Url,
+        schema: SchemaRef,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + '_>,
+    ) -> DeltaResult<FileMeta> {
+        let mut buffer = Vec::new();
+        write_parquet(&mut buffer, schema, data)?;
+
+        let path = Path::from(location.path());
+        let store = self.store.clone();
+        let meta = self.task_executor.block_on(async move {
+            store.put(&path, buffer.into()).await?;
+            store.head(&path).await
+        })?;
+        Ok(FileMeta {
+            location,
+            last_modified: meta.last_modified.timestamp_millis(),
+            size: meta.size,
+        })
+    }
 }
 
 /// Implements [`FileOpener`] for a parquet file
 struct ParquetOpener {
-    // projection: Arc<[usize]>,
     batch_size: usize,
     limit: Option<usize>,
+    /// The columns to read, in the order they are returned in
     table_schema: ArrowSchemaRef,
+    /// A predicate over the physical columns, used to skip row groups and pages which cannot
+    /// hold rows matching it
+    predicate: Option<Expression>,
     store: Arc<DynObjectStore>,
 }
 
@@ -102,12 +159,14 @@ impl ParquetOpener {
     pub(crate) fn new(
         batch_size: usize,
         schema: ArrowSchemaRef,
+        predicate: Option<Expression>,
         store: Arc<DynObjectStore>,
     ) -> Self {
         Self {
             batch_size,
             table_schema: schema,
             limit: None,
+            predicate,
             store,
         }
     }
@@ -119,29 +178,35 @@ impl FileOpener for ParquetOpener {
         let store = self.store.clone();
 
         let batch_size = self.batch_size;
-        // let projection = self.projection.clone();
-        let _table_schema = self.table_schema.clone();
+        let table_schema = self.table_schema.clone();
+        let predicate = self.predicate.clone();
         let limit = self.limit;
 
         Ok(Box::pin(async move {
             // TODO avoid IO by converting passed file meta to ObjectMeta
             let meta = store.head(&path).await?;
             let reader = ParquetObjectReader::new(store, meta);
-            let options = ArrowReaderOptions::new(); //.with_page_index(enable_page_index);
+            // the page index is only useful to skip pages
+            let options = ArrowReaderOptions::new().with_page_index(predicate.is_some());
             let mut builder =
                 ParquetRecordBatchStreamBuilder::new_with_options(reader, options).await?;
 
-            // let mask = ProjectionMask::roots(builder.parquet_schema(), projection.iter().cloned());
+            let mask = projection_mask(builder.parquet_schema(), &table_schema);
+            if let Some(predicate) = predicate {
+                builder = skip_row_groups_and_pages(builder, &predicate);
+            }
             if let Some(limit) = limit {
                 builder = builder.with_limit(limit)
             }
 
             let stream = builder
-                // .with_projection(mask)
+                .with_projection(mask)
                 .with_batch_size(batch_size)
                 .build()?;
 
-            let adapted = stream.map_err(Error::generic_err);
+            let adapted = stream.map_err(Error::generic_err).and_then(move |batch| {
+                futures::future::ready(reorder_columns(batch, &table_schema))
+            });
             Ok(adapted.boxed())
         }))
     }
@@ -151,8 +216,14 @@ impl FileOpener for ParquetOpener {
 mod tests {
     use std::path::PathBuf;
 
-    use arrow_array::RecordBatch;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::{Array, Int64Array, RecordBatch, StringArray};
     use object_store::{local::LocalFileSystem, ObjectStore};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+
+    use crate::schema::{DataType, StructField, StructType};
 
     use crate::{executor::tokio::TokioBackgroundExecutor, EngineData};
 
@@ -188,7 +259,7 @@ mod tests {
 
         let files = &[FileMeta {
             location: url.clone(),
-            last_modified: meta.last_modified.timestamp(),
+            last_modified: meta.last_modified.timestamp_millis(),
             size: meta.size,
         }];
 
@@ -203,4 +274,132 @@ mod tests {
         assert_eq!(data.len(), 1);
         assert_eq!(data[0].num_rows(), 10);
     }
+
+    /// Write ids 0..30 to a parquet file in three row groups of two pages each, and return a
+    /// handler to read it along with its file meta.
+    async fn write_ids(
+        dir: &tempfile::TempDir,
+    ) -> (DefaultParquetHandler<TokioBackgroundExecutor>, FileMeta) {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from_iter_values(0..30)) as _),
+            (
+                "name",
+                Arc::new(StringArray::from_iter_values(
+                    (0..30).map(|i| format!("{i}")),
+                )) as _,
+            ),
+        ])
+        .unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(10)
+            .set_data_page_row_count_limit(5)
+            .set_write_batch_size(5)
+            .build();
+        let path = dir.path().join("ids.parquet");
+        let mut writer = ArrowWriter::try_new(
+            std::fs::File::create(&path).unwrap(),
+            batch.schema(),
+            Some(props),
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let store = Arc::new(LocalFileSystem::new());
+        let url = url::Url::from_file_path(path).unwrap();
+        let meta = store.head(&Path::from(url.path())).await.unwrap();
+        let file = FileMeta {
+            location: url,
+            last_modified: meta.last_modified.timestamp_millis(),
+            size: meta.size,
+        };
+        let handler = DefaultParquetHandler::new(store, Arc::new(TokioBackgroundExecutor::new()));
+        (handler, file)
+    }
+
+    #[tokio::test]
+    async fn test_read_parquet_files_projection() {
+        let dir = tempfile::tempdir().unwrap();
+        let (handler, file) = write_ids(&dir).await;
+
+        let schema = StructType::new(vec![
+            StructField::new("name", DataType::STRING, true),
+            StructField::new("missing", DataType::INTEGER, true),
+            StructField::new("id", DataType::LONG, true),
+        ]);
+        let data: Vec<RecordBatch> = handler
+            .read_parquet_files(&[file], Arc::new(schema), None)
+            .unwrap()
+            .map(into_record_batch)
+            .try_collect()
+            .unwrap();
+
+        assert_eq!(data.iter().map(|batch| batch.num_rows()).sum::<usize>(), 30);
+        let names = data[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect_vec();
+        assert_eq!(names, ["name", "missing", "id"]);
+        assert_eq!(data[0].column(1).null_count(), data[0].num_rows());
+    }
+
+    #[tokio::test]
+    async fn test_read_parquet_files_skipping() {
+        let dir = tempfile::tempdir().unwrap();
+        let (handler, file) = write_ids(&dir).await;
+        let files = [file];
+        let schema = Arc::new(StructType::new(vec![StructField::new(
+            "id",
+            DataType::LONG,
+            true,
+        )]));
+
+        let cases = [
+            // skips the first two row groups and the first page of the last one
+            (
+                Expression::column("id").gt(Expression::literal(25i64)),
+                25..30,
+            ),
+            // skips the last row group
+            (
+                Expression::column("id").lt(Expression::literal(20i64)),
+                0..20,
+            ),
+            // the pages skipped for each column are combined
+            (
+                Expression::column("id")
+                    .ge(Expression::literal(10i64))
+                    .and(Expression::column("name").lt(Expression::literal("15"))),
+                10..15,
+            ),
+            // not eligible for skipping
+            (
+                Expression::column("id").distinct(Expression::literal(3i64)),
+                0..30,
+            ),
+            (
+                Expression::column("id").gt(Expression::literal(30i64)),
+                0..0,
+            ),
+        ];
+        for (predicate, expected) in cases {
+            let ids = handler
+                .read_parquet_files(&files, schema.clone(), Some(predicate.clone()))
+                .unwrap()
+                .map(into_record_batch)
+                .map_ok(|batch| {
+                    batch
+                        .column(0)
+                        .as_primitive::<Int64Type>()
+                        .values()
+                        .to_vec()
+                })
+                .flatten_ok()
+                .collect::<DeltaResult<Vec<_>>>()
+                .unwrap();
+            assert_eq!(ids, expected.collect_vec(), "{predicate}");
+        }
+    }
 }
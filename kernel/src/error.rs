@@ -1,4 +1,5 @@
 use crate::schema::DataType;
+use crate::Version;
 
 pub type DeltaResult<T, E = Error> = std::result::Result<T, E>;
 
@@ -39,6 +40,9 @@ pub enum Error {
     #[error("File not found: {0}")]
     FileNotFound(String),
 
+    #[error("File already exists: {0}")]
+    FileAlreadyExists(String),
+
     #[error("{0}")]
     MissingColumn(String),
 
@@ -51,6 +55,38 @@ pub enum Error {
     #[error("No table version found.")]
     MissingVersion,
 
+    #[error("Version {0} of the table was already committed by another writer")]
+    VersionAlreadyExists(Version),
+
+    #[error("The concurrent commit of version {0} added files matching the data read by the transaction")]
+    ConcurrentAppend(Version),
+
+    #[error(
+        "File {0}, read by the transaction, was removed by the concurrent commit of version {1}"
+    )]
+    ConcurrentDeleteRead(String, Version),
+
+    #[error("The table metadata was changed by the concurrent commit of version {0}")]
+    MetadataChanged(Version),
+
+    #[error("The table protocol was changed by the concurrent commit of version {0}")]
+    ProtocolChanged(Version),
+
+    #[error("Transaction {0} was also committed by the concurrent commit of version {1}")]
+    ConcurrentTransaction(String, Version),
+
+    #[error("Timestamp {0} is before the earliest available version {1} (committed at {2})")]
+    TimestampBeforeEarliestVersion(i64, Version, i64),
+
+    #[error("Timestamp {0} is after the latest available version {1} (committed at {2})")]
+    TimestampAfterLatestVersion(i64, Version, i64),
+
+    #[error("The checksum of version {0} does not match the state of the table: {1}")]
+    ChecksumMismatch(Version, String),
+
+    #[error("Change data feed is not enabled at version {0}")]
+    ChangeDataFeedNotEnabled(Version),
+
     #[error("Deletion Vector error: {0}")]
     DeletionVector(String),
 
@@ -74,6 +110,12 @@ pub enum Error {
 
     #[error("Join failure: {0}")]
     JoinFailure(String),
+
+    #[error("Invalid expression: {0}")]
+    InvalidExpression(String),
+
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
 }
 
 // Convenience constructors for Error types that take a String argument
@@ -89,6 +131,9 @@ impl Error {
     pub fn file_not_found(path: impl ToString) -> Self {
         Self::FileNotFound(path.to_string())
     }
+    pub fn file_already_exists(path: impl ToString) -> Self {
+        Self::FileAlreadyExists(path.to_string())
+    }
     pub fn missing_column(name: impl ToString) -> Self {
         Self::MissingColumn(name.to_string())
     }
@@ -107,6 +152,12 @@ impl Error {
     pub fn join_failure(msg: impl ToString) -> Self {
         Self::JoinFailure(msg.to_string())
     }
+    pub fn invalid_expression(msg: impl ToString) -> Self {
+        Self::InvalidExpression(msg.to_string())
+    }
+    pub fn unsupported(msg: impl ToString) -> Self {
+        Self::Unsupported(msg.to_string())
+    }
 }
 
 #[cfg(feature = "object_store")]
@@ -114,6 +165,7 @@ impl From<object_store::Error> for Error {
     fn from(value: object_store::Error) -> Self {
         match value {
             object_store::Error::NotFound { path, .. } => Self::file_not_found(path),
+            object_store::Error::AlreadyExists { path, .. } => Self::file_already_exists(path),
             err => Self::ObjectStore(err),
         }
     }
@@ -0,0 +1,330 @@
+//! Casts of values between types, both explicit ones and the implicit ones done to combine values
+//! of different types, which follow the type coercion rules of Delta (and Spark): numeric values
+//! are widened to the wider of the two types, and dates are widened to timestamps.
+
+use super::Scalar;
+use crate::schema::{DataType, PrimitiveType};
+use crate::{DeltaResult, Error};
+
+/// The maximum precision of a decimal
+const MAX_DECIMAL_PRECISION: u8 = 38;
+
+const MICROS_PER_DAY: i64 = 24 * 60 * 60 * 1_000_000;
+
+/// The position of a (non-decimal) numeric type in the order of widening.
+fn numeric_rank(data_type: &PrimitiveType) -> Option<u8> {
+    use PrimitiveType::*;
+    match data_type {
+        Byte => Some(0),
+        Short => Some(1),
+        Integer => Some(2),
+        Long => Some(3),
+        Float => Some(4),
+        Double => Some(5),
+        _ => None,
+    }
+}
+
+/// The precision and scale of the narrowest decimal type holding all values of an integral type.
+fn integral_decimal(data_type: &PrimitiveType) -> Option<(u8, i8)> {
+    use PrimitiveType::*;
+    match data_type {
+        Byte => Some((3, 0)),
+        Short => Some((5, 0)),
+        Integer => Some((10, 0)),
+        Long => Some((20, 0)),
+        _ => None,
+    }
+}
+
+/// The narrowest decimal type holding all values of two decimal types, as far as the maximum
+/// precision allows.
+fn wider_decimal((p1, s1): (u8, i8), (p2, s2): (u8, i8)) -> PrimitiveType {
+    let scale = s1.max(s2);
+    let integral_digits = (p1 as i16 - s1 as i16).max(p2 as i16 - s2 as i16);
+    let precision = (integral_digits + scale as i16).clamp(1, MAX_DECIMAL_PRECISION as i16);
+    PrimitiveType::Decimal(precision as u8, scale)
+}
+
+/// Get the type values of types `left` and `right` are implicitly cast to, to combine them e.g.
+/// in a comparison. Returns `None` if they cannot be combined.
+pub(crate) fn common_type(left: &DataType, right: &DataType) -> Option<DataType> {
+    use PrimitiveType::*;
+
+    if left == right {
+        return Some(left.clone());
+    }
+    let (DataType::Primitive(left), DataType::Primitive(right)) = (left, right) else {
+        return None;
+    };
+    let common = match (left, right) {
+        (Decimal(p1, s1), Decimal(p2, s2)) => wider_decimal((*p1, *s1), (*p2, *s2)),
+        (Decimal(..), Float | Double) | (Float | Double, Decimal(..)) => Double,
+        (Decimal(p, s), other) | (other, Decimal(p, s)) => {
+            wider_decimal((*p, *s), integral_decimal(other)?)
+        }
+        (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+        (left, right) => {
+            if numeric_rank(left)? >= numeric_rank(right)? {
+                left.clone()
+            } else {
+                right.clone()
+            }
+        }
+    };
+    Some(DataType::Primitive(common))
+}
+
+/// Whether casting values of type `from` to type `to` widens them, i.e. `to` is the common type of
+/// the two. Such a cast never fails and preserves the order of values, so the min and max values
+/// of a column cast to the type are the min and max values of the cast column.
+pub(crate) fn is_widening_cast(from: &DataType, to: &DataType) -> bool {
+    common_type(from, to).as_ref() == Some(to)
+}
+
+/// Rescale the unscaled value of a decimal, rounding half away from zero if digits are dropped.
+fn rescale(value: i128, from_scale: i8, to_scale: i8) -> Option<i128> {
+    let shift = to_scale as i32 - from_scale as i32;
+    let factor = 10i128.checked_pow(shift.unsigned_abs())?;
+    if shift >= 0 {
+        value.checked_mul(factor)
+    } else {
+        let rounded = value / factor;
+        let remainder = value % factor;
+        if remainder.abs() * 2 >= factor {
+            Some(rounded + value.signum())
+        } else {
+            Some(rounded)
+        }
+    }
+}
+
+impl Scalar {
+    /// Cast this value to `data_type`. Numbers which do not fit the type are an error, as are
+    /// strings which cannot be parsed as a value of the type.
+    pub(crate) fn cast(&self, data_type: &DataType) -> DeltaResult<Scalar> {
+        use PrimitiveType::*;
+
+        let error = || Error::generic(format!("Cannot cast {self} to {data_type}"));
+        if self.data_type() == *data_type {
+            return Ok(self.clone());
+        }
+        let DataType::Primitive(target) = data_type else {
+            return Err(error());
+        };
+
+        // the value as a decimal (an integer has scale zero), or as a floating point number
+        let (decimal, float) = match self {
+            Scalar::Null(_) => return Ok(Scalar::Null(data_type.clone())),
+            // unlike a binary partition value, a string cast to binary is its UTF-8 encoding
+            Scalar::String(value) if *target == Binary => {
+                return Ok(Scalar::Binary(value.as_bytes().to_vec()))
+            }
+            Scalar::String(value) => return target.parse_scalar(value),
+            Scalar::Byte(value) => (Some((*value as i128, 0)), None),
+            Scalar::Short(value) => (Some((*value as i128, 0)), None),
+            Scalar::Integer(value) => (Some((*value as i128, 0)), None),
+            Scalar::Long(value) => (Some((*value as i128, 0)), None),
+            Scalar::Decimal(value, _, scale) => (Some((*value, *scale)), None),
+            Scalar::Float(value) => (None, Some(*value as f64)),
+            Scalar::Double(value) => (None, Some(*value)),
+            Scalar::Date(days) => {
+                return match target {
+                    Timestamp => Ok(Scalar::Timestamp(*days as i64 * MICROS_PER_DAY)),
+                    _ => Err(error()),
+                }
+            }
+            Scalar::Timestamp(micros) => {
+                return match target {
+                    Date => Ok(Scalar::Date(
+                        micros
+                            .div_euclid(MICROS_PER_DAY)
+                            .try_into()
+                            .map_err(|_| error())?,
+                    )),
+                    _ => Err(error()),
+                }
+            }
+            Scalar::Boolean(_) | Scalar::Binary(_) => return Err(error()),
+        };
+
+        // integral values are truncated towards zero
+        let integral = || -> Option<i128> {
+            match (decimal, float) {
+                (Some((value, scale)), _) if scale > 0 => {
+                    Some(value / 10i128.checked_pow(scale as u32)?)
+                }
+                (Some((value, scale)), _) => rescale(value, scale, 0),
+                (_, Some(value)) if value.is_finite() => Some(value.trunc() as i128),
+                _ => None,
+            }
+        };
+        let floating = || match (decimal, float) {
+            (Some((value, scale)), _) => value as f64 / 10f64.powi(scale as i32),
+            (_, Some(value)) => value,
+            _ => f64::NAN,
+        };
+        let scalar = match target {
+            Byte => Scalar::Byte(
+                integral()
+                    .and_then(|v| v.try_into().ok())
+                    .ok_or_else(error)?,
+            ),
+            Short => Scalar::Short(
+                integral()
+                    .and_then(|v| v.try_into().ok())
+                    .ok_or_else(error)?,
+            ),
+            Integer => Scalar::Integer(
+                integral()
+                    .and_then(|v| v.try_into().ok())
+                    .ok_or_else(error)?,
+            ),
+            Long => Scalar::Long(
+                integral()
+                    .and_then(|v| v.try_into().ok())
+                    .ok_or_else(error)?,
+            ),
+            Float => Scalar::Float(floating() as f32),
+            Double => Scalar::Double(floating()),
+            Decimal(precision, scale) => {
+                let value = match (decimal, float) {
+                    (Some((value, from_scale)), _) => rescale(value, from_scale, *scale),
+                    (_, Some(value)) if value.is_finite() => {
+                        Some((value * 10f64.powi(*scale as i32)).round() as i128)
+                    }
+                    _ => None,
+                }
+                .ok_or_else(error)?;
+                let max = 10i128.checked_pow(*precision as u32).ok_or_else(error)?;
+                if value.abs() >= max {
+                    return Err(error());
+                }
+                Scalar::Decimal(value, *precision, *scale)
+            }
+            String | Boolean | Binary | Date | Timestamp => return Err(error()),
+        };
+        Ok(scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_type() {
+        let cases = [
+            (DataType::INTEGER, DataType::LONG, Some(DataType::LONG)),
+            (DataType::BYTE, DataType::SHORT, Some(DataType::SHORT)),
+            (DataType::LONG, DataType::FLOAT, Some(DataType::FLOAT)),
+            (DataType::FLOAT, DataType::DOUBLE, Some(DataType::DOUBLE)),
+            (
+                DataType::INTEGER,
+                DataType::decimal(5, 2),
+                Some(DataType::decimal(12, 2)),
+            ),
+            (
+                DataType::decimal(10, 2),
+                DataType::decimal(5, 4),
+                Some(DataType::decimal(12, 4)),
+            ),
+            (
+                DataType::decimal(38, 0),
+                DataType::decimal(38, 10),
+                Some(DataType::decimal(38, 10)),
+            ),
+            (
+                DataType::decimal(5, 2),
+                DataType::FLOAT,
+                Some(DataType::DOUBLE),
+            ),
+            (
+                DataType::DATE,
+                DataType::TIMESTAMP,
+                Some(DataType::TIMESTAMP),
+            ),
+            (DataType::STRING, DataType::STRING, Some(DataType::STRING)),
+            (DataType::STRING, DataType::INTEGER, None),
+            (DataType::BOOLEAN, DataType::INTEGER, None),
+            (DataType::DATE, DataType::LONG, None),
+        ];
+        for (left, right, expected) in cases {
+            assert_eq!(common_type(&left, &right), expected, "{left}, {right}");
+            assert_eq!(common_type(&right, &left), expected, "{right}, {left}");
+        }
+
+        assert!(is_widening_cast(&DataType::INTEGER, &DataType::LONG));
+        assert!(is_widening_cast(&DataType::DATE, &DataType::TIMESTAMP));
+        assert!(!is_widening_cast(&DataType::LONG, &DataType::INTEGER));
+        assert!(!is_widening_cast(&DataType::LONG, &DataType::decimal(5, 0)));
+        assert!(!is_widening_cast(&DataType::STRING, &DataType::INTEGER));
+    }
+
+    #[test]
+    fn test_cast_scalar() {
+        let cases = [
+            (Scalar::Integer(5), DataType::LONG, Scalar::Long(5)),
+            (Scalar::Long(5), DataType::BYTE, Scalar::Byte(5)),
+            (Scalar::Double(-2.7), DataType::INTEGER, Scalar::Integer(-2)),
+            (Scalar::Integer(3), DataType::DOUBLE, Scalar::Double(3.0)),
+            (
+                Scalar::Integer(3),
+                DataType::decimal(5, 2),
+                Scalar::Decimal(300, 5, 2),
+            ),
+            (
+                Scalar::Decimal(12345, 5, 3),
+                DataType::decimal(4, 2),
+                Scalar::Decimal(1235, 4, 2),
+            ),
+            (
+                Scalar::Decimal(-12345, 5, 3),
+                DataType::INTEGER,
+                Scalar::Integer(-12),
+            ),
+            (
+                Scalar::Decimal(125, 3, 2),
+                DataType::DOUBLE,
+                Scalar::Double(1.25),
+            ),
+            (
+                Scalar::Date(1),
+                DataType::TIMESTAMP,
+                Scalar::Timestamp(MICROS_PER_DAY),
+            ),
+            (Scalar::Timestamp(-1), DataType::DATE, Scalar::Date(-1)),
+            (Scalar::from("42"), DataType::LONG, Scalar::Long(42)),
+            (
+                Scalar::from("1.5"),
+                DataType::decimal(3, 2),
+                Scalar::Decimal(150, 3, 2),
+            ),
+            (
+                Scalar::from("é"),
+                DataType::BINARY,
+                Scalar::Binary(vec![0xc3, 0xa9]),
+            ),
+            (
+                Scalar::Null(DataType::INTEGER),
+                DataType::LONG,
+                Scalar::Null(DataType::LONG),
+            ),
+        ];
+        for (value, data_type, expected) in cases {
+            assert_eq!(value.cast(&data_type).unwrap(), expected, "{value}");
+        }
+
+        let errors = [
+            (Scalar::Integer(300), DataType::BYTE),
+            (Scalar::Double(f64::NAN), DataType::LONG),
+            (Scalar::Integer(1000), DataType::decimal(4, 2)),
+            (Scalar::from("a"), DataType::INTEGER),
+            (Scalar::Boolean(true), DataType::INTEGER),
+            (Scalar::Integer(1), DataType::DATE),
+        ];
+        for (value, data_type) in errors {
+            assert!(value.cast(&data_type).is_err(), "{value} to {data_type}");
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DeltaResult, Error};
+
+/// The name of a column, i.e. the path of field names leading to it through nested structs.
+///
+/// As a string, the field names of the path are separated by dots. Field names which contain dots
+/// or backticks are quoted with backticks, where a backtick in a quoted name is doubled, e.g.
+/// ``a.`b.c`.d`` names the field `d` of the field `b.c` of the column `a`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ColumnName {
+    path: Vec<String>,
+}
+
+impl ColumnName {
+    /// Create a column name from the field names of its path.
+    pub fn new(path: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            path: path.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The field names of the path of the column.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The name of the column nested in this one at the path of `other`.
+    pub fn join(&self, other: &ColumnName) -> Self {
+        Self::new(self.path.iter().chain(other.path.iter()).cloned())
+    }
+
+    /// Parse a dot separated path of possibly quoted field names.
+    fn parse(name: &str) -> DeltaResult<Vec<String>> {
+        let invalid =
+            |reason: &str| Error::generic(format!("Invalid column name {name}: {reason}"));
+        let mut path = vec![];
+        let mut chars = name.chars().peekable();
+        loop {
+            let mut field = String::new();
+            if chars.next_if_eq(&'`').is_some() {
+                loop {
+                    match chars.next() {
+                        Some('`') if chars.next_if_eq(&'`').is_some() => field.push('`'),
+                        Some('`') => break,
+                        Some(c) => field.push(c),
+                        None => return Err(invalid("unterminated quote")),
+                    }
+                }
+                path.push(field);
+                match chars.next() {
+                    Some('.') => {}
+                    Some(_) => return Err(invalid("expected a dot after a quoted field name")),
+                    None => return Ok(path),
+                }
+            } else {
+                loop {
+                    match chars.next() {
+                        Some('.') => break,
+                        Some('`') => return Err(invalid("unexpected quote")),
+                        Some(c) => field.push(c),
+                        None => {
+                            path.push(field);
+                            return Ok(path);
+                        }
+                    }
+                }
+                path.push(field);
+            }
+        }
+    }
+}
+
+impl FromStr for ColumnName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(Self::parse(s)?))
+    }
+}
+
+/// Strings are taken as a dot separated path (see [`ColumnName`]). A string which is not a valid
+/// path, e.g. because of an unterminated quote, is taken as a single field name.
+impl From<&str> for ColumnName {
+    fn from(name: &str) -> Self {
+        Self::parse(name).map_or_else(|_| Self::new([name]), Self::new)
+    }
+}
+
+impl From<String> for ColumnName {
+    fn from(name: String) -> Self {
+        name.as_str().into()
+    }
+}
+
+impl From<&String> for ColumnName {
+    fn from(name: &String) -> Self {
+        name.as_str().into()
+    }
+}
+
+impl Display for ColumnName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, field) in self.path.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            if field.contains(['.', '`']) {
+                write!(f, "`{}`", field.replace('`', "``"))?;
+            } else {
+                write!(f, "{}", field)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_column_name() {
+        let cases = [
+            ("a", vec!["a"]),
+            ("a.b.c", vec!["a", "b", "c"]),
+            ("`a.b`.c", vec!["a.b", "c"]),
+            ("a.`b.c`", vec!["a", "b.c"]),
+            ("`a``b`", vec!["a`b"]),
+            ("a b.c", vec!["a b", "c"]),
+        ];
+        for (name, expected) in cases {
+            let column: ColumnName = name.parse().unwrap();
+            assert_eq!(column.path(), expected, "{name}");
+            assert_eq!(column.to_string(), name);
+        }
+
+        for name in ["`a", "`a`b", "a`b`", "a.`b"] {
+            assert!(name.parse::<ColumnName>().is_err(), "{name}");
+            assert_eq!(ColumnName::from(name).path(), [name]);
+        }
+    }
+
+    #[test]
+    fn test_join_column_name() {
+        let stats = ColumnName::new(["minValues"]);
+        let column = ColumnName::new(["a.b", "c"]);
+        assert_eq!(stats.join(&column).to_string(), "minValues.`a.b`.c");
+    }
+}
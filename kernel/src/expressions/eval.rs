@@ -0,0 +1,452 @@
+//! Evaluation of [`Expression`]s on single values, e.g. the partition values of a file, without
+//! going through an engine's [`ExpressionHandler`].
+//!
+//! [`ExpressionHandler`]: crate::ExpressionHandler
+
+use std::cmp::Ordering;
+
+use super::coercion::common_type;
+use super::{
+    BinaryOperator, ColumnName, Expression, LikePattern, Scalar, UnaryOperator, VariadicOperator,
+};
+use crate::schema::DataType;
+use crate::{DeltaResult, Error};
+
+impl Expression {
+    /// Evaluate this expression on a single row, where `resolve` provides the values of the
+    /// referenced columns.
+    ///
+    /// Evaluation follows SQL semantics, e.g. comparisons with null are null. If `resolve` returns
+    /// `None` for a column its value is unknown, and so is the result of any part of the
+    /// expression that depends on it, unless that can be decided without the value (e.g. `false
+    /// AND x`). An unknown result is returned as `None`.
+    pub(crate) fn evaluate_scalar(
+        &self,
+        resolve: &dyn Fn(&ColumnName) -> Option<Scalar>,
+    ) -> DeltaResult<Option<Scalar>> {
+        match self {
+            Self::Literal(scalar) => Ok(Some(scalar.clone())),
+            Self::Column(name) => Ok(resolve(name)),
+            Self::Struct(_) => Err(Error::generic(
+                "Struct expressions cannot be evaluated to a single value",
+            )),
+            Self::UnaryOperation { op, expr } => {
+                let Some(value) = expr.evaluate_scalar(resolve)? else {
+                    return Ok(None);
+                };
+                let result = match (op, value) {
+                    (UnaryOperator::IsNull, value) => Scalar::Boolean(value.is_null()),
+                    (UnaryOperator::Not, Scalar::Boolean(b)) => Scalar::Boolean(!b),
+                    (UnaryOperator::Not, Scalar::Null(_)) => Scalar::Null(DataType::BOOLEAN),
+                    (UnaryOperator::Not, value) => {
+                        return Err(Error::generic(format!("Cannot negate {value}")))
+                    }
+                };
+                Ok(Some(result))
+            }
+            Self::BinaryOperation { op, left, right } => {
+                let left = left.evaluate_scalar(resolve)?;
+                let right = right.evaluate_scalar(resolve)?;
+                match (left, right) {
+                    (Some(left), Some(right)) => evaluate_binary(op, left, right).map(Some),
+                    _ => Ok(None),
+                }
+            }
+            Self::VariadicOperation { op, exprs } => {
+                // the value that decides the result on its own, e.g. false for AND
+                let dominant = matches!(op, VariadicOperator::Or);
+                let mut result = Some(Scalar::Boolean(!dominant));
+                for expr in exprs {
+                    match expr.evaluate_scalar(resolve)? {
+                        Some(Scalar::Boolean(b)) if b == dominant => {
+                            return Ok(Some(Scalar::Boolean(dominant)))
+                        }
+                        Some(Scalar::Boolean(_)) => {}
+                        Some(Scalar::Null(_)) => {
+                            // an unknown value may still be dominant, so it takes precedence
+                            if let Some(result) = result.as_mut() {
+                                *result = Scalar::Null(DataType::BOOLEAN);
+                            }
+                        }
+                        Some(value) => {
+                            return Err(Error::generic(format!(
+                                "Expected a boolean operand of {op:?}, got {value}"
+                            )))
+                        }
+                        None => result = None,
+                    }
+                }
+                Ok(result)
+            }
+            Self::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                // equivalent to comparing with each value of the list in turn
+                let any_equal =
+                    Self::or_from(list.iter().map(|value| (**expr).clone().eq(value.clone())));
+                if *negated {
+                    (!any_equal).evaluate_scalar(resolve)
+                } else {
+                    any_equal.evaluate_scalar(resolve)
+                }
+            }
+            Self::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => {
+                let like = LikePattern::try_new(pattern, *escape)?;
+                let result = match expr.evaluate_scalar(resolve)? {
+                    Some(Scalar::String(value)) => {
+                        Scalar::Boolean(like.matches(&value) != *negated)
+                    }
+                    Some(Scalar::Null(_)) => Scalar::Null(DataType::BOOLEAN),
+                    Some(value) => return Err(Error::generic(format!("Cannot match {value}"))),
+                    None => return Ok(None),
+                };
+                Ok(Some(result))
+            }
+            Self::StartsWith { expr, prefix } => {
+                let value = expr.evaluate_scalar(resolve)?;
+                let prefix = prefix.evaluate_scalar(resolve)?;
+                let result = match (value, prefix) {
+                    (Some(Scalar::String(value)), Some(Scalar::String(prefix))) => {
+                        Scalar::Boolean(value.starts_with(&prefix))
+                    }
+                    (Some(Scalar::Null(_)), Some(_)) | (Some(_), Some(Scalar::Null(_))) => {
+                        Scalar::Null(DataType::BOOLEAN)
+                    }
+                    (Some(value), Some(prefix)) => {
+                        return Err(Error::generic(format!(
+                            "Cannot evaluate STARTS_WITH({value}, {prefix})"
+                        )))
+                    }
+                    _ => return Ok(None),
+                };
+                Ok(Some(result))
+            }
+            Self::Cast { expr, data_type } => expr
+                .evaluate_scalar(resolve)?
+                .map(|value| value.cast(data_type))
+                .transpose(),
+        }
+    }
+}
+
+impl Scalar {
+    pub(crate) fn is_null(&self) -> bool {
+        matches!(self, Self::Null(_))
+    }
+
+    /// Compare two non-null values of the same type.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        use Scalar::*;
+        match (self, other) {
+            (Integer(a), Integer(b)) => a.partial_cmp(b),
+            (Long(a), Long(b)) => a.partial_cmp(b),
+            (Short(a), Short(b)) => a.partial_cmp(b),
+            (Byte(a), Byte(b)) => a.partial_cmp(b),
+            (Float(a), Float(b)) => a.partial_cmp(b),
+            (Double(a), Double(b)) => a.partial_cmp(b),
+            (String(a), String(b)) => a.partial_cmp(b),
+            (Boolean(a), Boolean(b)) => a.partial_cmp(b),
+            (Timestamp(a), Timestamp(b)) => a.partial_cmp(b),
+            (Date(a), Date(b)) => a.partial_cmp(b),
+            (Binary(a), Binary(b)) => a.partial_cmp(b),
+            (Decimal(a, _, a_scale), Decimal(b, _, b_scale)) if a_scale == b_scale => {
+                a.partial_cmp(b)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Implicitly cast two values to their common type if they differ in type. Values which cannot be
+/// combined are left as they are.
+fn coerce(left: Scalar, right: Scalar) -> DeltaResult<(Scalar, Scalar)> {
+    let (left_type, right_type) = (left.data_type(), right.data_type());
+    if left_type == right_type {
+        return Ok((left, right));
+    }
+    match common_type(&left_type, &right_type) {
+        Some(common) => Ok((left.cast(&common)?, right.cast(&common)?)),
+        None => Ok((left, right)),
+    }
+}
+
+/// Compare two non-null values, which are first cast to their common type if they differ in type.
+pub(super) fn compare_values(left: &Scalar, right: &Scalar) -> Option<Ordering> {
+    let (left, right) = coerce(left.clone(), right.clone()).ok()?;
+    left.compare(&right)
+}
+
+fn evaluate_binary(op: &BinaryOperator, left: Scalar, right: Scalar) -> DeltaResult<Scalar> {
+    use BinaryOperator::*;
+
+    let (left, right) = coerce(left, right)?;
+    let incompatible = || Error::generic(format!("Cannot evaluate {left} {op} {right}"));
+    match op {
+        Distinct => {
+            let distinct = match (left.is_null(), right.is_null()) {
+                (true, true) => false,
+                (false, false) => left.compare(&right).ok_or_else(incompatible)? != Ordering::Equal,
+                _ => true,
+            };
+            Ok(Scalar::Boolean(distinct))
+        }
+        LessThan | LessThanOrEqual | GreaterThan | GreaterThanOrEqual | Equal | NotEqual => {
+            if left.is_null() || right.is_null() {
+                return Ok(Scalar::Null(DataType::BOOLEAN));
+            }
+            let ordering = left.compare(&right).ok_or_else(incompatible)?;
+            let result = match op {
+                LessThan => ordering.is_lt(),
+                LessThanOrEqual => ordering.is_le(),
+                GreaterThan => ordering.is_gt(),
+                GreaterThanOrEqual => ordering.is_ge(),
+                Equal => ordering.is_eq(),
+                _ => ordering.is_ne(),
+            };
+            Ok(Scalar::Boolean(result))
+        }
+        Plus | Minus | Multiply | Divide => {
+            if left.is_null() || right.is_null() {
+                return Ok(Scalar::Null(left.data_type()));
+            }
+            macro_rules! integer_op {
+                ($variant:ident, $a:expr, $b:expr) => {
+                    match op {
+                        Plus => $a.checked_add($b),
+                        Minus => $a.checked_sub($b),
+                        Multiply => $a.checked_mul($b),
+                        _ => $a.checked_div($b),
+                    }
+                    .map(Scalar::$variant)
+                };
+            }
+            macro_rules! float_op {
+                ($variant:ident, $a:expr, $b:expr) => {
+                    Some(Scalar::$variant(match op {
+                        Plus => $a + $b,
+                        Minus => $a - $b,
+                        Multiply => $a * $b,
+                        _ => $a / $b,
+                    }))
+                };
+            }
+            let result = match (&left, &right) {
+                (Scalar::Integer(a), Scalar::Integer(b)) => integer_op!(Integer, a, *b),
+                (Scalar::Long(a), Scalar::Long(b)) => integer_op!(Long, a, *b),
+                (Scalar::Short(a), Scalar::Short(b)) => integer_op!(Short, a, *b),
+                (Scalar::Byte(a), Scalar::Byte(b)) => integer_op!(Byte, a, *b),
+                (Scalar::Float(a), Scalar::Float(b)) => float_op!(Float, a, b),
+                (Scalar::Double(a), Scalar::Double(b)) => float_op!(Double, a, b),
+                _ => return Err(incompatible()),
+            };
+            // integer overflow or division by zero
+            result.ok_or_else(|| Error::generic(format!("Failed to evaluate {left} {op} {right}")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(expr: &Expression) -> Option<Scalar> {
+        let resolve = |name: &ColumnName| match name.to_string().as_str() {
+            "x" => Some(Scalar::Integer(5)),
+            "s" => Some(Scalar::from("b")),
+            "n" => Some(Scalar::Null(DataType::INTEGER)),
+            _ => None,
+        };
+        expr.evaluate_scalar(&resolve).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_comparisons() {
+        let x = Expression::column("x");
+        let s = Expression::column("s");
+        let n = Expression::column("n");
+        let cases = [
+            (x.clone().eq(Expression::literal(5)), Scalar::Boolean(true)),
+            (x.clone().lt(Expression::literal(5)), Scalar::Boolean(false)),
+            (x.clone().ge(Expression::literal(5)), Scalar::Boolean(true)),
+            (
+                s.clone().gt(Expression::literal("a")),
+                Scalar::Boolean(true),
+            ),
+            (s.ne(Expression::literal("b")), Scalar::Boolean(false)),
+            (
+                (x.clone() + Expression::literal(2)).eq(Expression::literal(7)),
+                Scalar::Boolean(true),
+            ),
+            (n.clone().eq(x.clone()), Scalar::Null(DataType::BOOLEAN)),
+            (n.clone().is_null(), Scalar::Boolean(true)),
+            (!n.clone().is_null(), Scalar::Boolean(false)),
+            (n.clone().distinct(x.clone()), Scalar::Boolean(true)),
+            (n.clone().distinct(n.clone()), Scalar::Boolean(false)),
+            (x.distinct(Expression::literal(5)), Scalar::Boolean(false)),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(evaluate(&expr), Some(expected), "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_unknown_columns() {
+        let x = Expression::column("x");
+        let unknown = Expression::column("unknown").eq(Expression::literal(1));
+        let null = Expression::column("n").eq(Expression::literal(1));
+        let cases = [
+            (unknown.clone(), None),
+            (
+                unknown.clone().and(x.clone().eq(Expression::literal(1))),
+                Some(Scalar::Boolean(false)),
+            ),
+            (
+                unknown.clone().and(x.clone().eq(Expression::literal(5))),
+                None,
+            ),
+            (
+                unknown.clone().or(x.clone().eq(Expression::literal(5))),
+                Some(Scalar::Boolean(true)),
+            ),
+            (unknown.clone().or(null.clone()), None),
+            (
+                null.clone().and(x.eq(Expression::literal(5))),
+                Some(Scalar::Null(DataType::BOOLEAN)),
+            ),
+            (!unknown, None),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(evaluate(&expr), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_string_predicates() {
+        let x = Expression::column("x");
+        let s = Expression::column("s");
+        let n = Expression::column("n");
+        let unknown = Expression::column("unknown");
+        let cases = [
+            (
+                x.clone()
+                    .in_list([Expression::literal(1), Expression::literal(5)]),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                x.clone().in_list([Expression::literal(1), n.clone()]),
+                Some(Scalar::Null(DataType::BOOLEAN)),
+            ),
+            (
+                x.clone()
+                    .not_in_list([Expression::literal(1), Expression::literal(2)]),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                x.clone().in_list([unknown.clone(), Expression::literal(5)]),
+                Some(Scalar::Boolean(true)),
+            ),
+            (x.clone().in_list([unknown.clone()]), None),
+            (x.in_list([]), Some(Scalar::Boolean(false))),
+            (s.clone().like("_"), Some(Scalar::Boolean(true))),
+            (s.clone().not_like("a%"), Some(Scalar::Boolean(true))),
+            (unknown.clone().like("a%"), None),
+            (
+                s.clone().starts_with(Expression::literal("b")),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                s.starts_with(Expression::literal("c")),
+                Some(Scalar::Boolean(false)),
+            ),
+            (
+                Expression::literal(Scalar::Null(DataType::STRING)).like("a%"),
+                Some(Scalar::Null(DataType::BOOLEAN)),
+            ),
+            (unknown.starts_with(Expression::literal("a")), None),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(evaluate(&expr), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_casts() {
+        let x = Expression::column("x");
+        let n = Expression::column("n");
+        let cases = [
+            (x.clone().cast(DataType::LONG), Some(Scalar::Long(5))),
+            (
+                x.clone().cast(DataType::decimal(5, 1)),
+                Some(Scalar::Decimal(50, 5, 1)),
+            ),
+            (
+                n.clone().cast(DataType::LONG),
+                Some(Scalar::Null(DataType::LONG)),
+            ),
+            (Expression::column("unknown").cast(DataType::LONG), None),
+            (
+                Expression::literal("7").cast(DataType::INTEGER),
+                Some(Scalar::Integer(7)),
+            ),
+            // implicit widening of the narrower operand
+            (
+                x.clone().eq(Expression::literal(5i64)),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                x.clone().lt(Expression::literal(Scalar::Double(5.5))),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                x.clone()
+                    .gt(Expression::literal(Scalar::Decimal(4999, 4, 3))),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                (x.clone() + Expression::literal(1i64)).eq(Expression::literal(6)),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                Expression::literal(Scalar::Date(1))
+                    .lt(Expression::literal(Scalar::Timestamp(86_400_000_001))),
+                Some(Scalar::Boolean(true)),
+            ),
+            (
+                n.eq(Expression::literal(5i64)),
+                Some(Scalar::Null(DataType::BOOLEAN)),
+            ),
+            (
+                x.distinct(Expression::literal(5i64)),
+                Some(Scalar::Boolean(false)),
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(evaluate(&expr), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        let resolve = |_: &ColumnName| None;
+        let exprs = [
+            Expression::literal(1).lt(Expression::literal("a")),
+            Expression::literal(i32::MAX) + Expression::literal(1),
+            Expression::literal(1) / Expression::literal(0),
+            Expression::and_from([Expression::literal(1)]),
+            Expression::literal(1).like("1"),
+            Expression::literal("a").like_with_escape("a!", '!'),
+            Expression::literal(300).cast(DataType::BYTE),
+            Expression::literal(true).cast(DataType::INTEGER),
+        ];
+        for expr in exprs {
+            assert!(expr.evaluate_scalar(&resolve).is_err(), "{expr}");
+        }
+    }
+}
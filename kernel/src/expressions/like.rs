@@ -0,0 +1,147 @@
+//! Matching of strings against SQL `LIKE` patterns.
+
+use crate::{DeltaResult, Error};
+
+#[derive(Debug, Clone, PartialEq)]
+enum LikeToken {
+    /// A character which matches itself
+    Char(char),
+    /// `_`, which matches any single character
+    AnyChar,
+    /// `%`, which matches any sequence of characters
+    AnyString,
+}
+
+/// A compiled `LIKE` pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LikePattern {
+    tokens: Vec<LikeToken>,
+}
+
+impl LikePattern {
+    /// Compile `pattern`, where `escape` (if any) makes the character following it match
+    /// literally, e.g. `a\%` with `\` as escape character only matches `a%`.
+    pub(crate) fn try_new(pattern: &str, escape: Option<char>) -> DeltaResult<Self> {
+        let mut tokens = vec![];
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                c if Some(c) == escape => match chars.next() {
+                    Some(escaped) => LikeToken::Char(escaped),
+                    None => {
+                        return Err(Error::generic(format!(
+                            "LIKE pattern '{pattern}' ends with the escape character"
+                        )))
+                    }
+                },
+                '%' => LikeToken::AnyString,
+                '_' => LikeToken::AnyChar,
+                c => LikeToken::Char(c),
+            };
+            tokens.push(token);
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Whether `value` matches the pattern.
+    pub(crate) fn matches(&self, value: &str) -> bool {
+        let chars: Vec<char> = value.chars().collect();
+        let (mut token, mut char) = (0, 0);
+        // where to resume matching if the tokens after the last `%` fail to match: the token
+        // following the `%` and the character it is tried at
+        let mut backtrack = None;
+        while char < chars.len() {
+            match self.tokens.get(token) {
+                Some(LikeToken::AnyString) => {
+                    token += 1;
+                    backtrack = Some((token, char));
+                }
+                Some(LikeToken::AnyChar) => {
+                    token += 1;
+                    char += 1;
+                }
+                Some(LikeToken::Char(c)) if *c == chars[char] => {
+                    token += 1;
+                    char += 1;
+                }
+                _ => match backtrack.as_mut() {
+                    Some((resume_token, resume_char)) => {
+                        // let the `%` match one more character
+                        *resume_char += 1;
+                        token = *resume_token;
+                        char = *resume_char;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        self.tokens[token..]
+            .iter()
+            .all(|token| *token == LikeToken::AnyString)
+    }
+
+    /// Get the characters every matching string starts with, i.e. those before the first
+    /// wildcard.
+    pub(crate) fn prefix(&self) -> String {
+        self.tokens
+            .iter()
+            .map_while(|token| match token {
+                LikeToken::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_matches() {
+        let cases = [
+            ("abc", None, "abc", true),
+            ("abc", None, "abcd", false),
+            ("a%", None, "abc", true),
+            ("a%", None, "a", true),
+            ("a%", None, "ba", false),
+            ("%c", None, "abc", true),
+            ("%b%", None, "abc", true),
+            ("%b%", None, "ac", false),
+            ("a_c", None, "abc", true),
+            ("a_c", None, "ac", false),
+            ("a%b%c", None, "aXbYbZc", true),
+            ("a%b%c", None, "aXbYc", true),
+            ("a%b%c", None, "aXcYb", false),
+            ("%", None, "", true),
+            ("_", None, "", false),
+            ("a\\%", Some('\\'), "a%", true),
+            ("a\\%", Some('\\'), "ab", false),
+            ("a\\%", None, "a\\bc", true),
+            ("a!_%", Some('!'), "a_bc", true),
+            ("a!_%", Some('!'), "abc", false),
+            ("a!!", Some('!'), "a!", true),
+            ("ü_%", None, "üöü", true),
+        ];
+        for (pattern, escape, value, expected) in cases {
+            let like = LikePattern::try_new(pattern, escape).unwrap();
+            assert_eq!(like.matches(value), expected, "{value} LIKE {pattern}");
+        }
+    }
+
+    #[test]
+    fn test_like_prefix() {
+        let cases = [
+            ("abc%", None, "abc"),
+            ("ab_c%", None, "ab"),
+            ("%abc", None, ""),
+            ("abc", None, "abc"),
+            ("a\\%b%", Some('\\'), "a%b"),
+        ];
+        for (pattern, escape, expected) in cases {
+            let like = LikePattern::try_new(pattern, escape).unwrap();
+            assert_eq!(like.prefix(), expected, "{pattern}");
+        }
+        assert!(LikePattern::try_new("abc\\", Some('\\')).is_err());
+    }
+}
@@ -2,12 +2,22 @@ use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
+pub use self::column_name::ColumnName;
+pub(crate) use self::like::LikePattern;
 pub use self::scalars::Scalar;
+use crate::schema::DataType;
 
+pub(crate) mod coercion;
+mod column_name;
+mod eval;
+mod like;
+mod parser;
 mod scalars;
+mod simplify;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A binary operator.
 pub enum BinaryOperator {
     /// Arithmetic Plus
@@ -34,7 +44,7 @@ pub enum BinaryOperator {
     Distinct,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VariadicOperator {
     And,
     Or,
@@ -61,7 +71,7 @@ impl Display for BinaryOperator {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// A unary operator.
 pub enum UnaryOperator {
     /// Unary Not
@@ -75,12 +85,12 @@ pub enum UnaryOperator {
 /// These expressions do not track or validate data types, other than the type
 /// of literals. It is up to the expression evaluator to validate the
 /// expression against a schema and add appropriate casts as required.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expression {
     /// A literal value.
     Literal(Scalar),
-    /// A column reference by name.
-    Column(String),
+    /// A reference to a (possibly nested) column.
+    Column(ColumnName),
     ///
     Struct(Vec<Expression>),
     /// A binary operation.
@@ -105,7 +115,41 @@ pub enum Expression {
         /// The expressions.
         exprs: Vec<Expression>,
     },
-    // TODO: support more expressions, such as IS IN, LIKE, etc.
+    /// Whether a value is equal to any value in a list, e.g. `x IN (1, 2, 3)`.
+    InList {
+        /// The value to look up.
+        expr: Box<Expression>,
+        /// The values to compare with.
+        list: Vec<Expression>,
+        /// Whether this is `NOT IN` instead.
+        negated: bool,
+    },
+    /// Whether a string matches a pattern, where `%` matches any sequence of characters and `_`
+    /// matches any single character, e.g. `x LIKE 'a%'`.
+    Like {
+        /// The string to match.
+        expr: Box<Expression>,
+        /// The pattern to match against.
+        pattern: String,
+        /// The character which makes the next character of the pattern match literally.
+        escape: Option<char>,
+        /// Whether this is `NOT LIKE` instead.
+        negated: bool,
+    },
+    /// Whether a string starts with a prefix.
+    StartsWith {
+        /// The string to check.
+        expr: Box<Expression>,
+        /// The prefix.
+        prefix: Box<Expression>,
+    },
+    /// A conversion of a value to another type, e.g. `CAST(x AS bigint)`.
+    Cast {
+        /// The value to convert.
+        expr: Box<Expression>,
+        /// The type to convert to.
+        data_type: DataType,
+    },
 }
 
 impl<T: Into<Scalar>> From<T> for Expression {
@@ -114,11 +158,28 @@ impl<T: Into<Scalar>> From<T> for Expression {
     }
 }
 
+/// The precedence of comparisons, and of `IS NULL`, `IN` and `LIKE`, which cannot be chained
+/// without parentheses.
+const COMPARISON_PRECEDENCE: u8 = 3;
+
+/// Write `expr` as the operand of an operator, in parentheses if `parenthesize`.
+fn fmt_operand(f: &mut Formatter<'_>, expr: &Expression, parenthesize: bool) -> std::fmt::Result {
+    if parenthesize {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+/// Expressions are displayed in the syntax they are parsed from (see [`Expression::from_str`]),
+/// such that they parse back to the same expression.
+///
+/// [`Expression::from_str`]: std::str::FromStr::from_str
 impl Display for Expression {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Literal(l) => write!(f, "{}", l),
-            Self::Column(name) => write!(f, "Column({})", name),
+            Self::Literal(l) => parser::fmt_literal(f, l),
+            Self::Column(name) => parser::fmt_column(f, name),
             Self::Struct(exprs) => write!(
                 f,
                 "Struct({})",
@@ -129,10 +190,25 @@ impl Display for Expression {
                 left,
                 right,
             } => write!(f, "DISTINCT({}, {})", left, right),
-            Self::BinaryOperation { op, left, right } => write!(f, "{} {} {}", left, op, right),
+            Self::BinaryOperation { op, left, right } => {
+                let precedence = self.precedence();
+                // comparisons do not chain, and all operators are left associative
+                let left_precedence = left.precedence();
+                fmt_operand(
+                    f,
+                    left,
+                    left_precedence < precedence
+                        || (left_precedence == precedence && precedence == COMPARISON_PRECEDENCE),
+                )?;
+                write!(f, " {} ", op)?;
+                fmt_operand(f, right, right.precedence() <= precedence)
+            }
             Self::UnaryOperation { op, expr } => match op {
                 UnaryOperator::Not => write!(f, "NOT {}", expr),
-                UnaryOperator::IsNull => write!(f, "{} IS NULL", expr),
+                UnaryOperator::IsNull => {
+                    fmt_operand(f, expr, expr.precedence() <= COMPARISON_PRECEDENCE)?;
+                    write!(f, " IS NULL")
+                }
             },
             Self::VariadicOperation { op, exprs } => match op {
                 VariadicOperator::And => {
@@ -150,27 +226,86 @@ impl Display for Expression {
                     )
                 }
             },
+            Self::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                fmt_operand(f, expr, expr.precedence() <= COMPARISON_PRECEDENCE)?;
+                write!(
+                    f,
+                    " {}IN ({})",
+                    not,
+                    &list.iter().map(|e| format!("{e}")).join(", ")
+                )
+            }
+            Self::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                fmt_operand(f, expr, expr.precedence() <= COMPARISON_PRECEDENCE)?;
+                write!(f, " {}LIKE ", not)?;
+                parser::fmt_string(f, pattern)?;
+                match escape {
+                    Some(escape) => {
+                        write!(f, " ESCAPE ")?;
+                        parser::fmt_string(f, &escape.to_string())
+                    }
+                    None => Ok(()),
+                }
+            }
+            Self::StartsWith { expr, prefix } => write!(f, "STARTS_WITH({}, {})", expr, prefix),
+            Self::Cast { expr, data_type } => write!(f, "CAST({} AS {})", expr, data_type),
         }
     }
 }
 
 impl Expression {
+    /// How tightly the operator of this expression binds its operands when displayed, where
+    /// expressions without an operator between operands bind the tightest.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::BinaryOperation { op, .. } => match op {
+                BinaryOperator::Plus | BinaryOperator::Minus => 4,
+                BinaryOperator::Multiply | BinaryOperator::Divide => 5,
+                BinaryOperator::Distinct => u8::MAX,
+                _ => COMPARISON_PRECEDENCE,
+            },
+            Self::UnaryOperation {
+                op: UnaryOperator::Not,
+                ..
+            } => 1,
+            Self::UnaryOperation {
+                op: UnaryOperator::IsNull,
+                ..
+            }
+            | Self::InList { .. }
+            | Self::Like { .. } => COMPARISON_PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+
     /// Returns a set of columns referenced by this expression.
-    pub fn references(&self) -> HashSet<&str> {
+    pub fn references(&self) -> HashSet<&ColumnName> {
         let mut set = HashSet::new();
 
         for expr in self.walk() {
             if let Self::Column(name) = expr {
-                set.insert(name.as_str());
+                set.insert(name);
             }
         }
 
         set
     }
 
-    /// Create an new expression for a column reference
-    pub fn column(name: impl ToString) -> Self {
-        Self::Column(name.to_string())
+    /// Create an new expression for a column reference. A string is taken as a dot separated
+    /// path of field names, see [`ColumnName`].
+    pub fn column(name: impl Into<ColumnName>) -> Self {
+        Self::Column(name.into())
     }
 
     /// Create a new expression for a literal value
@@ -280,6 +415,70 @@ impl Expression {
         Self::binary(BinaryOperator::Distinct, self, other)
     }
 
+    /// Create a new expression `self IN (list...)`
+    pub fn in_list(self, list: impl IntoIterator<Item = Self>) -> Self {
+        Self::InList {
+            expr: Box::new(self),
+            list: list.into_iter().collect(),
+            negated: false,
+        }
+    }
+
+    /// Create a new expression `self NOT IN (list...)`
+    pub fn not_in_list(self, list: impl IntoIterator<Item = Self>) -> Self {
+        Self::InList {
+            expr: Box::new(self),
+            list: list.into_iter().collect(),
+            negated: true,
+        }
+    }
+
+    /// Create a new expression `self LIKE pattern`, without an escape character
+    pub fn like(self, pattern: impl ToString) -> Self {
+        Self::Like {
+            expr: Box::new(self),
+            pattern: pattern.to_string(),
+            escape: None,
+            negated: false,
+        }
+    }
+
+    /// Create a new expression `self NOT LIKE pattern`, without an escape character
+    pub fn not_like(self, pattern: impl ToString) -> Self {
+        Self::Like {
+            expr: Box::new(self),
+            pattern: pattern.to_string(),
+            escape: None,
+            negated: true,
+        }
+    }
+
+    /// Create a new expression `self LIKE pattern ESCAPE escape`
+    pub fn like_with_escape(self, pattern: impl ToString, escape: char) -> Self {
+        Self::Like {
+            expr: Box::new(self),
+            pattern: pattern.to_string(),
+            escape: Some(escape),
+            negated: false,
+        }
+    }
+
+    /// Create a new expression `STARTS_WITH(self, prefix)`
+    pub fn starts_with(self, prefix: Self) -> Self {
+        Self::StartsWith {
+            expr: Box::new(self),
+            prefix: Box::new(prefix),
+        }
+    }
+
+    /// Create a new expression `CAST(self AS data_type)`
+    pub fn cast(self, data_type: impl Into<DataType>) -> Self {
+        Self::Cast {
+            expr: Box::new(self),
+            data_type: data_type.into(),
+        }
+    }
+
     fn walk(&self) -> impl Iterator<Item = &Self> + '_ {
         let mut stack = vec![self];
         std::iter::from_fn(move || {
@@ -300,6 +499,17 @@ impl Expression {
                 Self::VariadicOperation { exprs, .. } => {
                     stack.extend(exprs.iter());
                 }
+                Self::InList { expr, list, .. } => {
+                    stack.push(expr);
+                    stack.extend(list.iter());
+                }
+                Self::Like { expr, .. } | Self::Cast { expr, .. } => {
+                    stack.push(expr);
+                }
+                Self::StartsWith { expr, prefix } => {
+                    stack.push(expr);
+                    stack.push(prefix);
+                }
             }
             Some(expr)
         })
@@ -349,27 +559,28 @@ impl std::ops::Div<Expression> for Expression {
 #[cfg(test)]
 mod tests {
     use super::Expression as Expr;
+    use crate::schema::DataType;
 
     #[test]
     fn test_expression_format() {
         let col_ref = Expr::column("x");
         let cases = [
-            (col_ref.clone(), "Column(x)"),
-            (col_ref.clone().eq(Expr::literal(2)), "Column(x) = 2"),
+            (col_ref.clone(), "x"),
+            (col_ref.clone().eq(Expr::literal(2)), "x = 2"),
             (
                 (col_ref.clone() - Expr::literal(4)).lt(Expr::literal(10)),
-                "Column(x) - 4 < 10",
+                "x - 4 < 10",
             ),
             (
                 (col_ref.clone() + Expr::literal(4)) / Expr::literal(10) * Expr::literal(42),
-                "Column(x) + 4 / 10 * 42",
+                "(x + 4) / 10 * 42",
             ),
             (
                 col_ref
                     .clone()
                     .gt_eq(Expr::literal(2))
                     .and(col_ref.clone().lt_eq(Expr::literal(10))),
-                "AND(Column(x) >= 2, Column(x) <= 10)",
+                "AND(x >= 2, x <= 10)",
             ),
             (
                 Expr::and_from([
@@ -377,16 +588,40 @@ mod tests {
                     col_ref.clone().lt_eq(Expr::literal(10)),
                     col_ref.clone().lt_eq(Expr::literal(100)),
                 ]),
-                "AND(Column(x) >= 2, Column(x) <= 10, Column(x) <= 100)",
+                "AND(x >= 2, x <= 10, x <= 100)",
             ),
             (
                 col_ref
                     .clone()
                     .gt(Expr::literal(2))
                     .or(col_ref.clone().lt(Expr::literal(10))),
-                "OR(Column(x) > 2, Column(x) < 10)",
+                "OR(x > 2, x < 10)",
+            ),
+            (col_ref.clone().eq(Expr::literal("foo")), "x = 'foo'"),
+            (
+                col_ref
+                    .clone()
+                    .in_list([Expr::literal(1), Expr::literal(2)]),
+                "x IN (1, 2)",
+            ),
+            (
+                col_ref.clone().not_in_list([Expr::literal("a")]),
+                "x NOT IN ('a')",
+            ),
+            (col_ref.clone().like("a%"), "x LIKE 'a%'"),
+            (col_ref.clone().not_like("a_"), "x NOT LIKE 'a_'"),
+            (
+                col_ref.clone().like_with_escape("a!%%", '!'),
+                "x LIKE 'a!%%' ESCAPE '!'",
+            ),
+            (
+                col_ref.clone().starts_with(Expr::literal("ab")),
+                "STARTS_WITH(x, 'ab')",
+            ),
+            (
+                col_ref.cast(DataType::LONG).gt(Expr::literal(1i64)),
+                "CAST(x AS bigint) > 1L",
             ),
-            (col_ref.eq(Expr::literal("foo")), "Column(x) = 'foo'"),
         ];
 
         for (expr, expected) in cases {
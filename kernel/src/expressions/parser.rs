@@ -0,0 +1,1068 @@
+//! Parsing of expressions from SQL-like text, e.g. `a > 5 AND b IN ('x', 'y') AND c IS NOT NULL`,
+//! and the formatting of columns and literals which makes the [`Display`] of an expression parse
+//! back to the same expression.
+//!
+//! The grammar, from the loosest to the tightest binding operators, is:
+//! - `a OR b`, `a AND b` and `NOT a`
+//! - comparisons (`=`, `!=`, `<>`, `<`, `<=`, `>`, `>=`), `a IS [NOT] NULL`,
+//!   `a IS [NOT] DISTINCT FROM b`, `a [NOT] IN (b, c)` and `a [NOT] LIKE 'p' [ESCAPE 'c']`
+//! - `a + b` and `a - b`, then `a * b` and `a / b`
+//! - literals, columns, functions (`AND(..)`, `OR(..)`, `DISTINCT(a, b)`, `STARTS_WITH(a, b)`,
+//!   `STRUCT(..)` and `CAST(a AS type)`) and parenthesized expressions
+//!
+//! Numbers without a suffix are integers, or doubles if they have a fraction or exponent. The
+//! suffixes `L`, `S`, `Y`, `F`, `D` and `BD` make them longs, shorts, bytes, floats, doubles and
+//! decimals. Other literals are strings (`'it''s'`), binary values (`X'0aff'`), `true`, `false`,
+//! `NULL` and values of a type given as a string, e.g. `date '2024-01-31'`.
+//!
+//! [`Display`]: std::fmt::Display
+
+use std::fmt::{Formatter, Write as _};
+use std::str::FromStr;
+
+use chrono::DateTime;
+use itertools::Itertools;
+
+use super::coercion::is_widening_cast;
+use super::{BinaryOperator, ColumnName, Expression, Scalar, VariadicOperator};
+use crate::schema::{DataType, PrimitiveType, StructType};
+use crate::{DeltaResult, Error};
+
+/// Words which cannot be used as unquoted field names.
+const KEYWORDS: [&str; 13] = [
+    "AND", "OR", "NOT", "IS", "NULL", "IN", "LIKE", "ESCAPE", "TRUE", "FALSE", "DISTINCT", "FROM",
+    "AS",
+];
+
+/// Operator symbols, where longer symbols come before their prefixes.
+const SYMBOLS: [&str; 16] = [
+    "<=", ">=", "<>", "!=", "==", "=", "<", ">", "(", ")", ",", ".", "+", "-", "*", "/",
+];
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Whether a field name can be written without quotes.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !is_keyword(name)
+}
+
+/// Write a column name such that it parses back to the same name: field names which are not
+/// plain identifiers are quoted with backticks, where a backtick in the name is doubled.
+pub(super) fn fmt_column(f: &mut Formatter<'_>, name: &ColumnName) -> std::fmt::Result {
+    for (i, field) in name.path().iter().enumerate() {
+        if i > 0 {
+            f.write_char('.')?;
+        }
+        if is_identifier(field) {
+            f.write_str(field)?;
+        } else {
+            write!(f, "`{}`", field.replace('`', "``"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a string literal, quoted with single quotes, where a quote in the string is doubled.
+pub(super) fn fmt_string(f: &mut Formatter<'_>, value: &str) -> std::fmt::Result {
+    write!(f, "'{}'", value.replace('\'', "''"))
+}
+
+/// Write a literal such that it parses back to the same value, of the same type.
+pub(super) fn fmt_literal(f: &mut Formatter<'_>, scalar: &Scalar) -> std::fmt::Result {
+    match scalar {
+        Scalar::Integer(value) => write!(f, "{value}"),
+        Scalar::Long(value) => write!(f, "{value}L"),
+        Scalar::Short(value) => write!(f, "{value}S"),
+        Scalar::Byte(value) => write!(f, "{value}Y"),
+        Scalar::Float(value) if value.is_finite() => write!(f, "{value}F"),
+        Scalar::Double(value) if value.is_finite() => write!(f, "{value}D"),
+        // NaN and infinities have no number syntax
+        Scalar::Float(value) => write!(f, "float '{value}'"),
+        Scalar::Double(value) => write!(f, "double '{value}'"),
+        Scalar::String(value) => fmt_string(f, value),
+        Scalar::Boolean(value) => write!(f, "{value}"),
+        Scalar::Timestamp(micros) => {
+            let seconds = micros.div_euclid(1_000_000);
+            let nanos = micros.rem_euclid(1_000_000) as u32 * 1_000;
+            match DateTime::from_timestamp(seconds, nanos) {
+                Some(timestamp) => write!(
+                    f,
+                    "timestamp '{}'",
+                    timestamp.format("%Y-%m-%d %H:%M:%S%.6f")
+                ),
+                None => write!(f, "timestamp '{micros}'"),
+            }
+        }
+        Scalar::Date(days) => match DateTime::from_timestamp(*days as i64 * SECONDS_PER_DAY, 0) {
+            Some(date) => write!(f, "date '{}'", date.format("%Y-%m-%d")),
+            None => write!(f, "date '{days}'"),
+        },
+        Scalar::Binary(bytes) => {
+            f.write_str("X'")?;
+            for byte in bytes {
+                write!(f, "{byte:02x}")?;
+            }
+            f.write_char('\'')
+        }
+        Scalar::Decimal(..) => write!(f, "{} '{}'", scalar.data_type(), scalar),
+        Scalar::Null(data_type) => write!(f, "CAST(NULL AS {data_type})"),
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// The type with the given (upper case) name, other than decimal types, which take arguments.
+fn primitive_type(name: &str) -> Option<PrimitiveType> {
+    let data_type = match name {
+        "STRING" => PrimitiveType::String,
+        "BIGINT" | "LONG" => PrimitiveType::Long,
+        "INT" | "INTEGER" => PrimitiveType::Integer,
+        "SMALLINT" | "SHORT" => PrimitiveType::Short,
+        "TINYINT" | "BYTE" => PrimitiveType::Byte,
+        "FLOAT" => PrimitiveType::Float,
+        "DOUBLE" => PrimitiveType::Double,
+        "BOOLEAN" => PrimitiveType::Boolean,
+        "BINARY" => PrimitiveType::Binary,
+        "DATE" => PrimitiveType::Date,
+        "TIMESTAMP" => PrimitiveType::Timestamp,
+        _ => return None,
+    };
+    Some(data_type)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A keyword, function or unquoted field name
+    Word(String),
+    /// A field name quoted with backticks
+    QuotedName(String),
+    /// A number, and its type suffix if any
+    Number(String, String),
+    /// A string quoted with single quotes
+    String(String),
+    /// A binary value, e.g. `X'0aff'`
+    Binary(Vec<u8>),
+    /// An operator or punctuation
+    Symbol(&'static str),
+}
+
+/// The length of the number at the start of `text`, without its type suffix.
+fn number_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let digits_from = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+        i
+    };
+    let mut end = digits_from(0);
+    if bytes.get(end) == Some(&b'.') {
+        end = digits_from(end + 1);
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        if bytes.get(end + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            end = digits_from(end + 1 + sign);
+        }
+    }
+    end
+}
+
+/// Split text into tokens, each with the byte position it starts at.
+fn tokenize(text: &str) -> DeltaResult<Vec<(usize, Token)>> {
+    let error = |position: usize, message: &str| {
+        Error::invalid_expression(format!("{message} at position {position} of '{text}'"))
+    };
+    // the text quoted by `quote` at `start`, and the position after the closing quote
+    let quoted = |start: usize, quote: char| -> DeltaResult<(String, usize)> {
+        let mut value = String::new();
+        let mut chars = text[start + 1..].char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != quote {
+                value.push(c);
+            } else if chars.next_if(|(_, next)| *next == quote).is_some() {
+                value.push(quote);
+            } else {
+                return Ok((value, start + 1 + i + 1));
+            }
+        }
+        Err(error(start, "unterminated quote"))
+    };
+
+    let mut tokens = vec![];
+    let mut position = 0;
+    while let Some(c) = text[position..].chars().next() {
+        let rest = &text[position..];
+        let start = position;
+        let token = if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        } else if c == '\'' {
+            let (value, end) = quoted(position, '\'')?;
+            position = end;
+            Token::String(value)
+        } else if c == '`' {
+            let (value, end) = quoted(position, '`')?;
+            position = end;
+            Token::QuotedName(value)
+        } else if (c == 'X' || c == 'x') && rest[1..].starts_with('\'') {
+            let (hex, end) = quoted(position + 1, '\'')?;
+            position = end;
+            Token::Binary(decode_hex(&hex).ok_or_else(|| error(start, "invalid binary value"))?)
+        } else if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let number_end = number_length(rest);
+            let suffix_end = number_end
+                + rest[number_end..]
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len() - number_end);
+            position += suffix_end;
+            Token::Number(
+                rest[..number_end].to_string(),
+                rest[number_end..suffix_end].to_string(),
+            )
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            position += end;
+            Token::Word(rest[..end].to_string())
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+            position += symbol.len();
+            Token::Symbol(symbol)
+        } else {
+            return Err(error(position, &format!("unexpected character '{c}'")));
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(usize, Token)>,
+    /// The index of the next token
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> DeltaResult<Self> {
+        Ok(Self {
+            text,
+            tokens: tokenize(text)?,
+            next: 0,
+        })
+    }
+
+    /// An error at the position of the next token.
+    fn error(&self, message: impl std::fmt::Display) -> Error {
+        match self.tokens.get(self.next) {
+            Some((position, _)) => Error::invalid_expression(format!(
+                "{message} at position {position} of '{}'",
+                self.text
+            )),
+            None => Error::invalid_expression(format!("{message} at the end of '{}'", self.text)),
+        }
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.next + offset).map(|(_, token)| token)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn is_keyword_at(&self, offset: usize, keyword: &str) -> bool {
+        matches!(self.peek_at(offset), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword_at(0, keyword);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> DeltaResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {keyword}")))
+        }
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.is_symbol(symbol);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> DeltaResult<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{symbol}'")))
+        }
+    }
+
+    fn expect_string(&mut self) -> DeltaResult<String> {
+        match self.peek() {
+            Some(Token::String(value)) => {
+                let value = value.clone();
+                self.next += 1;
+                Ok(value)
+            }
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    fn expect_integer<T: FromStr>(&mut self) -> DeltaResult<T> {
+        let value = match self.peek() {
+            Some(Token::Number(digits, suffix)) if suffix.is_empty() => digits.parse().ok(),
+            _ => None,
+        };
+        let value = value.ok_or_else(|| self.error("expected an integer"))?;
+        self.next += 1;
+        Ok(value)
+    }
+
+    /// Parse a whole expression, which must be followed by the end of the text.
+    fn parse(mut self) -> DeltaResult<Expression> {
+        let expr = self.parse_or()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(_) => Err(self.error("unexpected token")),
+        }
+    }
+
+    /// Parse operands separated by `keyword`, which become the operands of `op`.
+    fn parse_variadic(
+        &mut self,
+        op: VariadicOperator,
+        keyword: &str,
+        parse_operand: fn(&mut Self) -> DeltaResult<Expression>,
+    ) -> DeltaResult<Expression> {
+        let mut exprs = vec![parse_operand(self)?];
+        while self.eat_keyword(keyword) {
+            exprs.push(parse_operand(self)?);
+        }
+        match exprs.len() {
+            1 => Ok(exprs.remove(0)),
+            _ => Ok(Expression::variadic(op, exprs)),
+        }
+    }
+
+    fn parse_or(&mut self) -> DeltaResult<Expression> {
+        self.parse_variadic(VariadicOperator::Or, "OR", Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> DeltaResult<Expression> {
+        self.parse_variadic(VariadicOperator::And, "AND", Self::parse_not)
+    }
+
+    fn parse_not(&mut self) -> DeltaResult<Expression> {
+        if self.eat_keyword("NOT") {
+            Ok(!self.parse_not()?)
+        } else {
+            self.parse_predicate()
+        }
+    }
+
+    fn comparison_operator(&self) -> Option<BinaryOperator> {
+        let Some(Token::Symbol(symbol)) = self.peek() else {
+            return None;
+        };
+        let op = match *symbol {
+            "=" | "==" => BinaryOperator::Equal,
+            "!=" | "<>" => BinaryOperator::NotEqual,
+            "<" => BinaryOperator::LessThan,
+            "<=" => BinaryOperator::LessThanOrEqual,
+            ">" => BinaryOperator::GreaterThan,
+            ">=" => BinaryOperator::GreaterThanOrEqual,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn parse_predicate(&mut self) -> DeltaResult<Expression> {
+        let left = self.parse_additive()?;
+        if let Some(op) = self.comparison_operator() {
+            self.next += 1;
+            let right = self.parse_additive()?;
+            return Ok(Expression::binary(op, left, right));
+        }
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            let expr = if self.eat_keyword("NULL") {
+                left.is_null()
+            } else {
+                self.expect_keyword("DISTINCT")?;
+                self.expect_keyword("FROM")?;
+                left.distinct(self.parse_additive()?)
+            };
+            return Ok(if negated { !expr } else { expr });
+        }
+
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("IN") {
+            let list = self.parse_arguments()?;
+            Ok(Expression::InList {
+                expr: Box::new(left),
+                list,
+                negated,
+            })
+        } else if self.eat_keyword("LIKE") {
+            let pattern = self.expect_string()?;
+            let escape = if self.eat_keyword("ESCAPE") {
+                let escape = self.expect_string()?;
+                match escape.chars().exactly_one() {
+                    Ok(escape) => Some(escape),
+                    Err(_) => return Err(self.error("expected a single escape character")),
+                }
+            } else {
+                None
+            };
+            Ok(Expression::Like {
+                expr: Box::new(left),
+                pattern,
+                escape,
+                negated,
+            })
+        } else if negated {
+            Err(self.error("expected IN or LIKE"))
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn parse_additive(&mut self) -> DeltaResult<Expression> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = if self.eat_symbol("+") {
+                BinaryOperator::Plus
+            } else if self.eat_symbol("-") {
+                BinaryOperator::Minus
+            } else {
+                return Ok(left);
+            };
+            left = Expression::binary(op, left, self.parse_multiplicative()?);
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> DeltaResult<Expression> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = if self.eat_symbol("*") {
+                BinaryOperator::Multiply
+            } else if self.eat_symbol("/") {
+                BinaryOperator::Divide
+            } else {
+                return Ok(left);
+            };
+            left = Expression::binary(op, left, self.parse_unary()?);
+        }
+    }
+
+    /// Parse a primary expression, or a negative number.
+    fn parse_unary(&mut self) -> DeltaResult<Expression> {
+        if !self.eat_symbol("-") {
+            return self.parse_primary();
+        }
+        match self.peek() {
+            Some(Token::Number(digits, suffix)) => {
+                let number = self.parse_number(&format!("-{digits}"), suffix)?;
+                self.next += 1;
+                Ok(Expression::Literal(number))
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    fn parse_primary(&mut self) -> DeltaResult<Expression> {
+        let expr = match self.peek().cloned() {
+            Some(Token::Number(digits, suffix)) => {
+                Expression::Literal(self.parse_number(&digits, &suffix)?)
+            }
+            Some(Token::String(value)) => Expression::literal(value),
+            Some(Token::Binary(value)) => Expression::Literal(Scalar::Binary(value)),
+            Some(Token::Symbol("(")) => {
+                self.next += 1;
+                let expr = self.parse_or()?;
+                self.expect_symbol(")")?;
+                return Ok(expr);
+            }
+            Some(Token::QuotedName(name)) => {
+                self.next += 1;
+                return self.parse_column(name);
+            }
+            Some(Token::Word(word)) => return self.parse_word(word),
+            Some(Token::Symbol(_)) => return Err(self.error("unexpected token")),
+            None => return Err(self.error("unexpected end")),
+        };
+        self.next += 1;
+        Ok(expr)
+    }
+
+    /// Parse an expression starting with a word: a function, a typed literal, a keyword literal or
+    /// a column.
+    fn parse_word(&mut self, word: String) -> DeltaResult<Expression> {
+        let name = word.to_ascii_uppercase();
+        let is_typed_literal = (primitive_type(&name).is_some()
+            && matches!(self.peek_at(1), Some(Token::String(_))))
+            || (name == "DECIMAL" && matches!(self.peek_at(1), Some(Token::Symbol("("))));
+        if is_typed_literal {
+            let data_type = self.parse_type()?;
+            let value = self.expect_string()?;
+            return self.parse_typed_literal(data_type, value);
+        }
+        if matches!(self.peek_at(1), Some(Token::Symbol("("))) {
+            return self.parse_function(&name);
+        }
+
+        let literal = match name.as_str() {
+            "TRUE" => Scalar::Boolean(true),
+            "FALSE" => Scalar::Boolean(false),
+            "NULL" => Scalar::Null(DataType::BOOLEAN),
+            _ if is_keyword(&name) => return Err(self.error(format!("unexpected keyword {word}"))),
+            _ => {
+                self.next += 1;
+                return self.parse_column(word);
+            }
+        };
+        self.next += 1;
+        Ok(Expression::Literal(literal))
+    }
+
+    /// Parse the rest of a column name whose first field name is `first`.
+    fn parse_column(&mut self, first: String) -> DeltaResult<Expression> {
+        let mut path = vec![first];
+        while self.eat_symbol(".") {
+            match self.peek() {
+                Some(Token::Word(name) | Token::QuotedName(name)) => path.push(name.clone()),
+                _ => return Err(self.error("expected a field name")),
+            }
+            self.next += 1;
+        }
+        Ok(Expression::Column(ColumnName::new(path)))
+    }
+
+    /// Parse a parenthesized, comma separated list of expressions.
+    fn parse_arguments(&mut self) -> DeltaResult<Vec<Expression>> {
+        self.expect_symbol("(")?;
+        let mut args = vec![];
+        if self.eat_symbol(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_or()?);
+            if self.eat_symbol(")") {
+                return Ok(args);
+            }
+            self.expect_symbol(",")?;
+        }
+    }
+
+    fn parse_function(&mut self, name: &str) -> DeltaResult<Expression> {
+        let error = self.error(format!("unknown function {name}"));
+        self.next += 1;
+        let two_arguments = |parser: &mut Self| -> DeltaResult<(Expression, Expression)> {
+            let args = parser.parse_arguments()?;
+            args.into_iter()
+                .collect_tuple()
+                .ok_or_else(|| parser.error(format!("{name} takes two arguments")))
+        };
+        match name {
+            "AND" => Ok(Expression::and_from(self.parse_arguments()?)),
+            "OR" => Ok(Expression::or_from(self.parse_arguments()?)),
+            "STRUCT" => Ok(Expression::struct_expr(self.parse_arguments()?)),
+            "DISTINCT" => {
+                let (left, right) = two_arguments(self)?;
+                Ok(left.distinct(right))
+            }
+            "STARTS_WITH" => {
+                let (expr, prefix) = two_arguments(self)?;
+                Ok(expr.starts_with(prefix))
+            }
+            "CAST" => {
+                self.expect_symbol("(")?;
+                // a null cast to a type is a null literal of the type
+                let is_null = self.is_keyword_at(0, "NULL") && self.is_keyword_at(1, "AS");
+                let expr = if is_null {
+                    self.next += 1;
+                    None
+                } else {
+                    Some(self.parse_or()?)
+                };
+                self.expect_keyword("AS")?;
+                let data_type = self.parse_type()?;
+                self.expect_symbol(")")?;
+                match expr {
+                    Some(expr) => Ok(expr.cast(DataType::Primitive(data_type))),
+                    None => Ok(Expression::Literal(Scalar::Null(DataType::Primitive(
+                        data_type,
+                    )))),
+                }
+            }
+            _ => Err(error),
+        }
+    }
+
+    fn parse_type(&mut self) -> DeltaResult<PrimitiveType> {
+        let name = match self.peek() {
+            Some(Token::Word(word)) => word.to_ascii_uppercase(),
+            _ => return Err(self.error("expected a type")),
+        };
+        if name == "DECIMAL" {
+            self.next += 1;
+            self.expect_symbol("(")?;
+            let precision = self.expect_integer()?;
+            self.expect_symbol(",")?;
+            let scale = match self.eat_symbol("-") {
+                true => -self.expect_integer::<i8>()?,
+                false => self.expect_integer()?,
+            };
+            self.expect_symbol(")")?;
+            return Ok(PrimitiveType::Decimal(precision, scale));
+        }
+        let data_type = primitive_type(&name).ok_or_else(|| self.error("unknown type"))?;
+        self.next += 1;
+        Ok(data_type)
+    }
+
+    fn parse_typed_literal(
+        &self,
+        data_type: PrimitiveType,
+        value: String,
+    ) -> DeltaResult<Expression> {
+        let scalar = match data_type {
+            PrimitiveType::String => Scalar::String(value),
+            PrimitiveType::Binary => Scalar::Binary(value.into_bytes()),
+            data_type => data_type.parse_scalar(&value)?,
+        };
+        Ok(Expression::Literal(scalar))
+    }
+
+    /// Parse a number (without any type suffix) as the type of the suffix.
+    fn parse_number(&self, number: &str, suffix: &str) -> DeltaResult<Scalar> {
+        let error = || self.error(format!("invalid number {number}{suffix}"));
+        let is_integral = !number.contains(['.', 'e', 'E']);
+        let scalar = match suffix.to_ascii_uppercase().as_str() {
+            "" if is_integral => match number.parse() {
+                Ok(value) => Scalar::Integer(value),
+                Err(_) => Scalar::Long(number.parse().map_err(|_| error())?),
+            },
+            "" | "D" => Scalar::Double(number.parse().map_err(|_| error())?),
+            "F" => Scalar::Float(number.parse().map_err(|_| error())?),
+            "L" if is_integral => Scalar::Long(number.parse().map_err(|_| error())?),
+            "S" if is_integral => Scalar::Short(number.parse().map_err(|_| error())?),
+            "Y" if is_integral => Scalar::Byte(number.parse().map_err(|_| error())?),
+            "BD" if !number.contains(['e', 'E']) => {
+                // the narrowest decimal type holding the number
+                let digits = number.trim_start_matches('-');
+                let (integral, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+                let scale = fraction.len();
+                let precision = (integral.trim_start_matches('0').len() + scale).max(1);
+                let (precision, scale) = precision
+                    .try_into()
+                    .ok()
+                    .zip(scale.try_into().ok())
+                    .ok_or_else(error)?;
+                PrimitiveType::Decimal(precision, scale)
+                    .parse_scalar(number)
+                    .map_err(|_| error())?
+            }
+            _ => return Err(error()),
+        };
+        Ok(scalar)
+    }
+}
+
+/// Parses an expression from SQL-like text, e.g. `a > 5 AND b IN ('x', 'y')`. The [`Display`] of
+/// an expression parses back to the same expression.
+///
+/// The types of literals are given by their syntax alone, e.g. `5` is an integer and `'x'` is a
+/// string. See [`Expression::parse_with_schema`] to type them by the columns they are compared
+/// with.
+///
+/// [`Display`]: std::fmt::Display
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s)?.parse()
+    }
+}
+
+impl Expression {
+    /// Parse an expression from SQL-like text (see [`FromStr`]), resolving its columns against
+    /// `schema`.
+    ///
+    /// Field names match the fields of the schema exactly or, failing that, case-insensitively,
+    /// and the columns of the expression are given the names of the fields they match. Literals
+    /// compared with a column take the type of the column where possible, e.g. in `d > '2024-01-31'`
+    /// the string is parsed as a date if `d` is a date column, and in `l = 5` the integer is
+    /// widened to a long if `l` is a long column.
+    pub fn parse_with_schema(text: &str, schema: &StructType) -> DeltaResult<Self> {
+        text.parse::<Self>()?.resolve(schema)
+    }
+
+    /// Resolve the columns of this expression against `schema`, and type the literals compared
+    /// with them.
+    fn resolve(self, schema: &StructType) -> DeltaResult<Self> {
+        let resolve_all = |exprs: Vec<Self>| -> DeltaResult<Vec<Self>> {
+            exprs.into_iter().map(|expr| expr.resolve(schema)).collect()
+        };
+        let expr = match self {
+            Self::Literal(_) => self,
+            Self::Column(name) => {
+                let (name, _) = resolve_column(schema, name.path())
+                    .ok_or_else(|| Error::missing_column(&name))?;
+                Self::Column(name)
+            }
+            Self::Struct(exprs) => Self::Struct(resolve_all(exprs)?),
+            Self::BinaryOperation { op, left, right } => {
+                let left = left.resolve(schema)?;
+                let right = right.resolve(schema)?;
+                let right = match left.column_type(schema) {
+                    Some(data_type) => right.typed_as(&data_type)?,
+                    None => right,
+                };
+                let left = match right.column_type(schema) {
+                    Some(data_type) => left.typed_as(&data_type)?,
+                    None => left,
+                };
+                Self::binary(op, left, right)
+            }
+            Self::UnaryOperation { op, expr } => Self::unary(op, expr.resolve(schema)?),
+            Self::VariadicOperation { op, exprs } => Self::variadic(op, resolve_all(exprs)?),
+            Self::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let expr = expr.resolve(schema)?;
+                let list = resolve_all(list)?;
+                let list = match expr.column_type(schema) {
+                    Some(data_type) => list
+                        .into_iter()
+                        .map(|item| item.typed_as(&data_type))
+                        .try_collect()?,
+                    None => list,
+                };
+                Self::InList {
+                    expr: Box::new(expr),
+                    list,
+                    negated,
+                }
+            }
+            Self::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => Self::Like {
+                expr: Box::new(expr.resolve(schema)?),
+                pattern,
+                escape,
+                negated,
+            },
+            Self::StartsWith { expr, prefix } => {
+                expr.resolve(schema)?.starts_with(prefix.resolve(schema)?)
+            }
+            Self::Cast { expr, data_type } => expr.resolve(schema)?.cast(data_type),
+        };
+        Ok(expr)
+    }
+
+    /// The type of a (resolved) column or cast, if it is a primitive type.
+    fn column_type(&self, schema: &StructType) -> Option<DataType> {
+        let data_type = match self {
+            Self::Column(name) => resolve_column(schema, name.path())?.1.clone(),
+            Self::Cast { data_type, .. } => data_type.clone(),
+            _ => return None,
+        };
+        matches!(data_type, DataType::Primitive(_)).then_some(data_type)
+    }
+
+    /// Give a literal the type `data_type` if it is an untyped null, a string which parses as a
+    /// value of the type, or a number which widens to the type.
+    fn typed_as(self, data_type: &DataType) -> DeltaResult<Self> {
+        let (Self::Literal(scalar), DataType::Primitive(primitive)) = (&self, data_type) else {
+            return Ok(self);
+        };
+        let scalar = match scalar {
+            Scalar::Null(_) => Scalar::Null(data_type.clone()),
+            Scalar::String(value) => match primitive {
+                PrimitiveType::String => return Ok(self),
+                PrimitiveType::Binary => Scalar::Binary(value.as_bytes().to_vec()),
+                primitive => primitive.parse_scalar(value)?,
+            },
+            scalar if is_widening_cast(&scalar.data_type(), data_type) => scalar.cast(data_type)?,
+            _ => return Ok(self),
+        };
+        Ok(Self::Literal(scalar))
+    }
+}
+
+/// Find the field at `path` in `schema`, matching field names exactly or else case-insensitively.
+/// Returns the exact name of the column and its type.
+fn resolve_column<'a>(
+    schema: &'a StructType,
+    path: &[String],
+) -> Option<(ColumnName, &'a DataType)> {
+    let (name, rest) = path.split_first()?;
+    let field = match schema.field(name) {
+        Some(field) => field,
+        None => schema
+            .fields()
+            .filter(|field| field.name().eq_ignore_ascii_case(name))
+            .exactly_one()
+            .ok()?,
+    };
+    let column = ColumnName::new([field.name()]);
+    match (rest, field.data_type()) {
+        ([], data_type) => Some((column, data_type)),
+        (rest, DataType::Struct(structure)) => {
+            let (nested, data_type) = resolve_column(structure, rest)?;
+            Some((column.join(&nested), data_type))
+        }
+        (_, _) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::StructField;
+    use Expression as Expr;
+
+    #[test]
+    fn test_parse() {
+        let a = Expr::column("a");
+        let b = Expr::column("b");
+        let c = Expr::column("c");
+        let cases = [
+            (
+                "a > 5 AND b IN ('x','y') AND c IS NOT NULL",
+                Expr::and_from([
+                    a.clone().gt(Expr::literal(5)),
+                    b.clone().in_list([Expr::literal("x"), Expr::literal("y")]),
+                    !c.clone().is_null(),
+                ]),
+            ),
+            (
+                "a = 1 OR b = 2 AND NOT c",
+                a.clone()
+                    .eq(Expr::literal(1))
+                    .or(b.clone().eq(Expr::literal(2)).and(!c.clone())),
+            ),
+            (
+                "(a = 1 OR b = 2) and not c",
+                a.clone()
+                    .eq(Expr::literal(1))
+                    .or(b.clone().eq(Expr::literal(2)))
+                    .and(!c.clone()),
+            ),
+            (
+                "a + 2 * b - c / 4 <> 0",
+                (a.clone() + Expr::literal(2) * b.clone() - c.clone() / Expr::literal(4))
+                    .ne(Expr::literal(0)),
+            ),
+            ("a IS DISTINCT FROM b", a.clone().distinct(b.clone())),
+            (
+                "a NOT LIKE 'it''s!%%' ESCAPE '!'",
+                Expr::Like {
+                    expr: Box::new(a.clone()),
+                    pattern: "it's!%%".to_string(),
+                    escape: Some('!'),
+                    negated: true,
+                },
+            ),
+            (
+                "a not in (-1, 2L)",
+                a.clone()
+                    .not_in_list([Expr::literal(-1), Expr::literal(2i64)]),
+            ),
+            (
+                "STARTS_WITH(`a.b`.c, 'x')",
+                Expr::Column(ColumnName::new(["a.b", "c"])).starts_with(Expr::literal("x")),
+            ),
+            (
+                "cast(a AS decimal(5, 2)) >= 1.5BD",
+                a.clone()
+                    .cast(DataType::decimal(5, 2))
+                    .ge(Expr::Literal(Scalar::Decimal(15, 2, 1))),
+            ),
+            (
+                "a = date '2024-01-31'",
+                a.clone().eq(Expr::Literal(Scalar::Date(19753))),
+            ),
+            (
+                "a = X'0aFF' OR a = binary 'b'",
+                a.clone()
+                    .eq(Expr::Literal(Scalar::Binary(vec![10, 255])))
+                    .or(a.clone().eq(Expr::Literal(Scalar::Binary(vec![b'b'])))),
+            ),
+            (
+                "a = NULL",
+                a.clone().eq(Expr::Literal(Scalar::Null(DataType::BOOLEAN))),
+            ),
+            (
+                "a = 3000000000 AND b = 1.5 AND c = 1e3F",
+                Expr::and_from([
+                    a.clone().eq(Expr::literal(3_000_000_000i64)),
+                    b.clone().eq(Expr::Literal(Scalar::Double(1.5))),
+                    c.clone().eq(Expr::Literal(Scalar::Float(1000.0))),
+                ]),
+            ),
+        ];
+        for (text, expected) in cases {
+            let expr: Expression = text.parse().unwrap();
+            assert_eq!(expr, expected, "{text}");
+        }
+
+        let errors = [
+            "",
+            "a >",
+            "a = 'x",
+            "(a = 1",
+            "a = 1)",
+            "a < b < c",
+            "a NOT b",
+            "a = 1.5L",
+            "a = 300Y",
+            "a = X'abc'",
+            "AND = 1",
+            "FOO(a)",
+            "CAST(a AS text)",
+            "a LIKE 'x' ESCAPE 'ab'",
+            "a = date 'x'",
+            "a ? b",
+        ];
+        for text in errors {
+            assert!(text.parse::<Expression>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let a = Expr::column("a");
+        let b = Expr::column("b");
+        let exprs = [
+            (a.clone() - (b.clone() - Expr::literal(1))) * Expr::literal(2),
+            a.clone() / (b.clone() * Expr::literal(3i64)),
+            !(a.clone().is_null().is_null()),
+            (!a.clone()).eq(b.clone().lt(Expr::literal(1))),
+            (a.clone() + Expr::literal(1)).in_list([Expr::literal(-1), Expr::literal(i64::MIN)]),
+            a.clone().like("it's").or(Expr::Like {
+                expr: Box::new(a.clone()),
+                pattern: "%'%".to_string(),
+                escape: Some('\''),
+                negated: true,
+            }),
+            Expr::struct_expr([a.clone(), Expr::literal(true), Expr::literal(1i16)]),
+            Expr::and_from([]),
+            a.clone().and(b.clone()).and(
+                !a.clone()
+                    .distinct(Expr::Literal(Scalar::Null(DataType::LONG))),
+            ),
+            Expr::column(ColumnName::new(["and", "a b", "`", "_c1"]))
+                .starts_with(Expr::literal("x")),
+            Expr::column("a.b").cast(DataType::decimal(10, -2)),
+            Expr::Literal(Scalar::Decimal(-12345, 7, 3)).eq(Expr::Literal(Scalar::Byte(-1))),
+            Expr::Literal(Scalar::Float(1.5e-7)).lt(Expr::Literal(Scalar::Double(-1e300))),
+            Expr::Literal(Scalar::Double(f64::INFINITY)).gt(Expr::Literal(Scalar::Float(0.0))),
+            Expr::Literal(Scalar::Timestamp(-1)).gt(Expr::Literal(Scalar::Date(-1))),
+            Expr::Literal(Scalar::Binary(vec![0, 1, 254])).ne(Expr::literal("")),
+        ];
+        for expr in exprs {
+            let text = expr.to_string();
+            let parsed: Expression = text.parse().unwrap_or_else(|err| panic!("{text}: {err}"));
+            assert_eq!(parsed, expr, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_parse_with_schema() {
+        let schema = StructType::new(vec![
+            StructField::new("Id", DataType::LONG, false),
+            StructField::new("day", DataType::DATE, true),
+            StructField::new("price", DataType::decimal(5, 2), true),
+            StructField::new("blob", DataType::BINARY, true),
+            StructField::new(
+                "nested",
+                StructType::new(vec![StructField::new("Name", DataType::STRING, true)]),
+                true,
+            ),
+        ]);
+        let cases = [
+            (
+                "id > 5 AND ID IS NOT NULL",
+                Expr::column("Id")
+                    .gt(Expr::literal(5i64))
+                    .and(!Expr::column("Id").is_null()),
+            ),
+            (
+                "'2024-01-31' <= day",
+                Expr::Literal(Scalar::Date(19753)).le(Expr::column("day")),
+            ),
+            (
+                "price IN ('1.50', 2, NULL)",
+                Expr::column("price").in_list([
+                    Expr::Literal(Scalar::Decimal(150, 5, 2)),
+                    Expr::literal(2),
+                    Expr::Literal(Scalar::Null(DataType::decimal(5, 2))),
+                ]),
+            ),
+            (
+                "blob = 'ab'",
+                Expr::column("blob").eq(Expr::Literal(Scalar::Binary(vec![b'a', b'b']))),
+            ),
+            (
+                "NESTED.name LIKE 'a%' AND nested.Name = 'b'",
+                Expr::column("nested.Name")
+                    .like("a%")
+                    .and(Expr::column("nested.Name").eq(Expr::literal("b"))),
+            ),
+        ];
+        for (text, expected) in cases {
+            let expr = Expression::parse_with_schema(text, &schema).unwrap();
+            assert_eq!(expr, expected, "{text}");
+        }
+
+        for text in ["missing = 1", "nested.missing = 1", "id.x = 1", "day = 'x'"] {
+            assert!(
+                Expression::parse_with_schema(text, &schema).is_err(),
+                "{text}"
+            );
+        }
+    }
+}
@@ -2,13 +2,14 @@ use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 
 use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::schema::{DataType, PrimitiveType};
 use crate::Error;
 
 /// A single value, which can be null. Used for representing literal values
 /// in [Expressions][crate::expressions::Expression].
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Scalar {
     Integer(i32),
     Long(i64),
@@ -68,13 +69,16 @@ impl Display for Scalar {
                     write!(f, "{}", value)
                 }
                 Ordering::Greater => {
-                    let scalar_multiple = 10_i128.pow(*scale as u32);
-                    write!(f, "{}", value / scalar_multiple)?;
+                    let scalar_multiple = 10_u128.pow(*scale as u32);
+                    if *value < 0 {
+                        write!(f, "-")?;
+                    }
+                    write!(f, "{}", value.unsigned_abs() / scalar_multiple)?;
                     write!(f, ".")?;
                     write!(
                         f,
                         "{:0>scale$}",
-                        value % scalar_multiple,
+                        value.unsigned_abs() % scalar_multiple,
                         scale = *scale as usize
                     )
                 }
@@ -135,6 +139,10 @@ impl From<String> for Scalar {
 
 // TODO: add more From impls
 
+lazy_static::lazy_static! {
+    static ref UNIX_EPOCH: DateTime<Utc> = DateTime::from_timestamp(0, 0).unwrap();
+}
+
 impl PrimitiveType {
     fn data_type(&self) -> DataType {
         DataType::Primitive(self.clone())
@@ -143,10 +151,6 @@ impl PrimitiveType {
     pub fn parse_scalar(&self, raw: &str) -> Result<Scalar, Error> {
         use PrimitiveType::*;
 
-        lazy_static::lazy_static! {
-            static ref UNIX_EPOCH: DateTime<Utc> = DateTime::from_timestamp(0, 0).unwrap();
-        }
-
         if raw.is_empty() {
             return Ok(Scalar::Null(self.data_type()));
         }
@@ -177,18 +181,67 @@ impl PrimitiveType {
                 let days = date.signed_duration_since(*UNIX_EPOCH).num_days() as i32;
                 Ok(Scalar::Date(days))
             }
-            Timestamp => {
-                let timestamp = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f")
-                    .map_err(|_| self.parse_error(raw))?;
-                let timestamp = Utc.from_utc_datetime(&timestamp);
-                let micros = timestamp
-                    .signed_duration_since(*UNIX_EPOCH)
-                    .num_microseconds()
-                    .ok_or(self.parse_error(raw))?;
-                Ok(Scalar::Timestamp(micros))
-            }
-            _ => todo!(),
+            Timestamp => self.parse_timestamp(raw),
+            Decimal(precision, scale) => self.parse_decimal(raw, *precision, *scale),
+            // binary values are escaped as a string with a character for each byte
+            Binary => raw
+                .chars()
+                .map(|c| u8::try_from(c).ok())
+                .collect::<Option<Vec<u8>>>()
+                .map(Scalar::Binary)
+                .ok_or_else(|| self.parse_error(raw)),
+        }
+    }
+
+    /// Parse a timestamp in one of the formats Spark writes: `2024-01-31 12:00:00[.123456]`,
+    /// which is in UTC, or ISO 8601 with an offset, e.g. `2024-01-31T12:00:00.123456Z`. Digits
+    /// beyond microseconds are truncated.
+    fn parse_timestamp(&self, raw: &str) -> Result<Scalar, Error> {
+        let timestamp = DateTime::parse_from_rfc3339(raw)
+            .map(|timestamp| timestamp.naive_utc())
+            .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f"))
+            .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f"))
+            .map_err(|_| self.parse_error(raw))?;
+        let micros = Utc
+            .from_utc_datetime(&timestamp)
+            .signed_duration_since(*UNIX_EPOCH)
+            .num_microseconds()
+            .ok_or_else(|| self.parse_error(raw))?;
+        Ok(Scalar::Timestamp(micros))
+    }
+
+    /// Parse a decimal number, e.g. `-12.30`, as a decimal of the given precision and scale. The
+    /// number may not have more (non-zero) fractional digits than the scale, nor more digits than
+    /// the precision.
+    fn parse_decimal(&self, raw: &str, precision: u8, scale: i8) -> Result<Scalar, Error> {
+        let error = || self.parse_error(raw);
+        let (negative, digits) = match raw.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, raw.strip_prefix('+').unwrap_or(raw)),
+        };
+        let (integral, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_number = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if integral.len() + fraction.len() == 0 || !is_number(integral) || !is_number(fraction) {
+            return Err(error());
+        }
+        let unscaled: i128 = format!("{integral}{fraction}")
+            .parse()
+            .map_err(|_| error())?;
+        // shift the digits to the scale, which must not drop any but zeros
+        let shift = scale as i32 - fraction.len() as i32;
+        let factor = 10i128.checked_pow(shift.unsigned_abs()).ok_or_else(error)?;
+        let value = if shift >= 0 {
+            unscaled.checked_mul(factor).ok_or_else(error)?
+        } else if unscaled % factor == 0 {
+            unscaled / factor
+        } else {
+            return Err(error());
+        };
+        if value >= 10i128.checked_pow(precision as u32).ok_or_else(error)? {
+            return Err(error());
         }
+        let value = if negative { -value } else { value };
+        Ok(Scalar::Decimal(value, precision, scale))
     }
 
     fn parse_error(&self, raw: &str) -> Error {
@@ -228,5 +281,78 @@ mod tests {
 
         let s = Scalar::Decimal(123, 9, -3);
         assert_eq!(s.to_string(), "123000");
+
+        let s = Scalar::Decimal(-12345, 5, 3);
+        assert_eq!(s.to_string(), "-12.345");
+    }
+
+    #[test]
+    fn test_parse_scalar() {
+        let cases = [
+            (
+                "2024-01-31T12:00:00.5Z",
+                PrimitiveType::Timestamp,
+                Scalar::Timestamp(1_706_702_400_500_000),
+            ),
+            (
+                "2024-01-31T13:00:00+01:00",
+                PrimitiveType::Timestamp,
+                Scalar::Timestamp(1_706_702_400_000_000),
+            ),
+            (
+                "2024-01-31T12:00:00",
+                PrimitiveType::Timestamp,
+                Scalar::Timestamp(1_706_702_400_000_000),
+            ),
+            (
+                "\u{1}\u{2}\u{ff}",
+                PrimitiveType::Binary,
+                Scalar::Binary(vec![1, 2, 255]),
+            ),
+            ("", PrimitiveType::Binary, Scalar::Null(DataType::BINARY)),
+        ];
+        for (raw, data_type, expected) in cases {
+            assert_eq!(data_type.parse_scalar(raw).unwrap(), expected, "{raw}");
+        }
+
+        let errors = [
+            ("2024-01-31", PrimitiveType::Timestamp),
+            ("12:00:00", PrimitiveType::Timestamp),
+            ("\u{100}", PrimitiveType::Binary),
+            ("1.5", PrimitiveType::Integer),
+        ];
+        for (raw, data_type) in errors {
+            assert!(
+                matches!(data_type.parse_scalar(raw), Err(Error::ParseError(..))),
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        let cases = [
+            ("1234567.89", 9, 2, Some(123456789)),
+            ("-12.3", 5, 2, Some(-1230)),
+            ("12", 5, 2, Some(1200)),
+            ("1.500", 5, 2, Some(150)),
+            (".5", 2, 1, Some(5)),
+            ("123000", 9, -3, Some(123)),
+            ("1.234", 5, 2, None),
+            ("1000", 5, 2, None),
+            ("123400", 9, -3, None),
+            ("1.2.3", 5, 2, None),
+            ("-", 5, 2, None),
+            ("1e5", 9, 2, None),
+        ];
+        for (raw, precision, scale, expected) in cases {
+            let parsed = PrimitiveType::Decimal(precision, scale).parse_scalar(raw);
+            match expected {
+                Some(value) => {
+                    assert_eq!(parsed.unwrap(), Scalar::Decimal(value, precision, scale))
+                }
+                None => assert!(parsed.is_err(), "{raw}"),
+            }
+        }
     }
 }
@@ -0,0 +1,505 @@
+//! Simplification of [`Expression`]s, which makes (often machine-generated) predicates cheaper to
+//! evaluate and more of them eligible for data skipping.
+
+use std::cmp::Ordering;
+
+use super::eval::compare_values;
+use super::{BinaryOperator, ColumnName, Expression, Scalar, UnaryOperator, VariadicOperator};
+use crate::schema::DataType;
+
+impl Expression {
+    /// Simplify this expression without changing its result on any row:
+    /// - nested ANDs and ORs are flattened, e.g. `AND(a, AND(b, c))` becomes `AND(a, b, c)`, and
+    ///   duplicate operands and literals which do not affect the result are removed
+    /// - parts which do not reference any column are folded into their value, e.g. `1 + 2`
+    ///   becomes `3` and `AND(a, false)` becomes `false`
+    /// - NOT is pushed down through AND and OR (De Morgan's laws) and into comparisons, IN and
+    ///   LIKE, e.g. `NOT (a < 1 OR NOT b)` becomes `AND(a >= 1, b)`
+    /// - comparisons with a null literal become null, and `DISTINCT(x, x)` becomes false
+    ///
+    /// Parts which fail to evaluate, e.g. `1 / 0`, are left as they are.
+    pub fn simplify(self) -> Self {
+        use Expression::*;
+
+        let expr = match self {
+            Literal(_) | Column(_) => return self,
+            Struct(exprs) => return Struct(exprs.into_iter().map(Self::simplify).collect()),
+            UnaryOperation {
+                op: UnaryOperator::Not,
+                expr,
+            } => negate(expr.simplify()),
+            UnaryOperation { op, expr } => Self::unary(op, expr.simplify()),
+            BinaryOperation { op, left, right } => {
+                simplify_binary(op, left.simplify(), right.simplify())
+            }
+            VariadicOperation { op, exprs } => {
+                simplify_variadic(op, exprs.into_iter().map(Self::simplify))
+            }
+            InList {
+                expr,
+                list,
+                negated,
+            } => InList {
+                expr: Box::new(expr.simplify()),
+                list: list.into_iter().map(Self::simplify).collect(),
+                negated,
+            },
+            Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => Like {
+                expr: Box::new(expr.simplify()),
+                pattern,
+                escape,
+                negated,
+            },
+            StartsWith { expr, prefix } => expr.simplify().starts_with(prefix.simplify()),
+            Cast { expr, data_type } => expr.simplify().cast(data_type),
+        };
+        fold_constant(expr)
+    }
+
+    /// Simplify this expression as a predicate, i.e. where rows for which it is null are dropped
+    /// like those for which it is false. In addition to [`Expression::simplify`]:
+    /// - `x = x` becomes `NOT x IS NULL`, and `x < x` becomes false
+    /// - contradictions become false, e.g. `AND(a, NOT a)`, `AND(x > 2, x < 1)` or
+    ///   `AND(x IS NULL, x = 1)`
+    ///
+    /// A predicate which is false (or null) for all rows becomes the literal `false`.
+    pub fn simplify_predicate(self) -> Self {
+        simplify_predicate(self.simplify())
+    }
+}
+
+/// Evaluate an expression which does not reference any column to a literal. Other expressions,
+/// and those which fail to evaluate, are returned as they are.
+fn fold_constant(expr: Expression) -> Expression {
+    if matches!(expr, Expression::Literal(_) | Expression::Struct(_))
+        || !expr.references().is_empty()
+    {
+        return expr;
+    }
+    match expr.evaluate_scalar(&|_| None) {
+        Ok(Some(value)) => Expression::Literal(value),
+        _ => expr,
+    }
+}
+
+/// Get the comparison which is true exactly when `op` is false (for non-null operands).
+fn negate_comparison(op: &BinaryOperator) -> Option<BinaryOperator> {
+    use BinaryOperator::*;
+    match op {
+        LessThan => Some(GreaterThanOrEqual),
+        LessThanOrEqual => Some(GreaterThan),
+        GreaterThan => Some(LessThanOrEqual),
+        GreaterThanOrEqual => Some(LessThan),
+        Equal => Some(NotEqual),
+        NotEqual => Some(Equal),
+        _ => None,
+    }
+}
+
+/// Negate a simplified expression, pushing the NOT down as far as possible.
+fn negate(expr: Expression) -> Expression {
+    use Expression::*;
+
+    match expr {
+        UnaryOperation {
+            op: UnaryOperator::Not,
+            expr,
+        } => *expr,
+        BinaryOperation { op, left, right } => match negate_comparison(&op) {
+            Some(op) => BinaryOperation { op, left, right },
+            None => fold_constant(!BinaryOperation { op, left, right }),
+        },
+        VariadicOperation { op, exprs } => {
+            let op = match op {
+                VariadicOperator::And => VariadicOperator::Or,
+                VariadicOperator::Or => VariadicOperator::And,
+            };
+            simplify_variadic(op, exprs.into_iter().map(negate))
+        }
+        InList {
+            expr,
+            list,
+            negated,
+        } => InList {
+            expr,
+            list,
+            negated: !negated,
+        },
+        Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+        } => Like {
+            expr,
+            pattern,
+            escape,
+            negated: !negated,
+        },
+        expr => fold_constant(!expr),
+    }
+}
+
+/// Simplify a binary operation on simplified operands.
+fn simplify_binary(op: BinaryOperator, left: Expression, right: Expression) -> Expression {
+    use BinaryOperator::*;
+
+    let is_null = |expr: &Expression| matches!(expr, Expression::Literal(Scalar::Null(_)));
+    match op {
+        LessThan | LessThanOrEqual | GreaterThan | GreaterThanOrEqual | Equal | NotEqual
+            if is_null(&left) || is_null(&right) =>
+        {
+            Expression::literal(Scalar::Null(DataType::BOOLEAN))
+        }
+        Distinct if left == right => Expression::literal(false),
+        op => Expression::binary(op, left, right),
+    }
+}
+
+/// Simplify a variadic operation on simplified operands, which are flattened into it if they are
+/// the same operation.
+fn simplify_variadic(
+    op: VariadicOperator,
+    exprs: impl IntoIterator<Item = Expression>,
+) -> Expression {
+    // the value that decides the result on its own, e.g. false for AND
+    let dominant = matches!(op, VariadicOperator::Or);
+    let mut operands: Vec<Expression> = vec![];
+    for expr in exprs {
+        let nested = match expr {
+            Expression::VariadicOperation {
+                op: nested_op,
+                exprs,
+            } if nested_op == op => exprs,
+            expr => vec![expr],
+        };
+        for expr in nested {
+            match expr {
+                Expression::Literal(Scalar::Boolean(b)) if b == dominant => {
+                    return Expression::literal(dominant)
+                }
+                Expression::Literal(Scalar::Boolean(_)) => {}
+                expr if !operands.contains(&expr) => operands.push(expr),
+                _ => {}
+            }
+        }
+    }
+    match operands.len() {
+        0 => Expression::literal(!dominant),
+        1 => operands.remove(0),
+        _ => Expression::variadic(op, operands),
+    }
+}
+
+/// Simplify a simplified expression as a predicate, see [`Expression::simplify_predicate`]. Null
+/// can only be taken for false through AND and OR, so this does not look into other operations.
+fn simplify_predicate(expr: Expression) -> Expression {
+    use BinaryOperator::*;
+    use Expression::*;
+
+    match expr {
+        Literal(Scalar::Null(_)) => Expression::literal(false),
+        BinaryOperation {
+            op: Equal | LessThanOrEqual | GreaterThanOrEqual,
+            left,
+            right,
+        } if left == right => !(*left).is_null(),
+        BinaryOperation {
+            op: NotEqual | LessThan | GreaterThan,
+            left,
+            right,
+        } if left == right => Expression::literal(false),
+        VariadicOperation { op, exprs } => {
+            match simplify_variadic(op, exprs.into_iter().map(simplify_predicate)) {
+                VariadicOperation {
+                    op: VariadicOperator::And,
+                    exprs,
+                } if is_contradiction(&exprs) => Expression::literal(false),
+                Literal(Scalar::Null(_)) => Expression::literal(false),
+                expr => expr,
+            }
+        }
+        expr => expr,
+    }
+}
+
+/// The values of a column allowed by a comparison with a literal, as the lower and upper bounds
+/// of a range and whether they are included in it.
+struct ColumnRange<'a> {
+    column: &'a ColumnName,
+    lower: Option<(&'a Scalar, bool)>,
+    upper: Option<(&'a Scalar, bool)>,
+}
+
+impl<'a> ColumnRange<'a> {
+    /// Get the range of a comparison between a column and a (non-null) literal, if it is one.
+    fn try_new(expr: &'a Expression) -> Option<Self> {
+        use BinaryOperator::*;
+
+        let Expression::BinaryOperation { op, left, right } = expr else {
+            return None;
+        };
+        let (op, column, value) = match (left.as_ref(), right.as_ref()) {
+            (Expression::Column(column), Expression::Literal(value)) => (op.clone(), column, value),
+            (Expression::Literal(value), Expression::Column(column)) => {
+                let op = match op {
+                    LessThan => GreaterThan,
+                    LessThanOrEqual => GreaterThanOrEqual,
+                    GreaterThan => LessThan,
+                    GreaterThanOrEqual => LessThanOrEqual,
+                    op => op.clone(),
+                };
+                (op, column, value)
+            }
+            _ => return None,
+        };
+        if value.is_null() {
+            return None;
+        }
+        let (lower, upper) = match op {
+            LessThan => (None, Some((value, false))),
+            LessThanOrEqual => (None, Some((value, true))),
+            GreaterThan => (Some((value, false)), None),
+            GreaterThanOrEqual => (Some((value, true)), None),
+            Equal => (Some((value, true)), Some((value, true))),
+            _ => return None,
+        };
+        Some(Self {
+            column,
+            lower,
+            upper,
+        })
+    }
+
+    /// Whether no value is in both ranges, which are of the same column.
+    fn is_disjoint(&self, other: &Self) -> bool {
+        let below =
+            |upper: Option<(&Scalar, bool)>, lower: Option<(&Scalar, bool)>| match (upper, lower) {
+                (Some((upper, upper_included)), Some((lower, lower_included))) => {
+                    match compare_values(upper, lower) {
+                        Some(Ordering::Less) => true,
+                        Some(Ordering::Equal) => !(upper_included && lower_included),
+                        _ => false,
+                    }
+                }
+                _ => false,
+            };
+        below(self.upper, other.lower) || below(other.upper, self.lower)
+    }
+}
+
+/// Whether the operands of an AND can never all be true.
+fn is_contradiction(exprs: &[Expression]) -> bool {
+    // `a` and `NOT a`
+    let negated_operand = exprs.iter().any(|expr| match expr {
+        Expression::UnaryOperation {
+            op: UnaryOperator::Not,
+            expr,
+        } => exprs.contains(expr),
+        _ => false,
+    });
+    if negated_operand {
+        return true;
+    }
+
+    // a comparison is null if the column is null
+    let null_columns: Vec<_> = exprs
+        .iter()
+        .filter_map(|expr| match expr {
+            Expression::UnaryOperation {
+                op: UnaryOperator::IsNull,
+                expr,
+            } => match expr.as_ref() {
+                Expression::Column(column) => Some(column),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let ranges: Vec<_> = exprs.iter().filter_map(ColumnRange::try_new).collect();
+    if ranges
+        .iter()
+        .any(|range| null_columns.contains(&range.column))
+    {
+        return true;
+    }
+
+    // ranges of a column which do not overlap, which suffices as an intersection of ranges is
+    // only empty if two of them are disjoint
+    ranges.iter().enumerate().any(|(i, range)| {
+        ranges[i + 1..]
+            .iter()
+            .any(|other| range.column == other.column && range.is_disjoint(other))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::data_skipping::as_data_skipping_predicate;
+
+    #[test]
+    fn test_simplify() {
+        let a = Expression::column("a");
+        let b = Expression::column("b");
+        let x = Expression::column("x");
+        let null = Expression::literal(Scalar::Null(DataType::INTEGER));
+        let cases = [
+            (
+                Expression::and_from([
+                    a.clone(),
+                    Expression::and_from([b.clone(), Expression::literal(true)]),
+                    a.clone(),
+                ]),
+                Expression::and_from([a.clone(), b.clone()]),
+            ),
+            (
+                a.clone().or(b.clone().or(Expression::literal(true))),
+                Expression::literal(true),
+            ),
+            (a.clone().and(Expression::literal(true)), a.clone()),
+            (
+                x.clone()
+                    .lt(Expression::literal(1) + Expression::literal(2)),
+                x.clone().lt(Expression::literal(3)),
+            ),
+            (
+                Expression::literal(1)
+                    .lt(Expression::literal(2))
+                    .and(a.clone()),
+                a.clone(),
+            ),
+            (!!a.clone(), a.clone()),
+            (
+                !(x.clone().lt(Expression::literal(1)).or(!b.clone())),
+                Expression::and_from([x.clone().ge(Expression::literal(1)), b.clone()]),
+            ),
+            (
+                !(a.clone().and(x.clone().is_null())),
+                Expression::or_from([!a.clone(), !x.clone().is_null()]),
+            ),
+            (
+                !x.clone().in_list([Expression::literal(1)]),
+                x.clone().not_in_list([Expression::literal(1)]),
+            ),
+            (!x.clone().not_like("a%"), x.clone().like("a%")),
+            (
+                x.clone().eq(null.clone()),
+                Expression::literal(Scalar::Null(DataType::BOOLEAN)),
+            ),
+            (x.clone().distinct(x.clone()), Expression::literal(false)),
+            // these are not always false, but also null
+            (x.clone().eq(x.clone()), x.clone().eq(x.clone())),
+            (
+                a.clone().and(!a.clone()),
+                Expression::and_from([a.clone(), !a.clone()]),
+            ),
+            // errors are left to evaluation
+            (
+                Expression::literal(1) / Expression::literal(0),
+                Expression::literal(1) / Expression::literal(0),
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(expr.clone().simplify(), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_simplify_predicate() {
+        let a = Expression::column("a");
+        let x = Expression::column("x");
+        let y = Expression::column("y");
+        let cases = [
+            (x.clone().eq(x.clone()), !x.clone().is_null()),
+            (x.clone().lt(x.clone()), Expression::literal(false)),
+            (
+                Expression::literal(Scalar::Null(DataType::BOOLEAN)),
+                Expression::literal(false),
+            ),
+            (a.clone().and(!a.clone()), Expression::literal(false)),
+            (
+                x.clone()
+                    .gt(Expression::literal(2))
+                    .and(x.clone().lt(Expression::literal(1))),
+                Expression::literal(false),
+            ),
+            (
+                x.clone()
+                    .ge(Expression::literal(1))
+                    .and(Expression::literal(1i64).gt(x.clone())),
+                Expression::literal(false),
+            ),
+            (
+                x.clone()
+                    .eq(Expression::literal(1))
+                    .and(x.clone().eq(Expression::literal(2))),
+                Expression::literal(false),
+            ),
+            (
+                x.clone()
+                    .is_null()
+                    .and(x.clone().eq(Expression::literal(1))),
+                Expression::literal(false),
+            ),
+            (
+                Expression::or_from([
+                    x.clone()
+                        .lt(Expression::literal(1))
+                        .and(x.clone().gt(Expression::literal(1))),
+                    x.clone()
+                        .eq(Expression::literal(Scalar::Null(DataType::INTEGER))),
+                ]),
+                Expression::literal(false),
+            ),
+            // satisfiable
+            (
+                x.clone()
+                    .ge(Expression::literal(1))
+                    .and(x.clone().le(Expression::literal(1))),
+                Expression::and_from([
+                    x.clone().ge(Expression::literal(1)),
+                    x.clone().le(Expression::literal(1)),
+                ]),
+            ),
+            (
+                x.clone()
+                    .gt(Expression::literal(2))
+                    .and(y.clone().lt(Expression::literal(1))),
+                Expression::and_from([
+                    x.clone().gt(Expression::literal(2)),
+                    y.clone().lt(Expression::literal(1)),
+                ]),
+            ),
+            (!x.clone().eq(x.clone()), Expression::literal(false)),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(expr.clone().simplify_predicate(), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_simplified_data_skipping() {
+        let x = Expression::column("x");
+        let predicates = [
+            !x.clone().lt(Expression::literal(1)),
+            !(x.clone()
+                .eq(Expression::literal(1))
+                .and(!x.clone().is_null())),
+            !(x.clone().is_null().or(x.clone().lt(Expression::literal(1)))),
+        ];
+        for predicate in predicates {
+            assert!(as_data_skipping_predicate(&predicate, &|_| None).is_none());
+            let simplified = predicate.clone().simplify_predicate();
+            assert!(
+                as_data_skipping_predicate(&simplified, &|_| None).is_some(),
+                "{predicate}"
+            );
+        }
+    }
+}
@@ -43,6 +43,8 @@ use url::Url;
 use self::schema::{DataType, SchemaRef};
 
 pub mod actions;
+mod checkpoint;
+pub mod checksum;
 pub mod engine_data;
 pub mod error;
 pub mod expressions;
@@ -51,6 +53,12 @@ pub mod scan;
 pub mod schema;
 pub mod snapshot;
 pub mod table;
+pub mod table_features;
+pub mod table_properties;
+pub mod transaction;
+
+#[cfg(test)]
+pub(crate) mod test_utils;
 
 pub use engine_data::{DataVisitor, EngineData};
 pub use error::{DeltaResult, Error};
@@ -60,7 +68,11 @@ pub use table::Table;
 #[cfg(feature = "arrow-conversion")]
 pub mod arrow_conversion;
 
-#[cfg(feature = "simple-client")]
+#[cfg(feature = "parquet")]
+mod parquet_utils;
+
+// the default client represents data with the arrow based `SimpleData` of the simple client
+#[cfg(any(feature = "simple-client", feature = "default-client"))]
 pub mod simple_client;
 
 #[cfg(feature = "default-client")]
@@ -78,12 +90,16 @@ pub type FileDataReadResult = (FileMeta, Box<dyn EngineData>);
 pub type FileDataReadResultIterator =
     Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send>;
 
+/// Data to be written, along with a selection vector of the same length. Only the rows for which
+/// the selection vector is `true` are written.
+pub type FilteredEngineData = (Box<dyn EngineData>, Vec<bool>);
+
 /// The metadata that describes an object.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileMeta {
     /// The fully qualified path to the object
     pub location: Url,
-    /// The last modified time
+    /// The last modified time, as milliseconds since the unix epoch
     pub last_modified: i64,
     /// The size in bytes of the object
     pub size: usize,
@@ -142,6 +158,13 @@ pub trait FileSystemClient: Send + Sync {
         &self,
         files: Vec<FileSlice>,
     ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>>;
+
+    /// Write `data` to the file at `path`.
+    ///
+    /// If `overwrite` is false and the file already exists, the file must be left untouched and
+    /// [`Error::FileAlreadyExists`] returned. Implementations must perform this check atomically,
+    /// since it is used to detect concurrent writers.
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()>;
 }
 
 /// Provides JSON handling functionality to Delta Kernel.
@@ -193,6 +216,30 @@ pub trait ParquetHandler: Send + Sync {
         physical_schema: SchemaRef,
         predicate: Option<Expression>,
     ) -> DeltaResult<FileDataReadResultIterator>;
+
+    /// The number of files the kernel should have open at a time when it reads files one after
+    /// another, as in [`scan::Scan::execute_iter`]. Handlers which read files in the background
+    /// can return more than one so that the next files are read while the current one is
+    /// consumed. Defaults to 1.
+    fn file_concurrency(&self) -> usize {
+        1
+    }
+
+    /// Write the selected rows of `data` to a new parquet file at `location`, replacing any
+    /// existing file, and return the [`FileMeta`] of the written file.
+    ///
+    /// # Parameters
+    ///
+    /// - `location` - Location of the file to write.
+    /// - `schema` - Schema of the file. Each batch of `data` provides (at least) the columns of
+    ///   this schema, which may be null if they are missing from the batch.
+    /// - `data` - Batches of data to write, along with the rows of each batch to write.
+    fn write_parquet_file(
+        &self,
+        location: Url,
+        schema: SchemaRef,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + '_>,
+    ) -> DeltaResult<FileMeta>;
 }
 
 /// Interface encapsulating all clients needed by the Delta Kernel in order to read the Delta table.
@@ -0,0 +1,239 @@
+//! Utilities for reading and writing parquet files with arrow, shared by the simple and default
+//! clients.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    new_null_array, Array, ArrayRef, BooleanArray, ListArray, MapArray, RecordBatch,
+    RecordBatchOptions, StructArray,
+};
+use arrow_buffer::NullBuffer;
+use arrow_schema::{
+    DataType as ArrowDataType, FieldRef, Fields, Schema as ArrowSchema, SchemaRef as ArrowSchemaRef,
+};
+use arrow_select::filter::filter_record_batch;
+use itertools::Itertools;
+use parquet::arrow::{ArrowWriter, ProjectionMask};
+use parquet::schema::types::SchemaDescriptor;
+
+use crate::schema::SchemaRef;
+use crate::simple_client::data::SimpleData;
+use crate::{DeltaResult, Error, FilteredEngineData};
+
+pub(crate) mod skipping;
+
+/// Get the mask selecting the leaf columns of a parquet file that are part of `schema`. Nested
+/// fields of structs are selected individually, lists and maps are selected as a whole.
+pub(crate) fn projection_mask(
+    parquet_schema: &SchemaDescriptor,
+    schema: &ArrowSchema,
+) -> ProjectionMask {
+    fn is_selected(path: &[String], fields: &Fields) -> bool {
+        let Some((name, rest)) = path.split_first() else {
+            return false;
+        };
+        match fields.find(name) {
+            Some((_, field)) => match field.data_type() {
+                ArrowDataType::Struct(fields) if !rest.is_empty() => is_selected(rest, fields),
+                _ => true,
+            },
+            None => false,
+        }
+    }
+    let leaves = parquet_schema
+        .columns()
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| is_selected(column.path().parts(), schema.fields()))
+        .map(|(index, _)| index);
+    ProjectionMask::leaves(parquet_schema, leaves)
+}
+
+/// Reorder the (nested) columns of a batch read with a [`projection_mask`] to the order of
+/// `schema`, filling in the columns that the file does not have with nulls. Columns that the file
+/// has keep their type as read.
+pub(crate) fn reorder_columns(
+    batch: RecordBatch,
+    schema: &ArrowSchema,
+) -> DeltaResult<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let batch = StructArray::from(batch);
+    let (fields, columns) = reorder_fields(
+        batch.fields(),
+        batch.columns(),
+        schema.fields(),
+        num_rows,
+        None,
+    )?;
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(ArrowSchema::new(fields)),
+        columns,
+        &options,
+    )?)
+}
+
+type ReorderedFields = (Vec<FieldRef>, Vec<ArrayRef>);
+
+/// Reorder the fields of a struct, where `parent_nulls` are the nulls of its ancestors.
+fn reorder_fields(
+    file_fields: &Fields,
+    file_columns: &[ArrayRef],
+    fields: &Fields,
+    num_rows: usize,
+    parent_nulls: Option<&NullBuffer>,
+) -> DeltaResult<ReorderedFields> {
+    let mut reordered = (vec![], vec![]);
+    for field in fields {
+        let Some((index, file_field)) = file_fields.find(field.name()) else {
+            let field = field.as_ref().clone().with_nullable(true);
+            reordered
+                .1
+                .push(new_null_array(field.data_type(), num_rows));
+            reordered.0.push(Arc::new(field));
+            continue;
+        };
+        let column = &file_columns[index];
+        match (column.as_struct_opt(), field.data_type()) {
+            (Some(array), ArrowDataType::Struct(fields)) => {
+                // non-nullable fields may be null where an ancestor is, which only passes the
+                // validation of the rebuilt struct if it is null there too
+                let nulls = NullBuffer::union(parent_nulls, array.nulls());
+                let (fields, columns) = reorder_fields(
+                    array.fields(),
+                    array.columns(),
+                    fields,
+                    array.len(),
+                    nulls.as_ref(),
+                )?;
+                let array = StructArray::try_new(fields.into(), columns, nulls)?;
+                let field = file_field
+                    .as_ref()
+                    .clone()
+                    .with_data_type(array.data_type().clone());
+                reordered.0.push(Arc::new(field));
+                reordered.1.push(Arc::new(array));
+            }
+            _ => {
+                reordered.0.push(file_field.clone());
+                reordered.1.push(column.clone());
+            }
+        }
+    }
+    Ok(reordered)
+}
+
+/// Write the selected rows of each batch of `data` as parquet into `writer`.
+///
+/// Each batch is conformed to `schema` first, see [`conform_column`].
+pub(crate) fn write_parquet<W: Write + Send>(
+    writer: W,
+    schema: SchemaRef,
+    data: impl Iterator<Item = DeltaResult<FilteredEngineData>>,
+) -> DeltaResult<()> {
+    let schema: ArrowSchemaRef = Arc::new(ArrowSchema::try_from(schema.as_ref())?);
+    let mut writer = ArrowWriter::try_new(writer, schema.clone(), None)?;
+    for filtered_data in data {
+        let (data, selection_vector) = filtered_data?;
+        let batch: RecordBatch = SimpleData::try_from_engine_data(data)?.into();
+        let batch = filter_record_batch(&batch, &BooleanArray::from(selection_vector))?;
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                conform_column(
+                    batch.column_by_name(field.name()),
+                    field.data_type(),
+                    batch.num_rows(),
+                )
+            })
+            .try_collect()?;
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+/// Conform `column` to `data_type`.
+///
+/// Nested struct fields are matched by name, and fields that are missing are filled with nulls.
+/// Map entries are matched by position, since writers disagree on the names of keys and values.
+/// All nested field names are taken from `data_type`.
+fn conform_column(
+    column: Option<&ArrayRef>,
+    data_type: &ArrowDataType,
+    num_rows: usize,
+) -> DeltaResult<ArrayRef> {
+    let Some(column) = column.filter(|column| *column.data_type() != ArrowDataType::Null) else {
+        return Ok(new_null_array(data_type, num_rows));
+    };
+    match data_type {
+        ArrowDataType::Struct(fields) => {
+            let array = column
+                .as_struct_opt()
+                .ok_or(Error::unexpected_column_type("Expected a struct array"))?;
+            let columns = fields
+                .iter()
+                .map(|field| {
+                    conform_column(
+                        array.column_by_name(field.name()),
+                        field.data_type(),
+                        array.len(),
+                    )
+                })
+                .try_collect()?;
+            Ok(Arc::new(StructArray::try_new(
+                fields.clone(),
+                columns,
+                array.nulls().cloned(),
+            )?))
+        }
+        ArrowDataType::List(field) => {
+            let array = column
+                .as_list_opt::<i32>()
+                .ok_or(Error::unexpected_column_type("Expected a list array"))?;
+            let values = conform_column(
+                Some(array.values()),
+                field.data_type(),
+                array.values().len(),
+            )?;
+            Ok(Arc::new(ListArray::try_new(
+                field.clone(),
+                array.offsets().clone(),
+                values,
+                array.nulls().cloned(),
+            )?))
+        }
+        ArrowDataType::Map(field, ordered) => {
+            let array = column
+                .as_map_opt()
+                .ok_or(Error::unexpected_column_type("Expected a map array"))?;
+            let ArrowDataType::Struct(entry_fields) = field.data_type() else {
+                return Err(Error::generic("Map entries must be a struct"));
+            };
+            let entries = array.entries();
+            let columns = entry_fields
+                .iter()
+                .zip(entries.columns())
+                .map(|(field, column)| {
+                    conform_column(Some(column), field.data_type(), column.len())
+                })
+                .try_collect()?;
+            let entries = StructArray::try_new(entry_fields.clone(), columns, None)?;
+            Ok(Arc::new(MapArray::try_new(
+                field.clone(),
+                array.offsets().clone(),
+                entries,
+                array.nulls().cloned(),
+                *ordered,
+            )?))
+        }
+        data_type if column.data_type() == data_type => Ok(column.clone()),
+        data_type => Err(Error::unexpected_column_type(format!(
+            "Expected {data_type}, got {}",
+            column.data_type()
+        ))),
+    }
+}
@@ -0,0 +1,284 @@
+//! Skipping of the row groups and pages of a parquet file which cannot hold rows matching a
+//! predicate, based on the min and max values of their columns in the file's metadata.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use parquet::arrow::arrow_reader::{ArrowReaderBuilder, RowSelection, RowSelector};
+use parquet::basic::{ConvertedType, LogicalType, TimeUnit};
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::page_index::index::Index;
+use parquet::file::statistics::Statistics;
+use parquet::schema::types::ColumnDescriptor;
+
+use crate::expressions::{ColumnName, Expression, Scalar};
+use crate::scan::data_skipping::as_data_skipping_predicate;
+
+/// Configure `builder` to skip the row groups and pages of the file which cannot hold rows matching
+/// `predicate`. Pages are only skipped if the page index of the file was loaded.
+pub(crate) fn skip_row_groups_and_pages<T>(
+    builder: ArrowReaderBuilder<T>,
+    predicate: &Expression,
+) -> ArrowReaderBuilder<T> {
+    let metadata = builder.metadata().clone();
+    let Some(filter) = ParquetSkippingFilter::new(&metadata, predicate) else {
+        return builder;
+    };
+    let row_groups = filter.row_groups();
+    let builder = match filter.row_selection(&row_groups) {
+        Some(selection) => builder.with_row_selection(selection),
+        None => builder,
+    };
+    builder.with_row_groups(row_groups)
+}
+
+/// Decides which row groups and pages of a parquet file may hold rows matching a predicate.
+///
+/// Like data skipping of whole files, the predicate is rewritten in terms of the min and max
+/// values of the columns it references, which are looked up in the statistics of each row group
+/// and in the page index of the file. A row group or page is only skipped if the rewritten
+/// predicate is known to be false.
+pub(crate) struct ParquetSkippingFilter<'a> {
+    metadata: &'a ParquetMetaData,
+    stats_predicate: Expression,
+    /// The index of each leaf column of the file, by path
+    columns: HashMap<Vec<String>, usize>,
+}
+
+impl<'a> ParquetSkippingFilter<'a> {
+    /// Create a filter for the file with the given metadata. Returns `None` if the predicate is
+    /// not eligible for data skipping.
+    pub(crate) fn new(metadata: &'a ParquetMetaData, predicate: &Expression) -> Option<Self> {
+        // the types of the columns are not known up front, so casts are not eligible
+        let stats_predicate = as_data_skipping_predicate(predicate, &|_| None)?;
+        let columns = metadata
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(index, column)| (column.path().parts().to_vec(), index))
+            .collect();
+        Some(Self {
+            metadata,
+            stats_predicate,
+            columns,
+        })
+    }
+
+    /// Get the indices of the row groups which may hold matching rows.
+    pub(crate) fn row_groups(&self) -> Vec<usize> {
+        self.metadata
+            .row_groups()
+            .iter()
+            .enumerate()
+            .filter(|(_, row_group)| {
+                self.may_match(|column, max| {
+                    let column = row_group.column(column);
+                    statistics_scalar(column.column_descr(), column.statistics()?, max)
+                })
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Get the rows of the given row groups which may match, based on the min and max values of
+    /// each page in the page index of the file. Returns `None` if the file has no page index.
+    pub(crate) fn row_selection(&self, row_groups: &[usize]) -> Option<RowSelection> {
+        let column_index = self.metadata.column_index()?;
+        let offset_index = self.metadata.offset_index()?;
+        let columns = self
+            .stats_predicate
+            .references()
+            .into_iter()
+            .filter_map(|name| self.columns.get(stats_column(name)?.1).copied())
+            .unique()
+            .collect_vec();
+
+        let mut selectors = vec![];
+        for &row_group in row_groups {
+            let num_rows = self.metadata.row_group(row_group).num_rows() as usize;
+            let mut selection = RowSelection::from(vec![RowSelector::select(num_rows)]);
+            for &column in &columns {
+                let (Some(index), Some(pages)) = (
+                    column_index.get(row_group).and_then(|c| c.get(column)),
+                    offset_index.get(row_group).and_then(|c| c.get(column)),
+                ) else {
+                    continue;
+                };
+                let descr = self
+                    .metadata
+                    .row_group(row_group)
+                    .column(column)
+                    .column_descr();
+                let page_selectors = pages
+                    .iter()
+                    .enumerate()
+                    .map(|(page, location)| {
+                        let end = pages
+                            .get(page + 1)
+                            .map_or(num_rows, |next| next.first_row_index as usize);
+                        let rows = end - location.first_row_index as usize;
+                        // the values of the other columns in the rows of the page are unknown
+                        let stats = |c, max| {
+                            (c == column).then(|| index_scalar(descr, index, page, max))?
+                        };
+                        if self.may_match(stats) {
+                            RowSelector::select(rows)
+                        } else {
+                            RowSelector::skip(rows)
+                        }
+                    })
+                    .collect_vec();
+                selection = selection.intersection(&page_selectors.into());
+            }
+            selectors.extend(Vec::from(selection));
+        }
+        Some(selectors.into())
+    }
+
+    /// Whether rows with the given min (`max == false`) and max (`max == true`) values of each
+    /// column may match. The values of a column are unknown if `stats` returns `None`.
+    fn may_match(&self, stats: impl Fn(usize, bool) -> Option<Scalar>) -> bool {
+        let resolve = |name: &ColumnName| {
+            let (max, path) = stats_column(name)?;
+            stats(*self.columns.get(path)?, max)
+        };
+        !matches!(
+            self.stats_predicate.evaluate_scalar(&resolve),
+            Ok(Some(Scalar::Boolean(false)))
+        )
+    }
+}
+
+/// Split a column reference of the stats predicate into whether it refers to the max (or min)
+/// value of a column and the path of that column.
+fn stats_column(name: &ColumnName) -> Option<(bool, &[String])> {
+    let (stats, path) = name.path().split_first()?;
+    match stats.as_str() {
+        "maxValues" => Some((true, path)),
+        "minValues" => Some((false, path)),
+        _ => None,
+    }
+}
+
+/// Get the min or max value of a column chunk from its statistics.
+fn statistics_scalar(column: &ColumnDescriptor, stats: &Statistics, max: bool) -> Option<Scalar> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    macro_rules! value {
+        ($stats:expr) => {
+            if max {
+                $stats.max()
+            } else {
+                $stats.min()
+            }
+        };
+    }
+    match stats {
+        // the deprecated min and max values have a signed sort order, which is wrong for these
+        Statistics::Boolean(_) | Statistics::ByteArray(_) if stats.is_min_max_deprecated() => None,
+        Statistics::Boolean(stats) => Some(Scalar::Boolean(*value!(stats))),
+        Statistics::Int32(stats) => int32_scalar(column, *value!(stats)),
+        Statistics::Int64(stats) => int64_scalar(column, *value!(stats)),
+        Statistics::Float(stats) => Some(Scalar::Float(*value!(stats))),
+        Statistics::Double(stats) => Some(Scalar::Double(*value!(stats))),
+        Statistics::ByteArray(stats) => byte_array_scalar(column, value!(stats).data()),
+        Statistics::Int96(_) | Statistics::FixedLenByteArray(_) => None,
+    }
+}
+
+/// Get the min or max value of a column in a page from the page index.
+fn index_scalar(
+    column: &ColumnDescriptor,
+    index: &Index,
+    page: usize,
+    max: bool,
+) -> Option<Scalar> {
+    macro_rules! value {
+        ($index:expr) => {{
+            let page = $index.indexes.get(page)?;
+            if max {
+                page.max.as_ref()?
+            } else {
+                page.min.as_ref()?
+            }
+        }};
+    }
+    match index {
+        Index::BOOLEAN(index) => Some(Scalar::Boolean(*value!(index))),
+        Index::INT32(index) => int32_scalar(column, *value!(index)),
+        Index::INT64(index) => int64_scalar(column, *value!(index)),
+        Index::FLOAT(index) => Some(Scalar::Float(*value!(index))),
+        Index::DOUBLE(index) => Some(Scalar::Double(*value!(index))),
+        Index::BYTE_ARRAY(index) => byte_array_scalar(column, value!(index).data()),
+        Index::NONE | Index::INT96(_) | Index::FIXED_LEN_BYTE_ARRAY(_) => None,
+    }
+}
+
+fn int32_scalar(column: &ColumnDescriptor, value: i32) -> Option<Scalar> {
+    let scalar = match (column.logical_type(), column.converted_type()) {
+        (Some(LogicalType::Date), _) | (None, ConvertedType::DATE) => Scalar::Date(value),
+        (
+            Some(LogicalType::Integer {
+                bit_width: 8,
+                is_signed: true,
+            }),
+            _,
+        )
+        | (None, ConvertedType::INT_8) => Scalar::Byte(value.try_into().ok()?),
+        (
+            Some(LogicalType::Integer {
+                bit_width: 16,
+                is_signed: true,
+            }),
+            _,
+        )
+        | (None, ConvertedType::INT_16) => Scalar::Short(value.try_into().ok()?),
+        (
+            Some(LogicalType::Integer {
+                bit_width: 32,
+                is_signed: true,
+            }),
+            _,
+        )
+        | (None, ConvertedType::NONE | ConvertedType::INT_32) => Scalar::Integer(value),
+        _ => return None,
+    };
+    Some(scalar)
+}
+
+fn int64_scalar(column: &ColumnDescriptor, value: i64) -> Option<Scalar> {
+    let scalar = match (column.logical_type(), column.converted_type()) {
+        (
+            Some(LogicalType::Timestamp {
+                unit: TimeUnit::MICROS(_),
+                ..
+            }),
+            _,
+        )
+        | (None, ConvertedType::TIMESTAMP_MICROS) => Scalar::Timestamp(value),
+        (
+            Some(LogicalType::Integer {
+                bit_width: 64,
+                is_signed: true,
+            }),
+            _,
+        )
+        | (None, ConvertedType::NONE | ConvertedType::INT_64) => Scalar::Long(value),
+        _ => return None,
+    };
+    Some(scalar)
+}
+
+fn byte_array_scalar(column: &ColumnDescriptor, value: &[u8]) -> Option<Scalar> {
+    let scalar = match (column.logical_type(), column.converted_type()) {
+        (Some(LogicalType::String), _) | (None, ConvertedType::UTF8) => {
+            Scalar::String(std::str::from_utf8(value).ok()?.to_string())
+        }
+        (None, ConvertedType::NONE) => Scalar::Binary(value.to_vec()),
+        _ => return None,
+    };
+    Some(scalar)
+}
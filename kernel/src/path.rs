@@ -11,8 +11,25 @@ const DELIMITER: &str = "/";
 
 lazy_static! {
     static ref CHECKPOINT_FILE_PATTERN: Regex =
-        Regex::new(r"\d+\.checkpoint(\.\d+\.\d+)?\.parquet").unwrap();
-    static ref DELTA_FILE_PATTERN: Regex = Regex::new(r"\d+\.json").unwrap();
+        Regex::new(r"^\d+\.checkpoint(\.(?P<part>\d+)\.(?P<num_parts>\d+))?\.parquet$").unwrap();
+    static ref V2_CHECKPOINT_FILE_PATTERN: Regex = Regex::new(
+        r"^\d+\.checkpoint\.[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}\.(json|parquet)$"
+    )
+    .unwrap();
+    static ref DELTA_FILE_PATTERN: Regex = Regex::new(r"^\d+\.json$").unwrap();
+    static ref CHECKSUM_FILE_PATTERN: Regex = Regex::new(r"^\d+\.crc$").unwrap();
+}
+
+/// The different ways a checkpoint can be laid out in the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CheckpointKind {
+    /// A classic checkpoint, stored in a single parquet file.
+    SinglePart,
+    /// One part of a checkpoint that was written in `num_parts` parquet files.
+    MultiPart { part: u32, num_parts: u32 },
+    /// A V2 checkpoint, stored in a uniquely named json or parquet file which may reference
+    /// sidecar files holding its file actions.
+    V2,
 }
 
 #[derive(Debug)]
@@ -32,7 +49,6 @@ impl<'a> LogPath<'a> {
     }
 
     /// Returns the extension of the file stored in this [`LogPath`], if any
-    #[allow(unused)]
     pub(crate) fn extension(&self) -> Option<&str> {
         self.filename()
             .and_then(|f| f.rsplit_once('.'))
@@ -46,9 +62,26 @@ impl<'a> LogPath<'a> {
     }
 
     pub(crate) fn is_checkpoint_file(&self) -> bool {
-        self.filename()
-            .map(|name| CHECKPOINT_FILE_PATTERN.captures(name).is_some())
-            .unwrap_or(false)
+        self.checkpoint_kind().is_some()
+    }
+
+    /// Returns the kind of checkpoint file stored in this [`LogPath`], or `None` if it is not a
+    /// (valid) checkpoint file.
+    pub(crate) fn checkpoint_kind(&self) -> Option<CheckpointKind> {
+        let name = self.filename()?;
+        if V2_CHECKPOINT_FILE_PATTERN.is_match(name) {
+            return Some(CheckpointKind::V2);
+        }
+        let captures = CHECKPOINT_FILE_PATTERN.captures(name)?;
+        match (captures.name("part"), captures.name("num_parts")) {
+            (Some(part), Some(num_parts)) => {
+                let part = part.as_str().parse().ok()?;
+                let num_parts = num_parts.as_str().parse().ok()?;
+                (0 < part && part <= num_parts)
+                    .then_some(CheckpointKind::MultiPart { part, num_parts })
+            }
+            _ => Some(CheckpointKind::SinglePart),
+        }
     }
 
     pub(crate) fn is_commit_file(&self) -> bool {
@@ -57,6 +90,11 @@ impl<'a> LogPath<'a> {
             .unwrap_or(false)
     }
 
+    pub(crate) fn is_checksum_file(&self) -> bool {
+        self.filename()
+            .is_some_and(|name| CHECKSUM_FILE_PATTERN.is_match(name))
+    }
+
     /// Parse the version number assuming a commit json or checkpoint parquet file
     pub(crate) fn commit_version(&self) -> Option<Version> {
         self.filename()
@@ -121,5 +159,53 @@ mod tests {
         assert!(!log_path.is_commit_file());
         assert!(log_path.is_checkpoint_file());
         assert_eq!(log_path.commit_version(), Some(2));
+
+        let log_path = log_path.child("00000000000000000003.crc").unwrap();
+        let log_path = LogPath(&log_path);
+
+        assert!(log_path.is_checksum_file());
+        assert!(!log_path.is_commit_file());
+        assert!(!log_path.is_checkpoint_file());
+        assert_eq!(log_path.commit_version(), Some(3));
+    }
+
+    #[test]
+    fn test_checkpoint_kinds() {
+        let table_url = table_url();
+        let kind = |name: &str| {
+            let url = LogPath(&table_url).child(name).unwrap();
+            LogPath(&url).checkpoint_kind()
+        };
+
+        assert_eq!(
+            kind("00000000000000000002.checkpoint.parquet"),
+            Some(CheckpointKind::SinglePart)
+        );
+        assert_eq!(
+            kind("00000000000000000002.checkpoint.0000000002.0000000003.parquet"),
+            Some(CheckpointKind::MultiPart {
+                part: 2,
+                num_parts: 3
+            })
+        );
+        assert_eq!(
+            kind("00000000000000000002.checkpoint.0000000004.0000000003.parquet"),
+            None
+        );
+        assert_eq!(
+            kind("00000000000000000002.checkpoint.80a083e8-7026-4e79-81be-64bd76c43a11.json"),
+            Some(CheckpointKind::V2)
+        );
+        assert_eq!(
+            kind("00000000000000000002.checkpoint.80a083e8-7026-4e79-81be-64bd76c43a11.parquet"),
+            Some(CheckpointKind::V2)
+        );
+        assert_eq!(kind("00000000000000000002.json"), None);
+
+        // a V2 checkpoint must not be mistaken for a commit file
+        let url = LogPath(&table_url)
+            .child("00000000000000000002.checkpoint.80a083e8-7026-4e79-81be-64bd76c43a11.json")
+            .unwrap();
+        assert!(!LogPath(&url).is_commit_file());
     }
 }
@@ -5,7 +5,11 @@ use tracing::debug;
 
 use crate::actions::visitors::SelectionVectorVisitor;
 use crate::error::DeltaResult;
-use crate::expressions::{BinaryOperator, Expression as Expr, VariadicOperator};
+use crate::expressions::coercion::is_widening_cast;
+use crate::expressions::{
+    BinaryOperator, ColumnName, Expression as Expr, LikePattern, Scalar, UnaryOperator,
+    VariadicOperator,
+};
 use crate::schema::{DataType, SchemaRef, StructField, StructType};
 use crate::{EngineData, EngineInterface, ExpressionEvaluator, JsonHandler};
 
@@ -22,75 +26,236 @@ fn commute(op: &BinaryOperator) -> Option<BinaryOperator> {
     }
 }
 
+/// Get the column of the stats of a file holding the `stats` (e.g. `minValues`) of the column
+/// `col`, which has the same path within the `stats` field.
+fn stats_column(stats: &str, col: &ColumnName) -> Expr {
+    Expr::Column(ColumnName::new([stats]).join(col))
+}
+
+/// Get the predicate which is false if all values of `col` in a file are null, i.e. its null count
+/// equals its number of records.
+fn not_all_null(col: &ColumnName) -> Expr {
+    Expr::ne(stats_column("nullCount", col), Expr::column("numRecords"))
+}
+
+/// Get the column compared with a literal in a binary operation, along with the type it is cast
+/// to if the comparison is on a cast of the column. Only casts which widen the type of the column
+/// are supported, as these cast its min and max values to those of the cast column.
+fn comparison_column<'a>(
+    expr: &'a Expr,
+    column_type: &dyn Fn(&ColumnName) -> Option<DataType>,
+) -> Option<(&'a ColumnName, Option<&'a DataType>)> {
+    match expr {
+        Expr::Column(col) => Some((col, None)),
+        Expr::Cast { expr, data_type } => match expr.as_ref() {
+            Expr::Column(col) if is_widening_cast(&column_type(col)?, data_type) => {
+                Some((col, Some(data_type)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Get the smallest string which is greater than all strings starting with `prefix`, if any.
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        // skip over the surrogate code points, which are not valid chars
+        let next = match last {
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// Get the predicate which is false if no value of the string column `col` in a file can start
+/// with `prefix`, i.e. the range of its values does not overlap with the strings starting with it.
+fn starts_with_predicate(col: &ColumnName, prefix: String) -> Option<Expr> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let upper = prefix_successor(&prefix);
+    let mut exprs = vec![Expr::ge(
+        stats_column("maxValues", col),
+        Expr::literal(prefix),
+    )];
+    if let Some(upper) = upper {
+        exprs.push(Expr::lt(
+            stats_column("minValues", col),
+            Expr::literal(upper),
+        ));
+    }
+    exprs.push(not_all_null(col));
+    Some(Expr::and_from(exprs))
+}
+
 /// Rewrites a predicate to a predicate that can be used to skip files based on their stats.
 /// Returns `None` if the predicate is not eligible for data skipping.
 ///
 /// We normalize each binary operation to a comparison between a column and a literal value
 /// and rewite that in terms of the min/max values of the column.
-/// For example, `1 < a` is rewritten as `minValues.a > 1`.
+/// For example, `1 < a` is rewritten as `minValues.a > 1`. As a comparison with null is never
+/// true, the rewritten comparison is combined with a check that not all values of the column are
+/// null, e.g. `minValues.a > 1 AND nullCount.a != numRecords`.
+///
+/// Null checks are rewritten in terms of the null count of the column:
+/// - `a IS NULL` is rewritten as `nullCount.a != 0`
+/// - `NOT (a IS NULL)` is rewritten as `nullCount.a != numRecords`
+///
+/// A column cast to a wider type, e.g. `CAST(a AS bigint) > 1`, is rewritten like the column with
+/// the same cast applied to its min/max values, i.e. `CAST(maxValues.a AS bigint) > 1`. The type
+/// of each column is looked up with `column_type`, and other casts are not eligible.
+///
+/// `a IN (1, 2)` is rewritten like `a = 1 OR a = 2`. `a LIKE 'ab%'` and `STARTS_WITH(a, 'ab')`
+/// are rewritten as the range of strings starting with the literal prefix of the pattern, i.e.
+/// `maxValues.a >= 'ab' AND minValues.a < 'ac'`.
 ///
 /// The variadic operations are rewritten as follows:
 /// - `AND` is rewritten as a conjunction of the rewritten operands where we just skip
 ///   operands that are not eligible for data skipping.
 /// - `OR` is rewritten only if all operands are eligible for data skipping. Otherwise,
 ///   the whole OR expression is dropped.
-fn as_data_skipping_predicate(expr: &Expr) -> Option<Expr> {
+pub(crate) fn as_data_skipping_predicate(
+    expr: &Expr,
+    column_type: &dyn Fn(&ColumnName) -> Option<DataType>,
+) -> Option<Expr> {
     use BinaryOperator::*;
     use Expr::*;
 
+    let recurse = |expr: &Expr| as_data_skipping_predicate(expr, column_type);
     match expr {
         BinaryOperation { op, left, right } => {
-            let (op, col, val) = match (left.as_ref(), right.as_ref()) {
-                (Column(col), Literal(val)) => (op.clone(), col, val),
-                (Literal(val), Column(col)) => (commute(op)?, col, val),
+            let (op, (col, cast), val) = match (left.as_ref(), right.as_ref()) {
+                (left, Literal(val)) => (op.clone(), comparison_column(left, column_type)?, val),
+                (Literal(val), right) => {
+                    (commute(op)?, comparison_column(right, column_type)?, val)
+                }
                 _ => return None, // unsupported combination of operands
             };
-            let stats_col = match op {
-                LessThan | LessThanOrEqual => "minValues",
-                GreaterThan | GreaterThanOrEqual => "maxValues",
-                Equal => {
-                    let exprs = [
-                        Expr::le(Column(col.clone()), Literal(val.clone())),
-                        Expr::le(Literal(val.clone()), Column(col.clone())),
-                    ];
-                    return as_data_skipping_predicate(&Expr::and_from(exprs));
+            let with_cast = |expr: Expr| match cast {
+                Some(data_type) => expr.cast(data_type.clone()),
+                None => expr,
+            };
+            let min_col = || with_cast(stats_column("minValues", col));
+            let max_col = || with_cast(stats_column("maxValues", col));
+            let stats_exprs = match op {
+                LessThan | LessThanOrEqual => {
+                    vec![Expr::binary(op, min_col(), Literal(val.clone()))]
                 }
-                NotEqual => {
-                    let exprs = [
-                        Expr::gt(Column(format!("minValues.{}", col)), Literal(val.clone())),
-                        Expr::lt(Column(format!("maxValues.{}", col)), Literal(val.clone())),
-                    ];
-                    return Some(Expr::or_from(exprs));
+                GreaterThan | GreaterThanOrEqual => {
+                    vec![Expr::binary(op, max_col(), Literal(val.clone()))]
                 }
+                Equal => vec![
+                    Expr::le(min_col(), Literal(val.clone())),
+                    Expr::ge(max_col(), Literal(val.clone())),
+                ],
+                NotEqual => vec![Expr::or_from([
+                    Expr::gt(min_col(), Literal(val.clone())),
+                    Expr::lt(max_col(), Literal(val.clone())),
+                ])],
                 _ => return None, // unsupported operation
             };
-            let col = format!("{}.{}", stats_col, col);
-            Some(Expr::binary(op, Column(col), Literal(val.clone())))
+            Some(Expr::and_from(
+                stats_exprs.into_iter().chain([not_all_null(col)]),
+            ))
         }
+        UnaryOperation {
+            op: UnaryOperator::IsNull,
+            expr,
+        } => match expr.as_ref() {
+            Column(col) => Some(Expr::ne(
+                stats_column("nullCount", col),
+                Expr::literal(0i64),
+            )),
+            _ => None,
+        },
+        UnaryOperation {
+            op: UnaryOperator::Not,
+            expr,
+        } => match expr.as_ref() {
+            UnaryOperation {
+                op: UnaryOperator::IsNull,
+                expr,
+            } => match expr.as_ref() {
+                Column(col) => Some(not_all_null(col)),
+                _ => None,
+            },
+            _ => None,
+        },
         VariadicOperation {
             op: op @ VariadicOperator::And,
             exprs,
         } => Some(VariadicOperation {
             op: op.clone(),
-            exprs: exprs
-                .iter()
-                .filter_map(as_data_skipping_predicate)
-                .collect::<Vec<_>>(),
+            exprs: exprs.iter().filter_map(recurse).collect::<Vec<_>>(),
         }),
         VariadicOperation {
             op: op @ VariadicOperator::Or,
             exprs,
         } => Some(VariadicOperation {
             op: op.clone(),
-            exprs: exprs
-                .iter()
-                .map(as_data_skipping_predicate)
-                .collect::<Option<Vec<_>>>()?,
+            exprs: exprs.iter().map(recurse).collect::<Option<Vec<_>>>()?,
         }),
+        InList {
+            expr,
+            list,
+            negated: false,
+        } => Some(Expr::or_from(
+            list.iter()
+                .map(|value| recurse(&(**expr).clone().eq(value.clone())))
+                .collect::<Option<Vec<_>>>()?,
+        )),
+        Like {
+            expr,
+            pattern,
+            escape,
+            negated: false,
+        } => match expr.as_ref() {
+            Column(col) => {
+                let prefix = LikePattern::try_new(pattern, *escape).ok()?.prefix();
+                starts_with_predicate(col, prefix)
+            }
+            _ => None,
+        },
+        StartsWith { expr, prefix } => match (expr.as_ref(), prefix.as_ref()) {
+            (Column(col), Literal(Scalar::String(prefix))) => {
+                starts_with_predicate(col, prefix.clone())
+            }
+            _ => None,
+        },
         _ => None,
     }
 }
 
+/// Get the field of the null counts of `field` in the stats of a file, which has the same
+/// structure but counts in place of the values of leaf columns.
+fn null_count_field(field: &StructField) -> StructField {
+    let data_type = match field.data_type() {
+        DataType::Struct(structure) => {
+            StructType::new(structure.fields().map(null_count_field).collect()).into()
+        }
+        _ => DataType::LONG,
+    };
+    StructField::new(field.name(), data_type, true)
+}
+
+/// Get the field at a path of field names in `schema`.
+fn field_at_path<'a>(schema: &'a StructType, path: &[String]) -> Option<&'a StructField> {
+    let (name, rest) = path.split_first()?;
+    let field = schema.field(name)?;
+    match (rest, field.data_type()) {
+        ([], _) => Some(field),
+        (rest, DataType::Struct(structure)) => field_at_path(structure, rest),
+        (_, _) => None,
+    }
+}
+
 pub(crate) struct DataSkippingFilter {
     stats_schema: SchemaRef,
     select_stats_evaluator: Arc<dyn ExpressionEvaluator>,
@@ -124,13 +289,18 @@ impl DataSkippingFilter {
         };
 
         debug!("Creating a data skipping filter for {}", &predicate);
-        let field_names: HashSet<_> = predicate.references();
+        // Only the top-level field of nested columns is needed
+        let field_names: HashSet<_> = predicate
+            .references()
+            .into_iter()
+            .filter_map(|name| name.path().first())
+            .collect();
 
         // Build the stats read schema by extracting the column names referenced by the predicate,
         // extracting the corresponding field from the table schema, and inserting that field.
         let data_fields: Vec<_> = table_schema
             .fields()
-            .filter(|field| field_names.contains(&field.name.as_str()))
+            .filter(|field| field_names.contains(&field.name))
             .cloned()
             .collect();
         if data_fields.is_empty() {
@@ -138,7 +308,28 @@ impl DataSkippingFilter {
             return None;
         }
 
+        let column_type = |name: &ColumnName| {
+            field_at_path(table_schema, name.path()).map(|field| field.data_type().clone())
+        };
+        let skipping_predicate = as_data_skipping_predicate(predicate, &column_type)?;
+        // null counts are only known for leaf columns, a struct has the null counts of its fields
+        let struct_null_count = skipping_predicate
+            .references()
+            .into_iter()
+            .find(|name| match name.path().split_first() {
+                Some((stats, path)) if stats == "nullCount" => field_at_path(table_schema, path)
+                    .is_some_and(|field| matches!(field.data_type(), DataType::Struct(_))),
+                _ => false,
+            });
+        if let Some(name) = struct_null_count {
+            debug!("Not skipping data, the predicate needs {}", name);
+            return None;
+        }
+
+        let null_count_fields = data_fields.iter().map(null_count_field).collect();
         let stats_schema = Arc::new(StructType::new(vec![
+            StructField::new("numRecords", DataType::LONG, true),
+            StructField::new("nullCount", StructType::new(null_count_fields), true),
             StructField::new("minValues", StructType::new(data_fields.clone()), true),
             StructField::new("maxValues", StructType::new(data_fields), true),
         ]));
@@ -162,7 +353,7 @@ impl DataSkippingFilter {
 
         let skipping_evaluator = table_client.get_expression_handler().get_evaluator(
             stats_schema.clone(),
-            Expr::struct_expr([as_data_skipping_predicate(predicate)?]),
+            Expr::struct_expr([skipping_predicate]),
             PREDICATE_SCHEMA.clone(),
         );
 
@@ -223,73 +414,261 @@ mod tests {
         let lit_int = Expr::literal(1_i32);
         let min_col = Expr::column("minValues.a");
         let max_col = Expr::column("maxValues.a");
+        let not_all_null = Expr::ne(Expr::column("nullCount.a"), Expr::column("numRecords"));
+        let with_not_all_null =
+            |exprs: Vec<Expr>| Expr::and_from(exprs.into_iter().chain([not_all_null.clone()]));
 
         let cases = [
             (
                 column.clone().lt(lit_int.clone()),
-                Expr::lt(min_col.clone(), lit_int.clone()),
+                vec![Expr::lt(min_col.clone(), lit_int.clone())],
             ),
             (
                 lit_int.clone().lt(column.clone()),
-                Expr::gt(max_col.clone(), lit_int.clone()),
+                vec![Expr::gt(max_col.clone(), lit_int.clone())],
             ),
             (
                 column.clone().gt(lit_int.clone()),
-                Expr::gt(max_col.clone(), lit_int.clone()),
+                vec![Expr::gt(max_col.clone(), lit_int.clone())],
             ),
             (
                 lit_int.clone().gt(column.clone()),
-                Expr::lt(min_col.clone(), lit_int.clone()),
+                vec![Expr::lt(min_col.clone(), lit_int.clone())],
             ),
             (
                 column.clone().lt_eq(lit_int.clone()),
-                Expr::le(min_col.clone(), lit_int.clone()),
+                vec![Expr::le(min_col.clone(), lit_int.clone())],
             ),
             (
                 lit_int.clone().lt_eq(column.clone()),
-                Expr::ge(max_col.clone(), lit_int.clone()),
+                vec![Expr::ge(max_col.clone(), lit_int.clone())],
             ),
             (
                 column.clone().gt_eq(lit_int.clone()),
-                Expr::ge(max_col.clone(), lit_int.clone()),
+                vec![Expr::ge(max_col.clone(), lit_int.clone())],
             ),
             (
                 lit_int.clone().gt_eq(column.clone()),
-                Expr::le(min_col.clone(), lit_int.clone()),
+                vec![Expr::le(min_col.clone(), lit_int.clone())],
             ),
             (
                 column.clone().eq(lit_int.clone()),
-                Expr::and_from([
+                vec![
                     Expr::le(min_col.clone(), lit_int.clone()),
                     Expr::ge(max_col.clone(), lit_int.clone()),
-                ]),
+                ],
             ),
             (
                 lit_int.clone().eq(column.clone()),
-                Expr::and_from([
+                vec![
                     Expr::le(min_col.clone(), lit_int.clone()),
                     Expr::ge(max_col.clone(), lit_int.clone()),
-                ]),
+                ],
             ),
             (
                 column.clone().ne(lit_int.clone()),
-                Expr::or_from([
+                vec![Expr::or_from([
                     Expr::gt(min_col.clone(), lit_int.clone()),
                     Expr::lt(max_col.clone(), lit_int.clone()),
-                ]),
+                ])],
             ),
             (
                 lit_int.clone().ne(column.clone()),
-                Expr::or_from([
+                vec![Expr::or_from([
                     Expr::gt(min_col.clone(), lit_int.clone()),
                     Expr::lt(max_col.clone(), lit_int.clone()),
-                ]),
+                ])],
             ),
         ];
 
         for (input, expected) in cases {
-            let rewritten = as_data_skipping_predicate(&input).unwrap();
-            assert_eq!(rewritten, expected)
+            let rewritten = as_data_skipping_predicate(&input, &|_| None).unwrap();
+            assert_eq!(rewritten, with_not_all_null(expected))
         }
     }
+
+    #[test]
+    fn test_rewrite_string_predicates() {
+        let column = Expr::column("a");
+        let not_all_null = Expr::ne(Expr::column("nullCount.a"), Expr::column("numRecords"));
+        let prefix_range = |lower: &str, upper: &str| {
+            Expr::and_from([
+                Expr::ge(Expr::column("maxValues.a"), Expr::literal(lower)),
+                Expr::lt(Expr::column("minValues.a"), Expr::literal(upper)),
+                not_all_null.clone(),
+            ])
+        };
+        let equal = |value: i32| {
+            Expr::and_from([
+                Expr::le(Expr::column("minValues.a"), Expr::literal(value)),
+                Expr::ge(Expr::column("maxValues.a"), Expr::literal(value)),
+                not_all_null.clone(),
+            ])
+        };
+
+        let cases = [
+            (
+                column.clone().in_list([Expr::literal(1), Expr::literal(2)]),
+                Some(Expr::or_from([equal(1), equal(2)])),
+            ),
+            (
+                column
+                    .clone()
+                    .in_list([Expr::literal(1), Expr::column("b")]),
+                None,
+            ),
+            (column.clone().not_in_list([Expr::literal(1)]), None),
+            (column.clone().like("ab%"), Some(prefix_range("ab", "ac"))),
+            (column.clone().like("ab_d%"), Some(prefix_range("ab", "ac"))),
+            (
+                column.clone().like_with_escape("a!%%", '!'),
+                Some(prefix_range("a%", "a&")),
+            ),
+            (column.clone().like("%b"), None),
+            (column.clone().not_like("ab%"), None),
+            (
+                column.clone().starts_with(Expr::literal("a\u{10FFFF}")),
+                Some(prefix_range("a\u{10FFFF}", "b")),
+            ),
+            (column.clone().starts_with(Expr::column("b")), None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                as_data_skipping_predicate(&input, &|_| None),
+                expected,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rewrite_nested_column() {
+        let predicate = Expr::column("payload.`device.info`.os").eq(Expr::literal("ios"));
+        let stats_col =
+            |stats: &str| Expr::Column(ColumnName::new([stats, "payload", "device.info", "os"]));
+        let expected = Expr::and_from([
+            Expr::le(stats_col("minValues"), Expr::literal("ios")),
+            Expr::ge(stats_col("maxValues"), Expr::literal("ios")),
+            Expr::ne(stats_col("nullCount"), Expr::column("numRecords")),
+        ]);
+        assert_eq!(
+            as_data_skipping_predicate(&predicate, &|_| None),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_rewrite_casts() {
+        let column_type = |name: &ColumnName| match name.to_string().as_str() {
+            "a" => Some(DataType::INTEGER),
+            "d" => Some(DataType::DATE),
+            _ => None,
+        };
+        let stats_exprs = |col: &str, data_type: DataType, value: Expr| {
+            Expr::and_from([
+                Expr::gt(
+                    Expr::column(format!("maxValues.{col}")).cast(data_type),
+                    value,
+                ),
+                Expr::ne(
+                    Expr::column(format!("nullCount.{col}")),
+                    Expr::column("numRecords"),
+                ),
+            ])
+        };
+
+        let cases = [
+            (
+                Expr::column("a")
+                    .cast(DataType::LONG)
+                    .gt(Expr::literal(1i64)),
+                Some(stats_exprs("a", DataType::LONG, Expr::literal(1i64))),
+            ),
+            (
+                Expr::literal(Scalar::Timestamp(0)).lt(Expr::column("d").cast(DataType::TIMESTAMP)),
+                Some(stats_exprs(
+                    "d",
+                    DataType::TIMESTAMP,
+                    Expr::literal(Scalar::Timestamp(0)),
+                )),
+            ),
+            // narrowing casts may fail or reorder values
+            (
+                Expr::column("a")
+                    .cast(DataType::SHORT)
+                    .gt(Expr::literal(1i16)),
+                None,
+            ),
+            (
+                Expr::column("a")
+                    .cast(DataType::STRING)
+                    .gt(Expr::literal("1")),
+                None,
+            ),
+            // the type of the column is unknown
+            (
+                Expr::column("b")
+                    .cast(DataType::LONG)
+                    .gt(Expr::literal(1i64)),
+                None,
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                as_data_skipping_predicate(&input, &column_type),
+                expected,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rewrite_null_checks() {
+        let column = Expr::column("a.b");
+        let null_count = Expr::column("nullCount.a.b");
+
+        let cases = [
+            (
+                column.clone().is_null(),
+                Some(Expr::ne(null_count.clone(), Expr::literal(0i64))),
+            ),
+            (
+                !column.clone().is_null(),
+                Some(Expr::ne(null_count, Expr::column("numRecords"))),
+            ),
+            (Expr::literal(1).is_null(), None),
+            (!column.clone().eq(Expr::literal(1)), None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                as_data_skipping_predicate(&input, &|_| None),
+                expected,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_null_count_field() {
+        let field = StructField::new(
+            "a",
+            StructType::new(vec![
+                StructField::new("b", DataType::STRING, false),
+                StructField::new("c", DataType::DATE, true),
+            ]),
+            false,
+        );
+        let expected = StructField::new(
+            "a",
+            StructType::new(vec![
+                StructField::new("b", DataType::LONG, true),
+                StructField::new("c", DataType::LONG, true),
+            ]),
+            true,
+        );
+        assert_eq!(null_count_field(&field), expected);
+    }
 }
@@ -1,158 +1,245 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use either::Either;
+use lazy_static::lazy_static;
 use tracing::debug;
 
 use super::data_skipping::DataSkippingFilter;
-use crate::actions::{visitors::AddVisitor, visitors::RemoveVisitor, Add, Remove};
+use super::partition_pruning::PartitionPruner;
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::{visitors::AddVisitor, Add};
 use crate::engine_data::{GetData, TypedGetData};
 use crate::expressions::Expression;
-use crate::schema::{SchemaRef, StructType};
-use crate::{DataVisitor, DeltaResult, EngineData, EngineInterface};
+use crate::schema::{DataType, SchemaRef, StructField, StructType};
+use crate::{DataVisitor, DeltaResult, EngineData, EngineInterface, FilteredEngineData};
 
 struct LogReplayScanner {
+    partition_pruner: Option<PartitionPruner>,
     filter: Option<DataSkippingFilter>,
 
     /// A set of (data file path, dv_unique_id) pairs that have been seen thus
     /// far in the log. This is used to filter out files with Remove actions as
     /// well as duplicate entries in the log.
-    seen: HashSet<(String, Option<String>)>,
+    seen: HashSet<FileActionKey>,
 }
 
-#[derive(Default)]
-struct AddRemoveVisitor {
+/// Collects the Add actions of the rows of a batch which are selected for the scan.
+struct SelectedAddsVisitor {
     adds: Vec<Add>,
-    removes: Vec<Remove>,
-    selection_vector: Option<Vec<bool>>,
-    // whether or not we are visiting commit json (=true) or checkpoint (=false)
-    is_log_batch: bool,
+    selection_vector: Vec<bool>,
 }
 
-const ADD_FIELD_COUNT: usize = 15;
-
-impl AddRemoveVisitor {
-    fn new(selection_vector: Option<Vec<bool>>, is_log_batch: bool) -> Self {
-        AddRemoveVisitor {
-            selection_vector,
-            is_log_batch,
-            ..Default::default()
+impl DataVisitor for SelectedAddsVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        // rows past the end of the selection vector are not selected
+        for i in (0..row_count).filter(|i| self.selection_vector.get(*i).copied().unwrap_or(false))
+        {
+            let path = getters[0].get(i, "add.path")?;
+            self.adds.push(AddVisitor::visit_add(i, path, getters)?);
         }
+        Ok(())
     }
 }
 
-impl DataVisitor for AddRemoveVisitor {
+/// Identifies a file by its path and the unique id of its deletion vector.
+type FileActionKey = (String, Option<String>);
+
+/// Identifies each file action of a batch by its [`FileActionKey`], along with whether it is an
+/// Add (true) or a Remove (false) action.
+#[derive(Default)]
+struct FileActionKeysVisitor {
+    keys: Vec<Option<(FileActionKey, bool)>>,
+}
+
+impl FileActionKeysVisitor {
+    fn visit_key<'a>(
+        row_index: usize,
+        path: String,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<FileActionKey> {
+        let storage_type: Option<String> =
+            getters[0].get_opt(row_index, "deletionVector.storageType")?;
+        let Some(storage_type) = storage_type else {
+            return Ok((path, None));
+        };
+        let path_or_inline_dv: String =
+            getters[1].get(row_index, "deletionVector.pathOrInlineDv")?;
+        let offset: Option<i32> = getters[2].get_opt(row_index, "deletionVector.offset")?;
+        let dv_id = DeletionVectorDescriptor::unique_id_from_parts(
+            &storage_type,
+            &path_or_inline_dv,
+            offset,
+        );
+        Ok((path, Some(dv_id)))
+    }
+}
+
+impl DataVisitor for FileActionKeysVisitor {
     fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
         for i in 0..row_count {
-            // Add will have a path at index 0 if it is valid
+            let mut key = None;
             if let Some(path) = getters[0].get_opt(i, "add.path")? {
-                // Keep the file unless the selection vector is present and is false for this row
-                if !self
-                    .selection_vector
-                    .as_ref()
-                    .is_some_and(|selection| !selection[i])
-                {
-                    self.adds
-                        .push(AddVisitor::visit_add(i, path, &getters[..ADD_FIELD_COUNT])?)
-                }
-            }
-            // Remove will have a path at index 15 if it is valid
-            // TODO(nick): Should count the fields in Add to ensure we don't get this wrong if more
-            // are added
-            // TODO(zach): add a check for selection vector that we never skip a remove
-            else if self.is_log_batch {
-                if let Some(path) = getters[ADD_FIELD_COUNT].get_opt(i, "remove.path")? {
-                    let remove_getters = &getters[ADD_FIELD_COUNT..];
-                    self.removes
-                        .push(RemoveVisitor::visit_remove(i, path, remove_getters)?);
+                key = Some((Self::visit_key(i, path, &getters[1..4])?, true));
+            } else if getters.len() > 4 {
+                // Remove actions are only read from commits
+                if let Some(path) = getters[4].get_opt(i, "remove.path")? {
+                    key = Some((Self::visit_key(i, path, &getters[5..8])?, false));
                 }
             }
+            self.keys.push(key);
         }
         Ok(())
     }
 }
 
+lazy_static! {
+    static ref ADD_KEY_FIELD: StructField = file_action_key_field("add");
+    static ref REMOVE_KEY_FIELD: StructField = file_action_key_field("remove");
+}
+
+/// The fields of a file action that identify the file, i.e. its path and deletion vector.
+fn file_action_key_field(name: &str) -> StructField {
+    StructField::new(
+        name,
+        StructType::new(vec![
+            StructField::new("path", DataType::STRING, true),
+            StructField::new(
+                "deletionVector",
+                StructType::new(vec![
+                    StructField::new("storageType", DataType::STRING, true),
+                    StructField::new("pathOrInlineDv", DataType::STRING, true),
+                    StructField::new("offset", DataType::INTEGER, true),
+                ]),
+                true,
+            ),
+        ]),
+        true,
+    )
+}
+
 impl LogReplayScanner {
     /// Create a new [`LogReplayScanner`] instance
     fn new(
         table_client: &dyn EngineInterface,
         table_schema: &SchemaRef,
+        partition_columns: HashMap<String, DataType>,
         predicate: &Option<Expression>,
     ) -> Self {
         Self {
+            partition_pruner: PartitionPruner::new(predicate, partition_columns),
             filter: DataSkippingFilter::new(table_client, table_schema, predicate),
             seen: Default::default(),
         }
     }
 
-    /// Extract Add actions from a single batch. This will filter out rows that
-    /// don't match the predicate and Add actions that have corresponding Remove
-    /// actions in the log.
-    fn process_batch(
+    /// Compute which rows of a single batch hold Add actions of files to scan. This will skip rows
+    /// that don't match the predicate and Add actions that have corresponding Remove actions in
+    /// the log.
+    fn selection_vector(
         &mut self,
         actions: &dyn EngineData,
         is_log_batch: bool,
-    ) -> DeltaResult<Vec<Add>> {
-        // apply data skipping to get back a selection vector for actions that passed skipping
-        // note: None implies all files passed data skipping.
-        let selection_vector = self
-            .filter
+    ) -> DeltaResult<Vec<bool>> {
+        // prune the files of partitions that don't match first, as that is much cheaper than
+        // parsing their stats. note: None implies all files passed pruning.
+        let pruning_vector = self
+            .partition_pruner
             .as_ref()
-            .map(|filter| filter.apply(actions))
+            .map(|pruner| pruner.apply(actions))
             .transpose()?;
 
+        // apply data skipping to get back a selection vector for actions that passed skipping.
+        // the stats need not be parsed if all files of the batch were pruned.
+        // note: None implies all files passed data skipping.
+        let all_pruned = pruning_vector
+            .as_ref()
+            .is_some_and(|selection| !selection.contains(&true));
+        let skipping_vector = match &self.filter {
+            Some(filter) if !all_pruned => Some(filter.apply(actions)?),
+            _ => None,
+        };
+
         let schema_to_use = StructType::new(if is_log_batch {
-            vec![
-                crate::actions::schemas::ADD_FIELD.clone(),
-                crate::actions::schemas::REMOVE_FIELD.clone(),
-            ]
+            vec![ADD_KEY_FIELD.clone(), REMOVE_KEY_FIELD.clone()]
         } else {
             // All checkpoint actions are already reconciled and Remove actions in checkpoint files
             // only serve as tombstones for vacuum jobs. So no need to load them here.
-            vec![crate::actions::schemas::ADD_FIELD.clone()]
+            vec![ADD_KEY_FIELD.clone()]
         });
-        let mut visitor = AddRemoveVisitor::new(selection_vector, is_log_batch);
+        let mut visitor = FileActionKeysVisitor::default();
         actions.extract(Arc::new(schema_to_use), &mut visitor)?;
 
-        for remove in visitor.removes.into_iter() {
-            let dv_id = remove.dv_unique_id();
-            self.seen.insert((remove.path, dv_id));
+        for (key, _) in visitor.keys.iter().flatten().filter(|(_, is_add)| !is_add) {
+            self.seen.insert(key.clone());
         }
 
-        visitor
-            .adds
+        let selection_vector = visitor
+            .keys
             .into_iter()
-            .filter_map(|add| {
+            .enumerate()
+            .map(|(i, key)| {
+                let Some((key, true)) = key else {
+                    return false;
+                };
+                if [&pruning_vector, &skipping_vector]
+                    .iter()
+                    .any(|selection| selection.as_ref().is_some_and(|selection| !selection[i]))
+                {
+                    return false;
+                }
                 // Note: each (add.path + add.dv_unique_id()) pair has a
                 // unique Add + Remove pair in the log. For example:
                 // https://github.com/delta-io/delta/blob/master/spark/src/test/resources/delta/table-with-dv-large/_delta_log/00000000000000000001.json
-                if !self.seen.contains(&(add.path.clone(), add.dv_unique_id())) {
-                    debug!("Found file: {}, is log {}", &add.path, is_log_batch);
-                    if is_log_batch {
-                        // Remember file actions from this batch so we can ignore duplicates
-                        // as we process batches from older commit and/or checkpoint files. We
-                        // don't need to track checkpoint batches because they are already the
-                        // oldest actions and can never replace anything.
-                        self.seen.insert((add.path.clone(), add.dv_unique_id()));
-                    }
-                    Some(Ok(add))
-                } else {
-                    None
+                if self.seen.contains(&key) {
+                    return false;
+                }
+                debug!("Found file: {}, is log {}", &key.0, is_log_batch);
+                if is_log_batch {
+                    // Remember file actions from this batch so we can ignore duplicates
+                    // as we process batches from older commit and/or checkpoint files. We
+                    // don't need to track checkpoint batches because they are already the
+                    // oldest actions and can never replace anything.
+                    self.seen.insert(key);
                 }
+                true
             })
-            .collect()
+            .collect();
+        Ok(selection_vector)
+    }
+
+    /// Extract Add actions from a single batch. This will filter out rows that
+    /// don't match the predicate and Add actions that have corresponding Remove
+    /// actions in the log.
+    fn process_batch(
+        &mut self,
+        actions: &dyn EngineData,
+        is_log_batch: bool,
+    ) -> DeltaResult<Vec<Add>> {
+        let selection_vector = self.selection_vector(actions, is_log_batch)?;
+        let schema = StructType::new(vec![crate::actions::schemas::ADD_FIELD.clone()]);
+        let mut visitor = SelectedAddsVisitor {
+            adds: vec![],
+            selection_vector,
+        };
+        actions.extract(Arc::new(schema), &mut visitor)?;
+        Ok(visitor.adds)
     }
 }
 
 /// Given an iterator of (record batch, bool) tuples and a predicate, returns an iterator of `Adds`.
 /// The boolean flag indicates whether the record batch is a log or checkpoint batch.
+/// `partition_columns` holds the type of each partition column of the table by physical name, to
+/// prune the files of partitions not matching the predicate.
 pub fn log_replay_iter(
     engine_client: &dyn EngineInterface,
     action_iter: impl Iterator<Item = DeltaResult<(Box<dyn EngineData>, bool)>>,
     table_schema: &SchemaRef,
+    partition_columns: HashMap<String, DataType>,
     predicate: &Option<Expression>,
 ) -> impl Iterator<Item = DeltaResult<Add>> {
-    let mut log_scanner = LogReplayScanner::new(engine_client, table_schema, predicate);
+    let mut log_scanner =
+        LogReplayScanner::new(engine_client, table_schema, partition_columns, predicate);
 
     action_iter.flat_map(move |actions| match actions {
         Ok((batch, is_log_batch)) => {
@@ -164,3 +251,22 @@ pub fn log_replay_iter(
         Err(err) => Either::Right(std::iter::once(Err(err))),
     })
 }
+
+/// Given an iterator of (record batch, bool) tuples and a predicate, returns an iterator of the
+/// batches along with a selection vector of the rows holding Add actions of files to scan.
+pub(crate) fn scan_data_iter(
+    engine_client: &dyn EngineInterface,
+    action_iter: impl Iterator<Item = DeltaResult<(Box<dyn EngineData>, bool)>>,
+    table_schema: &SchemaRef,
+    partition_columns: HashMap<String, DataType>,
+    predicate: &Option<Expression>,
+) -> impl Iterator<Item = DeltaResult<FilteredEngineData>> {
+    let mut log_scanner =
+        LogReplayScanner::new(engine_client, table_schema, partition_columns, predicate);
+
+    action_iter.map(move |actions| {
+        let (batch, is_log_batch) = actions?;
+        let selection_vector = log_scanner.selection_vector(batch.as_ref(), is_log_batch)?;
+        Ok((batch, selection_vector))
+    })
+}
@@ -1,16 +1,32 @@
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
-use itertools::Itertools;
+use itertools::{Either, Itertools};
+use url::Url;
 
-use self::file_stream::log_replay_iter;
+use self::file_stream::{log_replay_iter, scan_data_iter};
+use self::state::ScanFileVisitor;
+use crate::actions::deletion_vector::{treemap_to_bools, DeletionVectorDescriptor};
+use crate::actions::schemas::ADD_FIELD;
 use crate::actions::Add;
-use crate::expressions::{Expression, Scalar};
-use crate::schema::{DataType, SchemaRef, StructType};
+use crate::expressions::{ColumnName, Expression, Scalar};
+use crate::schema::{ColumnMappingMode, DataType, SchemaRef, StructField, StructType};
 use crate::snapshot::Snapshot;
-use crate::{DeltaResult, EngineData, EngineInterface, Error, FileMeta};
+use crate::{
+    DeltaResult, EngineData, EngineInterface, Error, ExpressionEvaluator,
+    FileDataReadResultIterator, FileMeta, FilteredEngineData,
+};
 
-mod data_skipping;
+pub(crate) mod data_skipping;
 pub mod file_stream;
+mod partition_pruning;
+pub mod state;
+pub mod table_changes;
+
+pub use state::{
+    read_scan_file, transform_to_logical, GlobalScanState, ScanCallback, ScanFile, Stats,
+};
+pub use table_changes::{TableChanges, TableChangesBuilder};
 
 // TODO projection: something like fn select(self, columns: &[&str])
 /// Builder to scan a snapshot of a table.
@@ -55,6 +71,9 @@ impl ScanBuilder {
     ///
     /// Can be used to filter the rows in a scan. For example, using the predicate
     /// `x < 4` to return a subset of the rows in the scan which satisfy the filter.
+    ///
+    /// The predicate is simplified (see [`Expression::simplify_predicate`]) when the scan is
+    /// built. A scan whose predicate is false for all rows has no files to read.
     pub fn with_predicate(mut self, predicate: Expression) -> Self {
         self.predicate = Some(predicate);
         self
@@ -72,7 +91,7 @@ impl ScanBuilder {
         Scan {
             snapshot: self.snapshot,
             read_schema,
-            predicate: self.predicate,
+            predicate: self.predicate.map(Expression::simplify_predicate),
         }
     }
 }
@@ -122,33 +141,151 @@ impl Scan {
         &self.predicate
     }
 
+    /// Whether the predicate of the scan is false for all rows, in which case there are no files
+    /// to read.
+    fn is_statically_false(&self) -> bool {
+        matches!(
+            self.predicate,
+            Some(Expression::Literal(Scalar::Boolean(false)))
+        )
+    }
+
     /// Get an iterator of Add actions that should be included in scan for a query. This handles
     /// log-replay, reconciling Add and Remove actions, and applying data skipping (if possible)
     pub fn files(
         &self,
         engine_interface: &dyn EngineInterface,
     ) -> DeltaResult<impl Iterator<Item = DeltaResult<Add>>> {
+        if self.is_statically_false() {
+            return Ok(Either::Right(std::iter::empty()));
+        }
+        let (physical_schema, physical_predicate) = self.physical_schema_and_predicate()?;
+        let log_iter = self.replay_log(engine_interface, physical_predicate.clone())?;
+        Ok(Either::Left(log_replay_iter(
+            engine_interface,
+            log_iter,
+            &physical_schema,
+            self.physical_partition_columns()?,
+            &physical_predicate,
+        )))
+    }
+
+    /// Get an iterator of the batches of log data describing the files to read for the scan,
+    /// along with a selection vector of the rows holding the `add` action of such a file. Like
+    /// [`Scan::files`], this handles log-replay and data skipping, but without materializing
+    /// the `Add` actions, so that engines can pass the batches on to where the files are read.
+    ///
+    /// The `add` column of each batch holds the file's path, size, modification time, partition
+    /// values, stats and deletion vector descriptor, as found in the log. Use
+    /// [`Scan::visit_scan_files`] to get them with typed partition values and parsed stats.
+    pub fn scan_data(
+        &self,
+        engine_interface: &dyn EngineInterface,
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<FilteredEngineData>>> {
+        if self.is_statically_false() {
+            return Ok(Either::Right(std::iter::empty()));
+        }
+        let (physical_schema, physical_predicate) = self.physical_schema_and_predicate()?;
+        let log_iter = self.replay_log(engine_interface, physical_predicate.clone())?;
+        Ok(Either::Left(scan_data_iter(
+            engine_interface,
+            log_iter,
+            &physical_schema,
+            self.physical_partition_columns()?,
+            &physical_predicate,
+        )))
+    }
+
+    /// Visit the files to read in a batch of data returned by [`Scan::scan_data`], calling
+    /// `callback` with `context` for the [`ScanFile`] of each selected row. Returns the context
+    /// when all files have been visited.
+    pub fn visit_scan_files<T>(
+        &self,
+        data: &dyn EngineData,
+        selection_vector: &[bool],
+        context: T,
+        callback: ScanCallback<T>,
+    ) -> DeltaResult<T> {
+        let mut visitor = ScanFileVisitor {
+            table_schema: self.snapshot.schema(),
+            partition_columns: &self.snapshot.metadata().partition_columns,
+            column_mapping_mode: self.snapshot.column_mapping_mode(),
+            selection_vector,
+            context,
+            callback,
+        };
+        let schema = Arc::new(StructType::new(vec![ADD_FIELD.clone()]));
+        data.extract(schema, &mut visitor)?;
+        Ok(visitor.context)
+    }
+
+    /// Get the state of the scan that is needed to read its files, together with the
+    /// [`ScanFile`]s from [`Scan::visit_scan_files`], without the snapshot of the table.
+    pub fn global_scan_state(&self) -> DeltaResult<GlobalScanState> {
+        let partition_columns = self.snapshot.metadata().partition_columns.clone();
+        let column_mapping_mode = self.snapshot.column_mapping_mode();
+        let physical_schema =
+            physical_read_schema(&self.read_schema, &partition_columns, column_mapping_mode)?;
+        let (_, predicate) = self.physical_schema_and_predicate()?;
+        Ok(GlobalScanState {
+            table_root: self.snapshot.table_root.to_string(),
+            partition_columns,
+            logical_schema: self.read_schema.as_ref().clone(),
+            physical_schema,
+            predicate,
+            column_mapping_mode,
+        })
+    }
+
+    /// Get the schema and predicate of the scan in terms of the physical columns, which data
+    /// skipping must use as stats are keyed by physical column names.
+    fn physical_schema_and_predicate(&self) -> DeltaResult<(SchemaRef, Option<Expression>)> {
+        let column_mapping_mode = self.snapshot.column_mapping_mode();
+        let physical_schema = Arc::new(self.read_schema.make_physical(column_mapping_mode)?);
+        let physical_predicate = self
+            .predicate
+            .as_ref()
+            .map(|predicate| {
+                physical_predicate(predicate, self.snapshot.schema(), column_mapping_mode)
+            })
+            .transpose()?;
+        Ok((physical_schema, physical_predicate))
+    }
+
+    /// Get the type of each partition column of the table, by physical name.
+    fn physical_partition_columns(&self) -> DeltaResult<HashMap<String, DataType>> {
+        let schema = self.snapshot.schema();
+        self.snapshot
+            .metadata()
+            .partition_columns
+            .iter()
+            .map(|column| {
+                let field = schema.field(column).ok_or_else(|| {
+                    Error::generic(format!("Partition column {column} not found in schema"))
+                })?;
+                let physical_name = field.physical_name(self.snapshot.column_mapping_mode())?;
+                Ok((physical_name.to_string(), field.data_type().clone()))
+            })
+            .collect()
+    }
+
+    /// Read the file actions of the log.
+    fn replay_log(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        physical_predicate: Option<Expression>,
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<(Box<dyn EngineData>, bool)>>> {
         let commit_read_schema = Arc::new(StructType::new(vec![
-            crate::actions::schemas::ADD_FIELD.clone(),
+            ADD_FIELD.clone(),
             crate::actions::schemas::REMOVE_FIELD.clone(),
         ]));
-        let checkpoint_read_schema = Arc::new(StructType::new(vec![
-            crate::actions::schemas::ADD_FIELD.clone(),
-        ]));
-
-        let log_iter = self.snapshot.log_segment.replay(
+        let checkpoint_read_schema = Arc::new(StructType::new(vec![ADD_FIELD.clone()]));
+        self.snapshot.log_segment.replay(
             engine_interface,
             commit_read_schema,
             checkpoint_read_schema,
-            self.predicate.clone(),
-        )?;
-
-        Ok(log_replay_iter(
-            engine_interface,
-            log_iter,
-            &self.read_schema,
-            &self.predicate,
-        ))
+            physical_predicate,
+        )
     }
 
     /// This is the main method to 'materialize' the scan. It returns a [`Result`] of
@@ -157,108 +294,371 @@ impl Scan {
     /// data. Each [`ScanResult`] encapsulates the raw data and an optional boolean vector built
     /// from the deletion vector if it was present. See the documentation for [`ScanResult`] for
     /// more details.
+    ///
+    /// All data of the scan is held in memory. Use [`Scan::execute_iter`] to read it batch by
+    /// batch instead.
     pub fn execute(&self, engine_interface: &dyn EngineInterface) -> DeltaResult<Vec<ScanResult>> {
-        let parquet_handler = engine_interface.get_parquet_handler();
+        self.execute_iter(engine_interface)?.collect()
+    }
 
-        let partition_columns = &self.snapshot.metadata().partition_columns;
-        let read_schema = Arc::new(StructType::new(
-            self.schema()
-                .fields()
-                .filter(|f| !partition_columns.contains(f.name()))
-                .cloned()
-                .collect::<Vec<_>>(),
-        ));
+    /// Like [`Scan::execute`], but returns an iterator which only reads the files of the scan as
+    /// their data is consumed. Up to [`ParquetHandler::file_concurrency`] files are opened at a
+    /// time, so that engines can read the next files while the current one is consumed, without
+    /// reading ahead further than that.
+    ///
+    /// [`ParquetHandler::file_concurrency`]: crate::ParquetHandler::file_concurrency
+    pub fn execute_iter<'a>(
+        &self,
+        engine_interface: &'a dyn EngineInterface,
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanResult>> + 'a> {
+        let partition_columns = self.snapshot.metadata().partition_columns.clone();
+        let column_mapping_mode = self.snapshot.column_mapping_mode();
+        let physical_schema = Arc::new(physical_read_schema(
+            &self.read_schema,
+            &partition_columns,
+            column_mapping_mode,
+        )?);
 
-        let mut partition_fields = partition_columns
-            .iter()
-            .map(|column| {
-                self.schema()
-                    .field(column)
-                    .ok_or(Error::generic("Unexpected partition column"))
-            })
-            .collect::<DeltaResult<Vec<_>>>()?;
-        partition_fields.reverse();
-
-        let select_fields = read_schema
-            .fields()
-            .map(|f| Expression::column(f.name()))
-            .collect_vec();
-
-        let mut results: Vec<ScanResult> = vec![];
-        let files = self.files(engine_interface)?;
-        for add_result in files {
-            let add = add_result?;
-            let meta = FileMeta {
-                last_modified: add.modification_time,
-                size: add.size as usize,
-                location: self.snapshot.table_root.join(&add.path)?,
-            };
-            // TODO(nick) check if we need robert's try_collect change here
-            let read_results =
-                parquet_handler.read_parquet_files(&[meta], self.read_schema.clone(), None)?;
-
-            let dv_treemap = add
-                .deletion_vector
-                .as_ref()
-                .map(|dv_descriptor| {
-                    let fs_client = engine_interface.get_file_system_client();
-                    dv_descriptor.read(fs_client, self.snapshot.table_root.clone())
-                })
-                .transpose()?;
+        // data read from the files needs no transformation if it has all columns of the scan
+        // under their logical names
+        let needs_transform = column_mapping_mode != ColumnMappingMode::None
+            || self
+                .read_schema
+                .fields()
+                .any(|f| partition_columns.contains(f.name()));
+        let (_, physical_predicate) = self.physical_schema_and_predicate()?;
 
-            let mut dv_mask = dv_treemap.map(super::actions::deletion_vector::treemap_to_bools);
+        Ok(ScanResultIterator {
+            engine_interface,
+            files: Box::new(self.files(engine_interface)?),
+            open_files: VecDeque::new(),
+            file_concurrency: engine_interface
+                .get_parquet_handler()
+                .file_concurrency()
+                .max(1),
+            table_root: self.snapshot.table_root.clone(),
+            read_schema: self.read_schema.clone(),
+            physical_schema,
+            physical_predicate,
+            partition_columns,
+            column_mapping_mode,
+            needs_transform,
+        })
+    }
+}
 
-            for read_result in read_results {
-                let len = if let Ok(ref res) = read_result {
-                    res.length()
-                } else {
-                    0
-                };
+/// The iterator returned by [`Scan::execute_iter`], which keeps up to `file_concurrency` files
+/// open while their data is consumed.
+struct ScanResultIterator<'a> {
+    engine_interface: &'a dyn EngineInterface,
+    files: Box<dyn Iterator<Item = DeltaResult<Add>>>,
+    /// The files that are being read, in scan order
+    open_files: VecDeque<DeltaResult<DataFileIterator>>,
+    file_concurrency: usize,
+    table_root: Url,
+    read_schema: SchemaRef,
+    physical_schema: SchemaRef,
+    physical_predicate: Option<Expression>,
+    partition_columns: Vec<String>,
+    column_mapping_mode: ColumnMappingMode,
+    needs_transform: bool,
+}
 
-                let read_result = if partition_fields.is_empty() {
-                    read_result
-                } else {
-                    let mut fields = Vec::with_capacity(partition_fields.len() + len);
-                    for field in &partition_fields {
-                        let value_expression = parse_partition_value(
-                            add.partition_values.get(field.name()),
-                            field.data_type(),
-                        )?;
-                        fields.push(Expression::Literal(value_expression));
-                    }
-                    fields.extend(select_fields.clone());
-
-                    let evaluator = engine_interface.get_expression_handler().get_evaluator(
-                        read_schema.clone(),
+impl ScanResultIterator<'_> {
+    fn open_file(&self, add: Add) -> DeltaResult<DataFileIterator> {
+        let meta = FileMeta {
+            last_modified: add.modification_time,
+            size: add.size as usize,
+            location: self.table_root.join(&add.path)?,
+        };
+        let transform = if self.needs_transform {
+            let fields = logical_columns(
+                &self.read_schema,
+                &self.partition_columns,
+                self.column_mapping_mode,
+                |field, physical_name| {
+                    parse_partition_value(
+                        add.partition_values.get(physical_name),
+                        field.data_type(),
+                    )
+                },
+            )?;
+            Some(
+                self.engine_interface
+                    .get_expression_handler()
+                    .get_evaluator(
+                        self.physical_schema.clone(),
                         Expression::Struct(fields),
-                        DataType::Struct(Box::new(self.schema().as_ref().clone())),
-                    );
-
-                    evaluator.evaluate(read_result?.as_ref())
-                };
+                        DataType::Struct(Box::new(self.read_schema.as_ref().clone())),
+                    ),
+            )
+        } else {
+            None
+        };
+        open_data_file(
+            self.engine_interface,
+            &self.table_root,
+            meta,
+            add.deletion_vector.as_ref(),
+            self.physical_schema.clone(),
+            self.physical_predicate.clone(),
+            transform,
+        )
+    }
+}
 
-                // need to split the dv_mask. what's left in dv_mask covers this result, and rest
-                // will cover the following results
-                let rest = dv_mask.as_mut().map(|mask| mask.split_off(len));
+impl Iterator for ScanResultIterator<'_> {
+    type Item = DeltaResult<ScanResult>;
 
-                let scan_result = ScanResult {
-                    raw_data: read_result,
-                    mask: dv_mask,
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.open_files.len() < self.file_concurrency {
+                let Some(add) = self.files.next() else {
+                    break;
                 };
-                dv_mask = rest;
-                results.push(scan_result);
+                let file = add.and_then(|add| self.open_file(add));
+                self.open_files.push_back(file);
             }
+            if let Ok(file) = self.open_files.front_mut()? {
+                if let Some(result) = file.next() {
+                    return Some(Ok(result));
+                }
+            }
+            // the first file has been read completely, or failed to open
+            if let Err(err) = self.open_files.pop_front()? {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// Rewrite the column references of a predicate over the logical `schema` of a table to refer to
+/// the physical (nested) columns. References to unknown columns are left as is.
+fn physical_predicate(
+    predicate: &Expression,
+    schema: &StructType,
+    column_mapping_mode: ColumnMappingMode,
+) -> DeltaResult<Expression> {
+    if column_mapping_mode == ColumnMappingMode::None {
+        return Ok(predicate.clone());
+    }
+    let recurse = |expr: &Expression| physical_predicate(expr, schema, column_mapping_mode);
+    Ok(match predicate {
+        Expression::Literal(_) => predicate.clone(),
+        Expression::Column(name) => {
+            Expression::Column(physical_column_name(name, schema, column_mapping_mode)?)
         }
-        Ok(results)
+        Expression::Struct(exprs) => Expression::Struct(exprs.iter().map(recurse).try_collect()?),
+        Expression::BinaryOperation { op, left, right } => Expression::BinaryOperation {
+            op: op.clone(),
+            left: Box::new(recurse(left)?),
+            right: Box::new(recurse(right)?),
+        },
+        Expression::UnaryOperation { op, expr } => Expression::UnaryOperation {
+            op: op.clone(),
+            expr: Box::new(recurse(expr)?),
+        },
+        Expression::VariadicOperation { op, exprs } => Expression::VariadicOperation {
+            op: op.clone(),
+            exprs: exprs.iter().map(recurse).try_collect()?,
+        },
+        Expression::InList {
+            expr,
+            list,
+            negated,
+        } => Expression::InList {
+            expr: Box::new(recurse(expr)?),
+            list: list.iter().map(recurse).try_collect()?,
+            negated: *negated,
+        },
+        Expression::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+        } => Expression::Like {
+            expr: Box::new(recurse(expr)?),
+            pattern: pattern.clone(),
+            escape: *escape,
+            negated: *negated,
+        },
+        Expression::StartsWith { expr, prefix } => Expression::StartsWith {
+            expr: Box::new(recurse(expr)?),
+            prefix: Box::new(recurse(prefix)?),
+        },
+        Expression::Cast { expr, data_type } => Expression::Cast {
+            expr: Box::new(recurse(expr)?),
+            data_type: data_type.clone(),
+        },
+    })
+}
+
+/// Translate a column name of logical field names in `schema` to the physical names.
+fn physical_column_name(
+    name: &ColumnName,
+    schema: &StructType,
+    column_mapping_mode: ColumnMappingMode,
+) -> DeltaResult<ColumnName> {
+    let mut physical_path = Vec::new();
+    let mut current = Some(schema);
+    for field_name in name.path() {
+        let Some(field) = current.and_then(|s| s.field(field_name)) else {
+            return Ok(name.clone());
+        };
+        physical_path.push(field.physical_name(column_mapping_mode)?);
+        current = match field.data_type() {
+            DataType::Struct(structure) => Some(structure),
+            _ => None,
+        };
     }
+    Ok(ColumnName::new(physical_path))
 }
 
-fn parse_partition_value(
+/// Get the schema to read the data files of a scan with, i.e. the columns of `read_schema` that
+/// are not partition columns, under their physical names.
+fn physical_read_schema(
+    read_schema: &StructType,
+    partition_columns: &[String],
+    column_mapping_mode: ColumnMappingMode,
+) -> DeltaResult<StructType> {
+    let fields = read_schema
+        .fields()
+        .filter(|f| !partition_columns.contains(f.name()))
+        .map(|f| f.make_physical(column_mapping_mode))
+        .try_collect()?;
+    Ok(StructType::new(fields))
+}
+
+/// Get the expressions producing each column of `read_schema` from data read with its
+/// [`physical_read_schema`]. Partition columns are filled with the value `partition_value` returns
+/// for the field and its physical name.
+///
+/// When evaluated as a struct with `read_schema` as the output type, this also renames all
+/// (nested) fields back to their logical names.
+fn logical_columns(
+    read_schema: &StructType,
+    partition_columns: &[String],
+    column_mapping_mode: ColumnMappingMode,
+    partition_value: impl Fn(&StructField, &str) -> DeltaResult<Scalar>,
+) -> DeltaResult<Vec<Expression>> {
+    read_schema
+        .fields()
+        .map(|field| {
+            let physical_name = field.physical_name(column_mapping_mode)?;
+            if partition_columns.contains(field.name()) {
+                Ok(Expression::Literal(partition_value(field, physical_name)?))
+            } else {
+                Ok(Expression::Column(ColumnName::new([physical_name])))
+            }
+        })
+        .collect()
+}
+
+/// Read a single data file of a table with the engine's [`crate::ParquetHandler`].
+///
+/// Each batch read from the file is passed through `transform` if one is given, and returned
+/// along with the part of the file's deletion vector mask that covers the batch's rows.
+///
+/// The `predicate` over the physical columns is passed on to the engine, which may use it to skip
+/// rows that cannot match it.
+pub(crate) fn read_data_file(
+    engine_interface: &dyn EngineInterface,
+    table_root: &Url,
+    file: FileMeta,
+    deletion_vector: Option<&DeletionVectorDescriptor>,
+    physical_schema: SchemaRef,
+    predicate: Option<Expression>,
+    transform: Option<Arc<dyn ExpressionEvaluator>>,
+) -> DeltaResult<Vec<ScanResult>> {
+    Ok(open_data_file(
+        engine_interface,
+        table_root,
+        file,
+        deletion_vector,
+        physical_schema,
+        predicate,
+        transform,
+    )?
+    .collect())
+}
+
+/// Start reading a single data file like [`read_data_file`], returning an iterator over the
+/// batches of the file as the engine reads them.
+fn open_data_file(
+    engine_interface: &dyn EngineInterface,
+    table_root: &Url,
+    file: FileMeta,
+    deletion_vector: Option<&DeletionVectorDescriptor>,
+    physical_schema: SchemaRef,
+    predicate: Option<Expression>,
+    transform: Option<Arc<dyn ExpressionEvaluator>>,
+) -> DeltaResult<DataFileIterator> {
+    // the deletion vector masks rows by their position in the file, which only lines up with the
+    // rows read if none are skipped
+    let predicate = predicate.filter(|_| deletion_vector.is_none());
+    let parquet_handler = engine_interface.get_parquet_handler();
+    // TODO(nick) check if we need robert's try_collect change here
+    let read_results = parquet_handler.read_parquet_files(&[file], physical_schema, predicate)?;
+
+    let dv_treemap = deletion_vector
+        .map(|dv_descriptor| {
+            let fs_client = engine_interface.get_file_system_client();
+            dv_descriptor.read(fs_client, table_root.clone())
+        })
+        .transpose()?;
+
+    Ok(DataFileIterator {
+        read_results,
+        dv_mask: dv_treemap.map(treemap_to_bools),
+        transform,
+    })
+}
+
+/// The batches of a data file, see [`open_data_file`].
+struct DataFileIterator {
+    read_results: FileDataReadResultIterator,
+    /// The part of the deletion vector mask covering the rows which have not been read yet
+    dv_mask: Option<Vec<bool>>,
+    transform: Option<Arc<dyn ExpressionEvaluator>>,
+}
+
+impl Iterator for DataFileIterator {
+    type Item = ScanResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let read_result = self.read_results.next()?;
+        let len = if let Ok(ref res) = read_result {
+            res.length()
+        } else {
+            0
+        };
+
+        let read_result = match self.transform {
+            Some(ref evaluator) => read_result.and_then(|data| evaluator.evaluate(data.as_ref())),
+            None => read_result,
+        };
+
+        // need to split the dv_mask. what's left in dv_mask covers this result, and rest
+        // will cover the following results
+        let rest = self.dv_mask.as_mut().map(|mask| mask.split_off(len));
+
+        Some(ScanResult {
+            raw_data: read_result,
+            mask: std::mem::replace(&mut self.dv_mask, rest),
+        })
+    }
+}
+
+/// The partition value Hive-style partitioning uses for nulls, which some writers also write into
+/// the partition values of Add actions.
+const NULL_PARTITION_VALUE: &str = "__HIVE_DEFAULT_PARTITION__";
+
+pub(crate) fn parse_partition_value(
     raw: Option<&Option<String>>,
     data_type: &DataType,
 ) -> DeltaResult<Scalar> {
     match raw {
-        Some(Some(v)) => match data_type {
+        Some(Some(v)) if v != NULL_PARTITION_VALUE => match data_type {
             DataType::Primitive(primitive) => primitive.parse_scalar(v),
             _ => Err(Error::generic(format!(
                 "Unexpected partition column type: {data_type:?}"
@@ -274,7 +674,10 @@ mod tests {
     use std::path::PathBuf;
 
     use super::*;
+    use crate::client::DefaultTableClient;
+    use crate::executor::tokio::TokioBackgroundExecutor;
     use crate::schema::PrimitiveType;
+    use crate::simple_client::data::SimpleData;
     use crate::simple_client::SimpleClient;
     use crate::Table;
 
@@ -302,6 +705,67 @@ mod tests {
         assert!(&files[0].deletion_vector.is_none());
     }
 
+    #[test]
+    fn test_scan_files_partition_pruning() {
+        let path = std::fs::canonicalize(PathBuf::from("./tests/data/basic_partitioned/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine_interface = DefaultTableClient::try_new(
+            &url,
+            std::iter::empty::<(&str, &str)>(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+        .unwrap();
+
+        let table = Table::new(url);
+        let snapshot = table.snapshot(&engine_interface, None).unwrap();
+        let letter = Expression::column("letter");
+        let cases = [
+            (letter.clone().eq(Expression::literal("a")), 2),
+            (letter.clone().gt(Expression::literal("b")), 2),
+            (letter.clone().is_null(), 1),
+            (
+                letter
+                    .eq(Expression::literal("a"))
+                    .and(Expression::column("number").lt(Expression::literal(2i64))),
+                1,
+            ),
+        ];
+        for (predicate, expected) in cases {
+            let scan = ScanBuilder::new(snapshot.clone())
+                .with_predicate(predicate.clone())
+                .build();
+            let files: Vec<Add> = scan
+                .files(&engine_interface)
+                .unwrap()
+                .try_collect()
+                .unwrap();
+            assert_eq!(files.len(), expected, "{predicate}");
+        }
+    }
+
+    #[test]
+    fn test_scan_statically_false_predicate() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-without-dv-small/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        // the simple client cannot evaluate the predicate, so no files must be looked at
+        let engine_interface = SimpleClient::new();
+
+        let table = Table::new(url);
+        let snapshot = table.snapshot(&engine_interface, None).unwrap();
+        let value = Expression::column("value");
+        let predicate = Expression::and_from([
+            value.clone().gt(Expression::literal(5)),
+            Expression::literal(true),
+            value.lt(Expression::literal(3)),
+        ]);
+        let scan = ScanBuilder::new(snapshot).with_predicate(predicate).build();
+        assert_eq!(scan.predicate(), &Some(Expression::literal(false)));
+        assert_eq!(scan.files(&engine_interface).unwrap().count(), 0);
+        assert_eq!(scan.scan_data(&engine_interface).unwrap().count(), 0);
+        assert!(scan.execute(&engine_interface).unwrap().is_empty());
+    }
+
     #[test]
     fn test_scan_data() {
         let path =
@@ -319,6 +783,230 @@ mod tests {
         assert_eq!(num_rows, 10)
     }
 
+    #[test]
+    fn test_execute_iter() {
+        for (table, concurrency) in [
+            ("table-with-dv-small", 1),
+            ("basic_partitioned", 1),
+            ("basic_partitioned", 4),
+        ] {
+            let path = std::fs::canonicalize(PathBuf::from(format!("./tests/data/{table}/")));
+            let url = url::Url::from_directory_path(path.unwrap()).unwrap();
+            let engine_interface = DefaultTableClient::try_new(
+                &url,
+                std::iter::empty::<(&str, &str)>(),
+                Arc::new(TokioBackgroundExecutor::new()),
+            )
+            .unwrap()
+            .with_file_concurrency(concurrency);
+
+            let table = Table::new(url);
+            let snapshot = table.snapshot(&engine_interface, None).unwrap();
+            let scan = ScanBuilder::new(snapshot).build();
+            let expected = scan.execute(&engine_interface).unwrap();
+            let results: Vec<ScanResult> = scan
+                .execute_iter(&engine_interface)
+                .unwrap()
+                .try_collect()
+                .unwrap();
+
+            assert_eq!(results.len(), expected.len());
+            for (result, expected) in results.iter().zip(&expected) {
+                let data = result.raw_data.as_ref().unwrap();
+                assert_eq!(data.length(), expected.raw_data.as_ref().unwrap().length());
+                assert_eq!(result.mask, expected.mask);
+            }
+        }
+    }
+
+    #[test]
+    fn test_visit_scan_files() {
+        let path = std::fs::canonicalize(PathBuf::from("./tests/data/basic_partitioned/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine_interface = SimpleClient::new();
+
+        let table = Table::new(url);
+        let snapshot = table.snapshot(&engine_interface, None).unwrap();
+        let scan = ScanBuilder::new(snapshot).build();
+        let mut files = vec![];
+        for data in scan.scan_data(&engine_interface).unwrap() {
+            let (data, selection_vector) = data.unwrap();
+            assert_eq!(data.length(), selection_vector.len());
+            files = scan
+                .visit_scan_files(data.as_ref(), &selection_vector, files, |files, file| {
+                    files.push(file)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(files.len(), 6);
+        let file = files
+            .iter()
+            .find(|file| file.path.starts_with("letter=e/"))
+            .unwrap();
+        assert_eq!(file.size, 750);
+        assert_eq!(file.modification_time, 1674611429949);
+        assert_eq!(
+            file.partition_values,
+            HashMap::from([("letter".to_string(), Scalar::from("e"))])
+        );
+        assert!(file.deletion_vector.is_none());
+        let stats = file.stats.as_ref().unwrap();
+        assert_eq!(stats.num_records, Some(1));
+        assert_eq!(
+            stats.min_values[&ColumnName::new(["number"])],
+            Scalar::Long(5)
+        );
+        assert_eq!(
+            stats.max_values[&ColumnName::new(["a_float"])],
+            Scalar::Double(5.5)
+        );
+        assert_eq!(stats.null_count[&ColumnName::new(["number"])], 0);
+        // partition columns have no stats
+        assert!(!stats.min_values.contains_key(&ColumnName::new(["letter"])));
+
+        let file = files
+            .iter()
+            .find(|file| file.path.starts_with("letter=__HIVE_DEFAULT_PARTITION__/"))
+            .unwrap();
+        assert_eq!(
+            file.partition_values,
+            HashMap::from([("letter".to_string(), Scalar::Null(DataType::STRING))])
+        );
+    }
+
+    #[test]
+    fn test_visit_scan_files_typed_stats() {
+        let metadata = r#"{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"part\",\"type\":\"date\",\"nullable\":true,\"metadata\":{}},{\"name\":\"d\",\"type\":\"decimal(5,2)\",\"nullable\":true,\"metadata\":{}},{\"name\":\"b\",\"type\":\"binary\",\"nullable\":true,\"metadata\":{}},{\"name\":\"s\",\"type\":{\"type\":\"struct\",\"fields\":[{\"name\":\"x\",\"type\":\"long\",\"nullable\":true,\"metadata\":{}},{\"name\":\"t\",\"type\":\"timestamp\",\"nullable\":true,\"metadata\":{}}]},\"nullable\":true,\"metadata\":{}}]}","partitionColumns":["part"],"configuration":{},"createdTime":1587968585495}}"#;
+        let stats = r#"{"numRecords":2,"minValues":{"d":-1.5,"b":"ab","s":{"x":1,"t":"2024-01-31T12:00:00.000Z"}},"maxValues":{"d":"123.45","b":"Ā","s":{"x":7}},"nullCount":{"d":0,"b":1,"s":{"x":0,"t":2}}}"#;
+        let add = serde_json::json!({
+            "add": {
+                "path": "part=2024-01-31/a.parquet",
+                "partitionValues": {"part": "2024-01-31"},
+                "size": 262,
+                "modificationTime": 1587968586000i64,
+                "dataChange": true,
+                "stats": stats,
+            }
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let location = crate::test_utils::write_test_table(
+            dir.path(),
+            &[
+                crate::test_utils::PROTOCOL.into(),
+                metadata.into(),
+                add.to_string(),
+            ],
+        );
+        let engine_interface = SimpleClient::new();
+        let snapshot = Table::new(location)
+            .snapshot(&engine_interface, None)
+            .unwrap();
+        let scan = ScanBuilder::new(snapshot).build();
+        let mut files = vec![];
+        for data in scan.scan_data(&engine_interface).unwrap() {
+            let (data, selection_vector) = data.unwrap();
+            files = scan
+                .visit_scan_files(data.as_ref(), &selection_vector, files, |files, file| {
+                    files.push(file)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].partition_values,
+            HashMap::from([("part".to_string(), Scalar::Date(19753))])
+        );
+        let stats = files[0].stats.as_ref().unwrap();
+        let column = |path: &[&str]| ColumnName::new(path.iter().copied());
+        assert_eq!(
+            stats.min_values,
+            HashMap::from([
+                (column(&["d"]), Scalar::Decimal(-150, 5, 2)),
+                (column(&["b"]), Scalar::Binary(b"ab".to_vec())),
+                (column(&["s", "x"]), Scalar::Long(1)),
+                (column(&["s", "t"]), Scalar::Timestamp(1706702400000000)),
+            ])
+        );
+        // the binary value is not a byte string, so it is left out
+        assert_eq!(
+            stats.max_values,
+            HashMap::from([
+                (column(&["d"]), Scalar::Decimal(12345, 5, 2)),
+                (column(&["s", "x"]), Scalar::Long(7)),
+            ])
+        );
+        assert_eq!(
+            stats.null_count,
+            HashMap::from([
+                (column(&["d"]), 0),
+                (column(&["b"]), 1),
+                (column(&["s", "x"]), 0),
+                (column(&["s", "t"]), 2),
+            ])
+        );
+
+        // nested column names survive serialization
+        let serialized = serde_json::to_string(stats).unwrap();
+        assert_eq!(&serde_json::from_str::<Stats>(&serialized).unwrap(), stats);
+    }
+
+    #[test]
+    fn test_read_serialized_scan_state() {
+        let path = std::fs::canonicalize(PathBuf::from("./tests/data/basic_partitioned/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine_interface = DefaultTableClient::try_new(
+            &url,
+            std::iter::empty::<(&str, &str)>(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+        .unwrap();
+
+        let table = Table::new(url.clone());
+        let snapshot = table.snapshot(&engine_interface, None).unwrap();
+        let predicate = Expression::column("number").gt(Expression::literal(3i64));
+        let scan = ScanBuilder::new(snapshot).with_predicate(predicate).build();
+        let mut files = vec![];
+        for data in scan.scan_data(&engine_interface).unwrap() {
+            let (data, selection_vector) = data.unwrap();
+            files = scan
+                .visit_scan_files(data.as_ref(), &selection_vector, files, |files, file| {
+                    files.push(file)
+                })
+                .unwrap();
+        }
+        let global_state = scan.global_scan_state().unwrap();
+
+        // ship the state to an executor
+        let serialized = serde_json::to_string(&(&global_state, &files)).unwrap();
+        let (executor_state, executor_files): (GlobalScanState, Vec<ScanFile>) =
+            serde_json::from_str(&serialized).unwrap();
+        assert_eq!(executor_state, global_state);
+        assert_eq!(executor_files, files);
+        assert_eq!(executor_state.partition_columns, vec!["letter"]);
+        assert!(executor_state.physical_schema.field("letter").is_none());
+
+        let mut num_rows = 0;
+        for file in &executor_files {
+            for result in read_scan_file(&engine_interface, &executor_state, file).unwrap() {
+                let data = SimpleData::try_from_engine_data(result.raw_data.unwrap()).unwrap();
+                let batch = data.record_batch();
+                assert_eq!(
+                    batch
+                        .schema()
+                        .fields()
+                        .iter()
+                        .map(|f| f.name())
+                        .collect_vec(),
+                    vec!["letter", "number", "a_float"]
+                );
+                num_rows += batch.num_rows();
+            }
+        }
+        assert_eq!(num_rows, 3);
+    }
+
     #[test]
     fn test_get_partition_value() {
         let cases = [
@@ -351,6 +1039,27 @@ mod tests {
                 PrimitiveType::Timestamp,
                 Scalar::Timestamp(123456),
             ),
+            (
+                "1970-01-01T00:00:00.123456Z",
+                PrimitiveType::Timestamp,
+                Scalar::Timestamp(123456),
+            ),
+            (
+                "-12.30",
+                PrimitiveType::Decimal(5, 2),
+                Scalar::Decimal(-1230, 5, 2),
+            ),
+            (
+                "\u{0}\u{1}",
+                PrimitiveType::Binary,
+                Scalar::Binary(vec![0, 1]),
+            ),
+            (
+                "__HIVE_DEFAULT_PARTITION__",
+                PrimitiveType::Integer,
+                Scalar::Null(DataType::INTEGER),
+            ),
+            ("", PrimitiveType::String, Scalar::Null(DataType::STRING)),
         ];
 
         for (raw, data_type, expected) in &cases {
@@ -361,6 +1070,20 @@ mod tests {
             .unwrap();
             assert_eq!(value, *expected);
         }
+
+        let errors = [
+            ("1.234", PrimitiveType::Decimal(5, 2)),
+            ("1234", PrimitiveType::Decimal(5, 2)),
+            ("abc", PrimitiveType::Decimal(5, 2)),
+            ("2024-01-01 25:00:00", PrimitiveType::Timestamp),
+        ];
+        for (raw, data_type) in errors {
+            let value = parse_partition_value(
+                Some(&Some(raw.to_string())),
+                &DataType::Primitive(data_type),
+            );
+            assert!(matches!(value, Err(Error::ParseError(..))), "{raw}");
+        }
     }
 
     #[test_log::test]
@@ -0,0 +1,169 @@
+//! Pruning of the files of a scan whose partition values do not match the scan's predicate, which
+//! is done during log replay before the (much larger) stats of the files are parsed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use tracing::debug;
+
+use super::parse_partition_value;
+use crate::engine_data::{GetData, TypedGetData};
+use crate::expressions::{ColumnName, Expression, Scalar};
+use crate::schema::{DataType, MapType, StructField, StructType};
+use crate::{DataVisitor, DeltaResult, EngineData};
+
+lazy_static! {
+    static ref PARTITION_VALUES_SCHEMA: Arc<StructType> =
+        Arc::new(StructType::new(vec![StructField::new(
+            "add",
+            StructType::new(vec![
+                StructField::new("path", DataType::STRING, true),
+                StructField::new(
+                    "partitionValues",
+                    MapType::new(DataType::STRING, DataType::STRING, true),
+                    true,
+                ),
+            ]),
+            true,
+        )]));
+}
+
+/// Decides which Add actions of a batch are of files that may hold rows matching a predicate,
+/// based on the values of the partition columns of each file.
+pub(crate) struct PartitionPruner {
+    predicate: Expression,
+    /// The type of each partition column, by physical name
+    partition_columns: HashMap<String, DataType>,
+}
+
+impl PartitionPruner {
+    /// Create a pruner for a predicate in terms of the physical columns of the table. Returns
+    /// `None` if there is no predicate or it does not reference any partition column, in which
+    /// case no file can be pruned.
+    pub(crate) fn new(
+        predicate: &Option<Expression>,
+        partition_columns: HashMap<String, DataType>,
+    ) -> Option<Self> {
+        let predicate = predicate.as_ref()?;
+        // partition columns are top-level columns
+        let references_partition = predicate
+            .references()
+            .into_iter()
+            .any(|column| matches!(column.path(), [name] if partition_columns.contains_key(name)));
+        if !references_partition {
+            return None;
+        }
+        debug!("Pruning partitions with predicate {}", predicate);
+        Some(Self {
+            predicate: predicate.clone(),
+            partition_columns,
+        })
+    }
+
+    /// Apply the pruner to a batch of actions. Returns a selection vector which is true for the
+    /// rows holding an Add action of a file that may hold matching rows.
+    pub(crate) fn apply(&self, actions: &dyn EngineData) -> DeltaResult<Vec<bool>> {
+        let mut visitor = PartitionPruningVisitor {
+            pruner: self,
+            selection_vector: vec![],
+        };
+        actions.extract(PARTITION_VALUES_SCHEMA.clone(), &mut visitor)?;
+        Ok(visitor.selection_vector)
+    }
+
+    /// Whether a file with the given partition values may hold matching rows. As all rows of the
+    /// file share these values, it cannot if the predicate is known to be false or null. Columns
+    /// which are not partition columns have unknown values.
+    fn may_match(&self, partition_values: &HashMap<String, Option<String>>) -> bool {
+        let resolve = |column: &ColumnName| {
+            let [name] = column.path() else {
+                return None;
+            };
+            let data_type = self.partition_columns.get(name)?;
+            parse_partition_value(partition_values.get(name), data_type).ok()
+        };
+        !matches!(
+            self.predicate.evaluate_scalar(&resolve),
+            Ok(Some(Scalar::Boolean(false) | Scalar::Null(_)))
+        )
+    }
+}
+
+struct PartitionPruningVisitor<'a> {
+    pruner: &'a PartitionPruner,
+    selection_vector: Vec<bool>,
+}
+
+impl DataVisitor for PartitionPruningVisitor<'_> {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            let path: Option<String> = getters[0].get_opt(i, "add.path")?;
+            let selected = match path {
+                Some(_) => {
+                    let partition_values: Option<HashMap<_, _>> =
+                        getters[1].get_opt(i, "add.partitionValues")?;
+                    self.pruner.may_match(&partition_values.unwrap_or_default())
+                }
+                None => false,
+            };
+            self.selection_vector.push(selected);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pruner(predicate: Expression) -> Option<PartitionPruner> {
+        let partition_columns = HashMap::from([
+            ("letter".to_string(), DataType::STRING),
+            ("number".to_string(), DataType::LONG),
+        ]);
+        PartitionPruner::new(&Some(predicate), partition_columns)
+    }
+
+    #[test]
+    fn test_no_partition_references() {
+        let predicate = Expression::column("value").gt(Expression::literal(1));
+        assert!(pruner(predicate).is_none());
+    }
+
+    #[test]
+    fn test_may_match() {
+        let letter = Expression::column("letter");
+        let number = Expression::column("number");
+        let value = Expression::column("value");
+        let values = HashMap::from([
+            ("letter".to_string(), Some("a".to_string())),
+            ("number".to_string(), None),
+        ]);
+        let cases = [
+            (letter.clone().eq(Expression::literal("a")), true),
+            (letter.clone().eq(Expression::literal("b")), false),
+            (number.clone().gt(Expression::literal(1i64)), false),
+            (number.clone().is_null(), true),
+            (
+                letter
+                    .clone()
+                    .eq(Expression::literal("b"))
+                    .and(value.clone()),
+                false,
+            ),
+            (
+                letter
+                    .clone()
+                    .eq(Expression::literal("a"))
+                    .and(value.clone()),
+                true,
+            ),
+            (letter.eq(Expression::literal("b")).or(value), true),
+        ];
+        for (predicate, expected) in cases {
+            let pruner = pruner(predicate.clone()).unwrap();
+            assert_eq!(pruner.may_match(&values), expected, "{predicate}");
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! The state of a scan, as needed to read its files apart from the [`Scan`] that planned them,
+//! e.g. on the executors of a distributed engine.
+//!
+//! The [`GlobalScanState`] of a scan and the [`ScanFile`]s found in the batches of scan data
+//! returned by [`Scan::scan_data`] can be serialized to JSON (or any other format supported by
+//! serde) and sent to wherever the files are read, with [`read_scan_file`] or by the engine
+//! followed by [`transform_to_logical`].
+//!
+//! [`Scan`]: super::Scan
+//! [`Scan::scan_data`]: super::Scan::scan_data
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::debug;
+use url::Url;
+
+use super::{logical_columns, parse_partition_value, read_data_file, ScanResult};
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::visitors::AddVisitor;
+use crate::engine_data::{GetData, TypedGetData};
+use crate::expressions::{ColumnName, Expression, Scalar};
+use crate::schema::{ColumnMappingMode, DataType, PrimitiveType, StructType};
+use crate::{
+    DataVisitor, DeltaResult, EngineData, EngineInterface, Error, ExpressionEvaluator, FileMeta,
+};
+
+/// The state of a scan that is shared by all of its files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalScanState {
+    /// The URL of the root of the table
+    pub table_root: String,
+    /// The logical names of the partition columns of the table
+    pub partition_columns: Vec<String>,
+    /// The schema of the rows returned by the scan
+    pub logical_schema: StructType,
+    /// The schema to read the data files with, i.e. the columns of the logical schema that are
+    /// not partition columns, under their physical names
+    pub physical_schema: StructType,
+    /// The predicate of the scan in terms of the physical columns, if any
+    pub predicate: Option<Expression>,
+    /// The column mapping mode of the table
+    pub column_mapping_mode: ColumnMappingMode,
+}
+
+impl GlobalScanState {
+    fn table_root(&self) -> DeltaResult<Url> {
+        Ok(Url::parse(&self.table_root)?)
+    }
+
+    /// Whether data read with the physical schema differs from the logical rows of the scan,
+    /// because it lacks the partition columns or has physical column names.
+    fn needs_transform(&self) -> bool {
+        self.column_mapping_mode != ColumnMappingMode::None
+            || self
+                .logical_schema
+                .fields()
+                .any(|f| self.partition_columns.contains(f.name()))
+    }
+
+    /// Get an evaluator transforming data read with the physical schema to the logical rows of
+    /// the scan for a file with the given partition values, or `None` if it needs no transform.
+    fn logical_transform(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        partition_values: &HashMap<String, Scalar>,
+    ) -> DeltaResult<Option<Arc<dyn ExpressionEvaluator>>> {
+        if !self.needs_transform() {
+            return Ok(None);
+        }
+        let fields = logical_columns(
+            &self.logical_schema,
+            &self.partition_columns,
+            self.column_mapping_mode,
+            |field, _| {
+                Ok(partition_values
+                    .get(field.name())
+                    .cloned()
+                    .unwrap_or_else(|| Scalar::Null(field.data_type().clone())))
+            },
+        )?;
+        let evaluator = engine_interface.get_expression_handler().get_evaluator(
+            Arc::new(self.physical_schema.clone()),
+            Expression::Struct(fields),
+            DataType::Struct(Box::new(self.logical_schema.clone())),
+        );
+        Ok(Some(evaluator))
+    }
+}
+
+/// Transform data read from a file of a scan with the scan's physical schema to the logical rows
+/// of the scan, filling in the file's partition values and renaming columns to their logical
+/// names.
+pub fn transform_to_logical(
+    engine_interface: &dyn EngineInterface,
+    physical_data: Box<dyn EngineData>,
+    global_state: &GlobalScanState,
+    partition_values: &HashMap<String, Scalar>,
+) -> DeltaResult<Box<dyn EngineData>> {
+    match global_state.logical_transform(engine_interface, partition_values)? {
+        Some(evaluator) => evaluator.evaluate(physical_data.as_ref()),
+        None => Ok(physical_data),
+    }
+}
+
+/// Read a file of a scan with the engine's [`crate::ParquetHandler`], returning its logical rows
+/// along with the mask of its deletion vector, like [`Scan::execute`] does for all files.
+///
+/// [`Scan::execute`]: super::Scan::execute
+pub fn read_scan_file(
+    engine_interface: &dyn EngineInterface,
+    global_state: &GlobalScanState,
+    file: &ScanFile,
+) -> DeltaResult<Vec<ScanResult>> {
+    let table_root = global_state.table_root()?;
+    let meta = FileMeta {
+        last_modified: file.modification_time,
+        size: file.size as usize,
+        location: table_root.join(&file.path)?,
+    };
+    let transform = global_state.logical_transform(engine_interface, &file.partition_values)?;
+    read_data_file(
+        engine_interface,
+        &table_root,
+        meta,
+        file.deletion_vector.as_ref(),
+        Arc::new(global_state.physical_schema.clone()),
+        global_state.predicate.clone(),
+        transform,
+    )
+}
+
+/// A data file to read for a scan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanFile {
+    /// A relative path to the file from the root of the table or an absolute path to the file.
+    /// The path is a URI as specified by [RFC 2396 URI Generic Syntax], which needs to be decoded
+    /// to get the file path.
+    ///
+    /// [RFC 2396 URI Generic Syntax]: https://www.ietf.org/rfc/rfc2396.txt
+    pub path: String,
+    /// The size of the file in bytes
+    pub size: i64,
+    /// The time the file was created, as milliseconds since the epoch
+    pub modification_time: i64,
+    /// The values of the partition columns of the file, by logical column name
+    pub partition_values: HashMap<String, Scalar>,
+    /// The statistics of the file, if it has any
+    pub stats: Option<Stats>,
+    /// The deletion vector of the file, masking out rows which are not part of the table
+    pub deletion_vector: Option<DeletionVectorDescriptor>,
+}
+
+/// [Statistics] about the data in a file.
+///
+/// Values are given for the columns of the table with a primitive type, including those nested in
+/// structs, by logical [`ColumnName`]. Values that cannot be represented as a [`Scalar`] are left
+/// out.
+///
+/// [Statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    /// The number of records in the file
+    pub num_records: Option<i64>,
+    /// The minimum value of each column
+    #[serde(with = "column_name_keys")]
+    pub min_values: HashMap<ColumnName, Scalar>,
+    /// The maximum value of each column
+    #[serde(with = "column_name_keys")]
+    pub max_values: HashMap<ColumnName, Scalar>,
+    /// The number of null values of each column
+    #[serde(with = "column_name_keys")]
+    pub null_count: HashMap<ColumnName, i64>,
+}
+
+/// Serialize maps keyed by [`ColumnName`] with the names as strings, since JSON object keys must
+/// be strings.
+mod column_name_keys {
+    use super::*;
+
+    pub(super) fn serialize<S: Serializer, V: Serialize>(
+        map: &HashMap<ColumnName, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(name, value)| (name.to_string(), value)))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<ColumnName, V>, D::Error> {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| Ok((name.parse().map_err(serde::de::Error::custom)?, value)))
+            .collect()
+    }
+}
+
+/// The stats of a file as stored in the log, keyed by physical field name
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawStats {
+    num_records: Option<i64>,
+    #[serde(default)]
+    min_values: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    max_values: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    null_count: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Called by [`Scan::visit_scan_files`] for each file to read, with the `context` that was passed
+/// in.
+///
+/// [`Scan::visit_scan_files`]: super::Scan::visit_scan_files
+pub type ScanCallback<T> = fn(context: &mut T, file: ScanFile);
+
+/// Converts the Add actions of the selected rows of a batch of scan data to [`ScanFile`]s.
+pub(crate) struct ScanFileVisitor<'a, T> {
+    pub(crate) table_schema: &'a StructType,
+    pub(crate) partition_columns: &'a [String],
+    pub(crate) column_mapping_mode: ColumnMappingMode,
+    pub(crate) selection_vector: &'a [bool],
+    pub(crate) context: T,
+    pub(crate) callback: ScanCallback<T>,
+}
+
+impl<T> ScanFileVisitor<'_, T> {
+    fn partition_values(
+        &self,
+        raw_values: &HashMap<String, Option<String>>,
+    ) -> DeltaResult<HashMap<String, Scalar>> {
+        self.partition_columns
+            .iter()
+            .map(|column| {
+                let field = self.table_schema.field(column).ok_or_else(|| {
+                    Error::generic(format!("Partition column {column} not found in schema"))
+                })?;
+                let physical_name = field.physical_name(self.column_mapping_mode)?;
+                let value =
+                    parse_partition_value(raw_values.get(physical_name), field.data_type())?;
+                Ok((column.clone(), value))
+            })
+            .collect()
+    }
+
+    fn stats(&self, raw_stats: &str) -> DeltaResult<Stats> {
+        let raw_stats: RawStats = serde_json::from_str(raw_stats)?;
+        let mut stats = Stats {
+            num_records: raw_stats.num_records,
+            ..Default::default()
+        };
+        let schema = self.table_schema;
+        self.visit_stats_values(
+            schema,
+            &[],
+            &raw_stats.min_values,
+            &mut |column, ty, value| {
+                if let Some(value) = stats_value(ty, value) {
+                    stats.min_values.insert(column, value);
+                }
+            },
+        )?;
+        self.visit_stats_values(
+            schema,
+            &[],
+            &raw_stats.max_values,
+            &mut |column, ty, value| {
+                if let Some(value) = stats_value(ty, value) {
+                    stats.max_values.insert(column, value);
+                }
+            },
+        )?;
+        self.visit_stats_values(
+            schema,
+            &[],
+            &raw_stats.null_count,
+            &mut |column, _, count| {
+                if let Some(count) = count.as_i64() {
+                    stats.null_count.insert(column, count);
+                }
+            },
+        )?;
+        Ok(stats)
+    }
+
+    /// Call `f` with the logical name, type and raw stats value of each primitive column nested
+    /// in `schema` that has a value in `values`. The fields of `schema` are those of the column at
+    /// `path`, and `values` holds their stats by physical field name.
+    fn visit_stats_values(
+        &self,
+        schema: &StructType,
+        path: &[String],
+        values: &serde_json::Map<String, serde_json::Value>,
+        f: &mut dyn FnMut(ColumnName, &PrimitiveType, &serde_json::Value),
+    ) -> DeltaResult<()> {
+        for field in schema.fields() {
+            let Some(value) = values.get(field.physical_name(self.column_mapping_mode)?) else {
+                continue;
+            };
+            let column = ColumnName::new(path.iter().chain([field.name()]));
+            match (field.data_type(), value) {
+                (DataType::Struct(fields), serde_json::Value::Object(values)) => {
+                    self.visit_stats_values(fields, column.path(), values, f)?
+                }
+                (DataType::Primitive(primitive), value) => f(column, primitive, value),
+                // there are no stats for arrays and maps
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> DataVisitor for ScanFileVisitor<'_, T> {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        // rows past the end of the selection vector are not selected
+        for i in (0..row_count).filter(|i| self.selection_vector.get(*i).copied().unwrap_or(false))
+        {
+            let path = getters[0].get(i, "add.path")?;
+            let add = AddVisitor::visit_add(i, path, getters)?;
+            let stats = match add.stats.as_deref() {
+                Some(stats) => Some(self.stats(stats)?),
+                None => None,
+            };
+            let file = ScanFile {
+                partition_values: self.partition_values(&add.partition_values)?,
+                path: add.path,
+                size: add.size,
+                modification_time: add.modification_time,
+                stats,
+                deletion_vector: add.deletion_vector,
+            };
+            (self.callback)(&mut self.context, file);
+        }
+        Ok(())
+    }
+}
+
+/// Convert a min or max value from the stats of a file to a [`Scalar`] of the column's type.
+fn stats_value(data_type: &PrimitiveType, value: &serde_json::Value) -> Option<Scalar> {
+    use PrimitiveType::*;
+    let scalar = match data_type {
+        Byte => Scalar::Byte(value.as_i64()?.try_into().ok()?),
+        Short => Scalar::Short(value.as_i64()?.try_into().ok()?),
+        Integer => Scalar::Integer(value.as_i64()?.try_into().ok()?),
+        Long => Scalar::Long(value.as_i64()?),
+        Float => Scalar::Float(value.as_f64()? as f32),
+        Double => Scalar::Double(value.as_f64()?),
+        Boolean => Scalar::Boolean(value.as_bool()?),
+        String => Scalar::String(value.as_str()?.to_string()),
+        Date | Timestamp | Binary | Decimal(..) => {
+            // decimals are usually written as JSON numbers, but may be strings
+            let raw = match value {
+                serde_json::Value::Number(number) if matches!(data_type, Decimal(..)) => {
+                    number.to_string()
+                }
+                value => value.as_str()?.to_string(),
+            };
+            match data_type.parse_scalar(&raw) {
+                Ok(scalar) => scalar,
+                Err(err) => {
+                    debug!("Ignoring stats value {}: {}", value, err);
+                    return None;
+                }
+            }
+        }
+    };
+    Some(scalar)
+}
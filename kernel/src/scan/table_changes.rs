@@ -0,0 +1,455 @@
+//! Reading the [change data feed] of a table, i.e. the row level changes made by a range of
+//! commits.
+//!
+//! [change data feed]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#add-cdc-file
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{
+    logical_columns, parse_partition_value, physical_read_schema, read_data_file, ScanResult,
+};
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::schemas::{
+    ADD_FIELD, CDC_FIELD, IN_COMMIT_TIMESTAMP_FIELD, METADATA_FIELD, REMOVE_FIELD,
+};
+use crate::actions::visitors::{AddVisitor, CdcVisitor, MetadataVisitor, RemoveVisitor};
+use crate::actions::{Add, Cdc, Metadata, Remove};
+use crate::engine_data::{GetData, TypedGetData};
+use crate::expressions::{Expression, Scalar};
+use crate::path::LogPath;
+use crate::schema::{DataType, Schema, SchemaRef, StructField, StructType};
+use crate::snapshot::{list_commit_timestamps, CommitTimestamp, Snapshot};
+use crate::table_properties::TableProperties;
+use crate::{DataVisitor, DeltaResult, EngineInterface, Error, FileMeta, Version};
+
+/// Name of the column holding the kind of change of a row, which is one of `insert`, `delete`,
+/// `update_preimage` or `update_postimage`.
+pub const CHANGE_TYPE_COL_NAME: &str = "_change_type";
+/// Name of the column holding the version of the commit that made the change.
+pub const COMMIT_VERSION_COL_NAME: &str = "_commit_version";
+/// Name of the column holding the timestamp of the commit that made the change.
+pub const COMMIT_TIMESTAMP_COL_NAME: &str = "_commit_timestamp";
+
+/// Builder to read the changes made to a table by a range of commits.
+pub struct TableChangesBuilder {
+    snapshot: Arc<Snapshot>,
+    start_version: Version,
+    schema: Option<SchemaRef>,
+}
+
+impl std::fmt::Debug for TableChangesBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("TableChangesBuilder")
+            .field("start_version", &self.start_version)
+            .field("end_version", &self.snapshot.version())
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl TableChangesBuilder {
+    /// Create a new [`TableChangesBuilder`] instance for the changes made by the commits from
+    /// `start_version` up to and including the version of `snapshot`.
+    pub fn new(snapshot: Arc<Snapshot>, start_version: Version) -> Self {
+        Self {
+            snapshot,
+            start_version,
+            schema: None,
+        }
+    }
+
+    /// Provide [`Schema`] for the table columns to select from the changed rows.
+    ///
+    /// The change data columns `_change_type`, `_commit_version` and `_commit_timestamp` are
+    /// always appended to the selected columns.
+    ///
+    /// [`Schema`]: crate::schema::Schema
+    pub fn with_schema(mut self, schema: SchemaRef) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Build the [`TableChanges`].
+    ///
+    /// This performs no reads, but fails if the start version is after the end version.
+    pub fn build(self) -> DeltaResult<TableChanges> {
+        let end_version = self.snapshot.version();
+        if self.start_version > end_version {
+            return Err(Error::generic(format!(
+                "Start version {} is after end version {end_version}",
+                self.start_version
+            )));
+        }
+        // if no schema is provided, use snapshot's entire schema (e.g. SELECT *)
+        let read_schema = self
+            .schema
+            .unwrap_or_else(|| self.snapshot.schema().clone().into());
+        let output_schema = Arc::new(StructType::new(
+            read_schema
+                .fields()
+                .cloned()
+                .chain([
+                    StructField::new(CHANGE_TYPE_COL_NAME, DataType::STRING, false),
+                    StructField::new(COMMIT_VERSION_COL_NAME, DataType::LONG, false),
+                    StructField::new(COMMIT_TIMESTAMP_COL_NAME, DataType::TIMESTAMP, false),
+                ])
+                .collect(),
+        ));
+        Ok(TableChanges {
+            snapshot: self.snapshot,
+            start_version: self.start_version,
+            read_schema,
+            output_schema,
+        })
+    }
+}
+
+/// Where the rows of a [`ChangeFile`] come from, which determines their `_change_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeFileType {
+    /// A file from a `cdc` action. The file holds the `_change_type` of each row.
+    Cdc,
+    /// A file added by a commit without `cdc` actions. All rows are inserts.
+    Add,
+    /// A file removed by a commit without `cdc` actions. All rows are deletes.
+    Remove,
+}
+
+impl ChangeFileType {
+    /// The `_change_type` of all rows in files of this type, or `None` if it is stored per row.
+    fn change_type(&self) -> Option<&'static str> {
+        match self {
+            Self::Cdc => None,
+            Self::Add => Some("insert"),
+            Self::Remove => Some("delete"),
+        }
+    }
+}
+
+/// A data file holding rows changed by a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeFile {
+    /// A relative path to the file from the root of the table or an absolute path to the file.
+    pub path: String,
+    /// A map from partition column to value for this file.
+    pub partition_values: HashMap<String, Option<String>>,
+    /// The size of this file in bytes, if known.
+    pub size: Option<i64>,
+    /// The time this file was created, as milliseconds since the epoch, if known. Only `add`
+    /// actions record it.
+    pub modification_time: Option<i64>,
+    /// The deletion vector of the file, masking out rows which are not part of the change.
+    pub deletion_vector: Option<DeletionVectorDescriptor>,
+    /// Where the rows of this file come from.
+    pub file_type: ChangeFileType,
+    /// Version of the commit that made the change.
+    pub commit_version: Version,
+    /// Timestamp of the commit that made the change, as milliseconds since the epoch.
+    pub commit_timestamp: i64,
+}
+
+/// The result of building a [`TableChangesBuilder`]. This can be used to get the rows changed by
+/// the commits in the requested range.
+pub struct TableChanges {
+    snapshot: Arc<Snapshot>,
+    start_version: Version,
+    read_schema: SchemaRef,
+    output_schema: SchemaRef,
+}
+
+impl std::fmt::Debug for TableChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("TableChanges")
+            .field("start_version", &self.start_version)
+            .field("end_version", &self.end_version())
+            .field("schema", &self.output_schema)
+            .finish()
+    }
+}
+
+impl TableChanges {
+    /// First version (inclusive) of the range of commits to read changes from.
+    pub fn start_version(&self) -> Version {
+        self.start_version
+    }
+
+    /// Last version (inclusive) of the range of commits to read changes from.
+    pub fn end_version(&self) -> Version {
+        self.snapshot.version()
+    }
+
+    /// Get a shared reference to the [`Schema`] of the changed rows. These are the selected
+    /// table columns, followed by `_change_type`, `_commit_version` and `_commit_timestamp`.
+    ///
+    /// [`Schema`]: crate::schema::Schema
+    pub fn schema(&self) -> &SchemaRef {
+        &self.output_schema
+    }
+
+    /// Get the files holding the changed rows, in commit order.
+    ///
+    /// For commits that have `cdc` actions, only their change data files are returned. Otherwise
+    /// the files of the commit's `add` and `remove` actions that change data are returned, as
+    /// inserted and deleted rows respectively.
+    ///
+    /// Fails with [`Error::ChangeDataFeedNotEnabled`] if the change data feed is not enabled
+    /// throughout the range, and with [`Error::Unsupported`] if the table schema changes within it.
+    pub fn files(&self, engine_interface: &dyn EngineInterface) -> DeltaResult<Vec<ChangeFile>> {
+        let fs_client = engine_interface.get_file_system_client();
+        let log_root = LogPath(&self.snapshot.table_root).child("_delta_log/")?;
+        let end_version = self.end_version();
+        let commits: Vec<_> = list_commit_timestamps(fs_client.as_ref(), &log_root, end_version)?
+            .into_iter()
+            .filter(|commit| commit.version >= self.start_version)
+            .collect();
+        let mut next_version = self.start_version;
+        for commit in commits.iter() {
+            if commit.version != next_version {
+                break;
+            }
+            next_version += 1;
+        }
+        if next_version <= end_version {
+            let missing = log_root.join(&format!("{:020}.json", next_version))?;
+            return Err(Error::file_not_found(missing));
+        }
+
+        // changes can only be read with the schema of the end version, under which the whole
+        // range must have had the change data feed enabled. Metadata changes inside the range are
+        // checked while reading the commits.
+        let schema = self.snapshot.schema();
+        check_metadata(end_version, self.snapshot.metadata(), schema)?;
+        if self.start_version < end_version {
+            let start_snapshot = Snapshot::try_new(
+                self.snapshot.table_root.clone(),
+                engine_interface,
+                Some(self.start_version),
+            )?;
+            check_metadata(self.start_version, start_snapshot.metadata(), schema)?;
+        }
+
+        let mut files = vec![];
+        for commit in commits {
+            files.extend(read_commit_changes(engine_interface, commit, schema)?);
+        }
+        Ok(files)
+    }
+
+    /// This is the main method to 'materialize' the changes. It calls [`TableChanges::files`] to
+    /// get the files holding changed rows, and then uses the `engine_interface`'s
+    /// [`crate::ParquetHandler`] to read them, just like [`super::Scan::execute`]. Each
+    /// [`ScanResult`] holds data in the shape of [`TableChanges::schema`].
+    pub fn execute(&self, engine_interface: &dyn EngineInterface) -> DeltaResult<Vec<ScanResult>> {
+        let partition_columns = &self.snapshot.metadata().partition_columns;
+        let column_mapping_mode = self.snapshot.column_mapping_mode();
+        let read_schema =
+            physical_read_schema(&self.read_schema, partition_columns, column_mapping_mode)?;
+        let cdc_read_schema = Arc::new(StructType::new(
+            read_schema
+                .fields()
+                .cloned()
+                .chain([StructField::new(
+                    CHANGE_TYPE_COL_NAME,
+                    DataType::STRING,
+                    false,
+                )])
+                .collect(),
+        ));
+        let read_schema = Arc::new(read_schema);
+        let output_type = DataType::Struct(Box::new(self.output_schema.as_ref().clone()));
+
+        let mut results = vec![];
+        for file in self.files(engine_interface)? {
+            let mut fields = logical_columns(
+                &self.read_schema,
+                partition_columns,
+                column_mapping_mode,
+                |field, physical_name| {
+                    parse_partition_value(
+                        file.partition_values.get(physical_name),
+                        field.data_type(),
+                    )
+                },
+            )?;
+            let (physical_schema, change_type) = match file.file_type.change_type() {
+                Some(change_type) => (read_schema.clone(), Expression::literal(change_type)),
+                None => (
+                    cdc_read_schema.clone(),
+                    Expression::column(CHANGE_TYPE_COL_NAME),
+                ),
+            };
+            fields.push(change_type);
+            fields.push(Expression::literal(Scalar::Long(
+                file.commit_version as i64,
+            )));
+            // the timestamp type is in microseconds
+            fields.push(Expression::literal(Scalar::Timestamp(
+                file.commit_timestamp * 1000,
+            )));
+
+            let evaluator = engine_interface.get_expression_handler().get_evaluator(
+                physical_schema.clone(),
+                Expression::Struct(fields),
+                output_type.clone(),
+            );
+            // `remove` and `cdc` actions don't record the modification time of their file
+            let meta = FileMeta {
+                last_modified: file.modification_time.unwrap_or_default(),
+                size: file.size.unwrap_or_default() as usize,
+                location: self.snapshot.table_root.join(&file.path)?,
+            };
+            results.extend(read_data_file(
+                engine_interface,
+                &self.snapshot.table_root,
+                meta,
+                file.deletion_vector.as_ref(),
+                physical_schema,
+                None,
+                Some(evaluator),
+            )?);
+        }
+        Ok(results)
+    }
+}
+
+/// Check that the changes made by the commit of `version` under `metadata` can be read: the change
+/// data feed must be enabled and the table schema must match the `schema` the changes are read
+/// with.
+fn check_metadata(version: Version, metadata: &Metadata, schema: &Schema) -> DeltaResult<()> {
+    let properties = TableProperties::try_from(&metadata.configuration)?;
+    if !properties.enable_change_data_feed()?.unwrap_or(false) {
+        return Err(Error::ChangeDataFeedNotEnabled(version));
+    }
+    if metadata.schema()? != *schema {
+        return Err(Error::unsupported(format!(
+            "Reading changes across the schema change of version {version} is not supported"
+        )));
+    }
+    Ok(())
+}
+
+/// Read the files holding the rows changed by a single commit, checking any metadata change it
+/// makes against the `schema` the changes are read with.
+fn read_commit_changes(
+    engine_interface: &dyn EngineInterface,
+    commit: CommitTimestamp,
+    schema: &Schema,
+) -> DeltaResult<Vec<ChangeFile>> {
+    let read_schema = Arc::new(StructType::new(vec![
+        ADD_FIELD.clone(),
+        REMOVE_FIELD.clone(),
+        CDC_FIELD.clone(),
+        METADATA_FIELD.clone(),
+        IN_COMMIT_TIMESTAMP_FIELD.clone(),
+    ]));
+    let mut visitor = CommitChangesVisitor::default();
+    let batches = engine_interface.get_json_handler().read_json_files(
+        std::slice::from_ref(&commit.file),
+        read_schema.clone(),
+        None,
+    )?;
+    for batch in batches {
+        batch?.extract(read_schema.clone(), &mut visitor)?;
+    }
+    let version = commit.version;
+    for metadata in visitor.metadata.iter() {
+        check_metadata(version, metadata, schema)?;
+    }
+
+    let commit_timestamp = visitor.in_commit_timestamp.unwrap_or(commit.timestamp);
+    let change_file =
+        |path, partition_values, size, modification_time, deletion_vector, file_type| ChangeFile {
+            path,
+            partition_values,
+            size,
+            modification_time,
+            deletion_vector,
+            file_type,
+            commit_version: version,
+            commit_timestamp,
+        };
+
+    if !visitor.cdcs.is_empty() {
+        return Ok(visitor
+            .cdcs
+            .into_iter()
+            .map(|cdc| {
+                change_file(
+                    cdc.path,
+                    cdc.partition_values,
+                    Some(cdc.size),
+                    None,
+                    None,
+                    ChangeFileType::Cdc,
+                )
+            })
+            .collect());
+    }
+
+    let adds = visitor.adds.into_iter().filter(|add| add.data_change);
+    let removes = visitor
+        .removes
+        .into_iter()
+        .filter(|remove| remove.data_change);
+    Ok(adds
+        .map(|add| {
+            change_file(
+                add.path,
+                add.partition_values,
+                Some(add.size),
+                Some(add.modification_time),
+                add.deletion_vector,
+                ChangeFileType::Add,
+            )
+        })
+        .chain(removes.map(|remove| {
+            change_file(
+                remove.path,
+                remove.partition_values.unwrap_or_default(),
+                remove.size,
+                None,
+                remove.deletion_vector,
+                ChangeFileType::Remove,
+            )
+        }))
+        .collect())
+}
+
+/// Collects the file actions, metadata changes and in-commit timestamp of a commit
+#[derive(Default)]
+struct CommitChangesVisitor {
+    adds: Vec<Add>,
+    removes: Vec<Remove>,
+    cdcs: Vec<Cdc>,
+    metadata: Vec<Metadata>,
+    in_commit_timestamp: Option<i64>,
+}
+
+impl DataVisitor for CommitChangesVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        let (add_getters, rest) = getters.split_at(ADD_FIELD.data_type().leaf_count());
+        let (remove_getters, rest) = rest.split_at(REMOVE_FIELD.data_type().leaf_count());
+        let (cdc_getters, rest) = rest.split_at(CDC_FIELD.data_type().leaf_count());
+        let (metadata_getters, commit_info_getters) =
+            rest.split_at(METADATA_FIELD.data_type().leaf_count());
+        for i in 0..row_count {
+            if let Some(path) = add_getters[0].get_opt(i, "add.path")? {
+                self.adds.push(AddVisitor::visit_add(i, path, add_getters)?);
+            } else if let Some(path) = remove_getters[0].get_opt(i, "remove.path")? {
+                self.removes
+                    .push(RemoveVisitor::visit_remove(i, path, remove_getters)?);
+            } else if let Some(path) = cdc_getters[0].get_opt(i, "cdc.path")? {
+                self.cdcs.push(CdcVisitor::visit_cdc(i, path, cdc_getters)?);
+            } else if let Some(id) = metadata_getters[0].get_opt(i, "metaData.id")? {
+                self.metadata
+                    .push(MetadataVisitor::visit_metadata(i, id, metadata_getters)?);
+            } else if let Some(ts) =
+                commit_info_getters[0].get_opt(i, "commitInfo.inCommitTimestamp")?
+            {
+                self.in_commit_timestamp = Some(ts);
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
 use std::fmt::Formatter;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::{collections::HashMap, fmt::Display};
 
 use indexmap::IndexMap;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use crate::{DeltaResult, Error};
+
 pub type Schema = StructType;
 pub type SchemaRef = Arc<StructType>;
 
@@ -60,6 +64,51 @@ impl AsRef<str> for ColumnMetadataKey {
     }
 }
 
+/// Modes of mapping the logical columns of a table to the physical columns of its data files, as
+/// configured by the `delta.columnMapping.mode` table property.
+///
+/// See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#column-mapping>
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnMappingMode {
+    /// Physical columns have the same names as the logical columns.
+    #[default]
+    None,
+    /// Physical columns are identified by the field ids in `delta.columnMapping.id`. Writers
+    /// always record the `delta.columnMapping.physicalName` as well, which is used for reads.
+    Id,
+    /// Physical columns are identified by the names in `delta.columnMapping.physicalName`.
+    Name,
+}
+
+impl ColumnMappingMode {
+    /// Name of the table property setting the column mapping mode
+    pub const TABLE_PROPERTY: &'static str = "delta.columnMapping.mode";
+}
+
+impl FromStr for ColumnMappingMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "id" => Ok(Self::Id),
+            "name" => Ok(Self::Name),
+            _ => Err(Error::generic(format!("Invalid column mapping mode: {s}"))),
+        }
+    }
+}
+
+impl Display for ColumnMappingMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Id => write!(f, "id"),
+            Self::Name => write!(f, "name"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Eq)]
 pub struct StructField {
     /// Name of this (possibly nested) column
@@ -118,6 +167,34 @@ impl StructField {
     pub const fn metadata(&self) -> &HashMap<String, MetadataValue> {
         &self.metadata
     }
+
+    /// Get the name of the column holding this field in the data files, for the given
+    /// [`ColumnMappingMode`].
+    pub fn physical_name(&self, mapping_mode: ColumnMappingMode) -> DeltaResult<&str> {
+        match mapping_mode {
+            ColumnMappingMode::None => Ok(&self.name),
+            ColumnMappingMode::Id | ColumnMappingMode::Name => {
+                match self.get_config_value(&ColumnMetadataKey::ColumnMappingPhysicalName) {
+                    Some(MetadataValue::String(physical_name)) => Ok(physical_name),
+                    _ => Err(Error::generic(format!(
+                        "Column mapping mode is {mapping_mode}, but field {} has no physical name",
+                        self.name
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Convert this field to the field of the data files, with this field and all nested fields
+    /// renamed to their physical names.
+    pub fn make_physical(&self, mapping_mode: ColumnMappingMode) -> DeltaResult<StructField> {
+        Ok(Self {
+            name: self.physical_name(mapping_mode)?.to_string(),
+            data_type: self.data_type.make_physical(mapping_mode)?,
+            nullable: self.nullable,
+            metadata: self.metadata.clone(),
+        })
+    }
 }
 
 /// A struct is used to represent both the top-level schema of the table
@@ -147,6 +224,16 @@ impl StructType {
     pub fn fields(&self) -> impl Iterator<Item = &StructField> {
         self.fields.values()
     }
+
+    /// Convert this struct to the struct of the data files, with all (nested) fields renamed to
+    /// their physical names. See [`StructField::make_physical`].
+    pub fn make_physical(&self, mapping_mode: ColumnMappingMode) -> DeltaResult<StructType> {
+        let fields = self
+            .fields()
+            .map(|field| field.make_physical(mapping_mode))
+            .try_collect()?;
+        Ok(Self::new(fields))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -402,6 +489,41 @@ impl DataType {
     pub fn decimal(precision: u8, scale: i8) -> Self {
         DataType::Primitive(PrimitiveType::Decimal(precision, scale))
     }
+
+    /// Convert this type to the type of the data files, with all nested struct fields renamed to
+    /// their physical names. See [`StructField::make_physical`].
+    pub fn make_physical(&self, mapping_mode: ColumnMappingMode) -> DeltaResult<DataType> {
+        Ok(match self {
+            DataType::Primitive(_) => self.clone(),
+            DataType::Array(array) => ArrayType::new(
+                array.element_type.make_physical(mapping_mode)?,
+                array.contains_null,
+            )
+            .into(),
+            DataType::Struct(structure) => structure.make_physical(mapping_mode)?.into(),
+            DataType::Map(map) => MapType::new(
+                map.key_type.make_physical(mapping_mode)?,
+                map.value_type.make_physical(mapping_mode)?,
+                map.value_contains_null,
+            )
+            .into(),
+        })
+    }
+
+    /// The number of leaf columns of this type, i.e. the number of [`GetData`] items that
+    /// [`EngineData::extract`] passes for a column of this type. Arrays and maps are single leaves.
+    ///
+    /// [`GetData`]: crate::engine_data::GetData
+    /// [`EngineData::extract`]: crate::EngineData::extract
+    pub(crate) fn leaf_count(&self) -> usize {
+        match self {
+            DataType::Struct(structure) => structure
+                .fields()
+                .map(|field| field.data_type().leaf_count())
+                .sum(),
+            _ => 1,
+        }
+    }
 }
 
 impl Display for DataType {
@@ -583,4 +705,120 @@ mod tests {
         let schema: Result<Schema, _> = serde_json::from_reader(file);
         assert!(schema.is_ok())
     }
+
+    #[test]
+    fn test_make_physical() {
+        let data = r#"
+        {
+            "name": "a",
+            "type": {
+                "type": "struct",
+                "fields": [
+                    {
+                        "name": "b",
+                        "type": {
+                            "type": "array",
+                            "elementType": {
+                                "type": "struct",
+                                "fields": [
+                                    {
+                                        "name": "c",
+                                        "type": "integer",
+                                        "nullable": true,
+                                        "metadata": {
+                                            "delta.columnMapping.id": 3,
+                                            "delta.columnMapping.physicalName": "col-c"
+                                        }
+                                    }
+                                ]
+                            },
+                            "containsNull": true
+                        },
+                        "nullable": true,
+                        "metadata": {
+                            "delta.columnMapping.id": 2,
+                            "delta.columnMapping.physicalName": "col-b"
+                        }
+                    }
+                ]
+            },
+            "nullable": true,
+            "metadata": {
+                "delta.columnMapping.id": 1,
+                "delta.columnMapping.physicalName": "col-a"
+            }
+        }
+        "#;
+        let field: StructField = serde_json::from_str(data).unwrap();
+
+        assert_eq!(field.make_physical(ColumnMappingMode::None).unwrap(), field);
+
+        for mode in [ColumnMappingMode::Id, ColumnMappingMode::Name] {
+            let physical = field.make_physical(mode).unwrap();
+            assert_eq!(physical.name(), "col-a");
+            let DataType::Struct(a) = physical.data_type() else {
+                panic!("expected a struct")
+            };
+            let b = a.field("col-b").unwrap();
+            let DataType::Array(b) = b.data_type() else {
+                panic!("expected an array")
+            };
+            let DataType::Struct(element) = b.element_type() else {
+                panic!("expected a struct")
+            };
+            assert!(element.field("col-c").is_some());
+        }
+
+        let unmapped = StructField::new("a", DataType::INTEGER, true);
+        assert!(unmapped.make_physical(ColumnMappingMode::Name).is_err());
+    }
+
+    #[test]
+    fn test_leaf_count() {
+        let data_type: DataType = StructType::new(vec![
+            StructField::new("a", DataType::INTEGER, true),
+            StructField::new(
+                "b",
+                StructType::new(vec![
+                    StructField::new("c", DataType::STRING, true),
+                    StructField::new("d", StructType::new(vec![]), true),
+                    StructField::new("e", DataType::LONG, true),
+                ]),
+                true,
+            ),
+            StructField::new(
+                "f",
+                ArrayType::new(
+                    StructType::new(vec![StructField::new("g", DataType::LONG, true)]).into(),
+                    true,
+                ),
+                true,
+            ),
+            StructField::new(
+                "h",
+                MapType::new(DataType::STRING, DataType::STRING, true),
+                true,
+            ),
+        ])
+        .into();
+        assert_eq!(data_type.leaf_count(), 5);
+        assert_eq!(DataType::LONG.leaf_count(), 1);
+    }
+
+    #[test]
+    fn test_parse_column_mapping_mode() {
+        assert_eq!(
+            "none".parse::<ColumnMappingMode>().unwrap(),
+            ColumnMappingMode::None
+        );
+        assert_eq!(
+            "id".parse::<ColumnMappingMode>().unwrap(),
+            ColumnMappingMode::Id
+        );
+        assert_eq!(
+            "Name".parse::<ColumnMappingMode>().unwrap(),
+            ColumnMappingMode::Name
+        );
+        assert!("other".parse::<ColumnMappingMode>().is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use super::parquet::read_parquet_file;
 use crate::engine_data::{EngineData, EngineList, EngineMap, GetData};
 use crate::schema::{DataType, PrimitiveType, Schema, SchemaRef, StructField};
 use crate::{DataVisitor, DeltaResult, Error};
@@ -6,7 +7,8 @@ use arrow_array::cast::AsArray;
 use arrow_array::types::{Int32Type, Int64Type};
 use arrow_array::{Array, GenericListArray, MapArray, RecordBatch, StructArray};
 use arrow_schema::{ArrowError, DataType as ArrowDataType, Schema as ArrowSchema};
-use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use arrow_select::concat::concat_batches;
+use itertools::Itertools;
 use tracing::{debug, warn};
 use url::Url;
 
@@ -170,19 +172,17 @@ impl SimpleData {
         Ok(SimpleData::new(data?))
     }
 
-    // TODO needs to apply the schema to the parquet read
-    pub fn try_create_from_parquet(_schema: SchemaRef, location: Url) -> DeltaResult<Self> {
-        let file = File::open(
-            location
-                .to_file_path()
-                .map_err(|_| Error::generic("can only read local files"))?,
-        )?;
-        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
-        let mut reader = builder.build()?;
-        let data = reader
-            .next()
-            .ok_or(Error::generic("No data found reading parquet file"))?;
-        Ok(SimpleData::new(data?))
+    /// Read the columns of `schema` from the local parquet file at `location` into a single
+    /// batch.
+    pub fn try_create_from_parquet(schema: SchemaRef, location: Url) -> DeltaResult<Self> {
+        let arrow_schema: Arc<ArrowSchema> = Arc::new(schema.as_ref().try_into()?);
+        let batches: Vec<_> =
+            read_parquet_file(&location, arrow_schema.clone(), None)?.try_collect()?;
+        let data = match batches.first() {
+            Some(batch) => concat_batches(&batch.schema(), &batches)?,
+            None => RecordBatch::new_empty(arrow_schema),
+        };
+        Ok(SimpleData::new(data))
     }
 
     /// Extracts an exploded view (all leaf values), in schema order of that data contained
@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::time::SystemTime;
 
 use bytes::Bytes;
@@ -55,7 +56,7 @@ impl FileSystemClient for SimpleFilesystemClient {
                             .modified()
                             .map(
                                 |modified| match modified.duration_since(SystemTime::UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
+                                    Ok(d) => d.as_millis() as u64,
                                     Err(_) => 0,
                                 },
                             )
@@ -93,6 +94,27 @@ impl FileSystemClient for SimpleFilesystemClient {
         });
         Ok(Box::new(iter))
     }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        if path.scheme() != "file" {
+            return Err(Error::generic("Can only write to local filesystem"));
+        }
+        let file_path = path
+            .to_file_path()
+            .map_err(|_| Error::generic(format!("Invalid path for write_file: {:?}", path)))?;
+        let mut options = std::fs::OpenOptions::new();
+        if overwrite {
+            options.write(true).create(true).truncate(true);
+        } else {
+            options.write(true).create_new(true);
+        }
+        let mut file = options.open(&file_path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::AlreadyExists => Error::file_already_exists(path.path()),
+            _ => err.into(),
+        })?;
+        file.write_all(&data)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -172,4 +194,20 @@ mod tests {
         assert_eq!(file_count, 1);
         Ok(())
     }
+
+    #[test]
+    fn test_write_file() -> Result<(), Box<dyn std::error::Error>> {
+        let client = SimpleFilesystemClient;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let url = Url::from_file_path(tmp_dir.path().join("a")).unwrap();
+
+        client.write_file(&url, "a".into(), false)?;
+        assert!(matches!(
+            client.write_file(&url, "b".into(), false),
+            Err(crate::Error::FileAlreadyExists(_))
+        ));
+        client.write_file(&url, "c".into(), true)?;
+        assert_eq!(std::fs::read_to_string(tmp_dir.path().join("a"))?, "c");
+        Ok(())
+    }
 }
@@ -8,7 +8,7 @@ pub mod data;
 mod fs_client;
 mod get_data;
 pub(crate) mod json;
-mod parquet;
+pub(crate) mod parquet;
 
 /// This is a simple implemention of [`EngineInterface`]. It only supports reading data from the
 /// local filesystem, and internally represents data using `Arrow`.
@@ -1,8 +1,20 @@
+use std::fs::File;
+use std::sync::Arc;
+
+use arrow_array::RecordBatch;
+use arrow_schema::SchemaRef as ArrowSchemaRef;
+use either::Either;
+use itertools::Itertools;
+use parquet::arrow::arrow_reader::{ArrowReaderOptions, ParquetRecordBatchReaderBuilder};
 use tracing::debug;
+use url::Url;
 
+use super::data::SimpleData;
+use crate::parquet_utils::skipping::skip_row_groups_and_pages;
+use crate::parquet_utils::{projection_mask, reorder_columns, write_parquet};
 use crate::{
-    schema::SchemaRef, DeltaResult, Expression, FileDataReadResultIterator, FileMeta,
-    ParquetHandler,
+    schema::SchemaRef, DeltaResult, EngineData, Error, Expression, FileDataReadResultIterator,
+    FileMeta, FilteredEngineData, ParquetHandler,
 };
 
 pub(crate) struct SimpleParquetHandler {}
@@ -12,16 +24,182 @@ impl ParquetHandler for SimpleParquetHandler {
         &self,
         files: &[FileMeta],
         schema: SchemaRef,
-        _predicate: Option<Expression>,
+        predicate: Option<Expression>,
     ) -> DeltaResult<FileDataReadResultIterator> {
         debug!("Reading parquet files: {:#?}", files);
         if files.is_empty() {
             return Ok(Box::new(std::iter::empty()));
         }
+        let schema: ArrowSchemaRef = Arc::new(schema.as_ref().try_into()?);
         let locations: Vec<_> = files.iter().map(|file| file.location.clone()).collect();
-        Ok(Box::new(locations.into_iter().map(move |location| {
-            let d = super::data::SimpleData::try_create_from_parquet(schema.clone(), location);
-            d.map(|d| Box::new(d) as _)
-        })))
+        Ok(Box::new(locations.into_iter().flat_map(
+            move |location| match read_parquet_file(&location, schema.clone(), predicate.as_ref()) {
+                Ok(batches) => Either::Left(
+                    batches.map_ok(|batch| Box::new(SimpleData::new(batch)) as Box<dyn EngineData>),
+                ),
+                Err(err) => Either::Right(std::iter::once(Err(err))),
+            },
+        )))
+    }
+
+    fn write_parquet_file(
+        &self,
+        location: Url,
+        schema: SchemaRef,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + '_>,
+    ) -> DeltaResult<FileMeta> {
+        debug!("Writing parquet file: {}", location);
+        let path = location
+            .to_file_path()
+            .map_err(|_| Error::generic("can only write local files"))?;
+        write_parquet(std::fs::File::create(&path)?, schema, data)?;
+        let metadata = std::fs::metadata(&path)?;
+        let last_modified = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        Ok(FileMeta {
+            location,
+            last_modified,
+            size: metadata.len() as usize,
+        })
+    }
+}
+
+/// Read the columns of `schema` from the local parquet file at `location`, skipping the row groups
+/// and pages that cannot hold rows matching `predicate`, if one is given.
+///
+/// Columns are returned in the order of `schema`, and the columns that the file does not have are
+/// filled with nulls.
+pub(crate) fn read_parquet_file(
+    location: &Url,
+    schema: ArrowSchemaRef,
+    predicate: Option<&Expression>,
+) -> DeltaResult<impl Iterator<Item = DeltaResult<RecordBatch>>> {
+    let path = location
+        .to_file_path()
+        .map_err(|_| Error::generic("can only read local files"))?;
+    // the page index is only useful to skip pages
+    let options = ArrowReaderOptions::new().with_page_index(predicate.is_some());
+    let mut builder =
+        ParquetRecordBatchReaderBuilder::try_new_with_options(File::open(path)?, options)?;
+    let mask = projection_mask(builder.parquet_schema(), &schema);
+    if let Some(predicate) = predicate {
+        builder = skip_row_groups_and_pages(builder, predicate);
+    }
+    let reader = builder.with_projection(mask).build()?;
+    Ok(reader.map(move |batch| reorder_columns(batch?, &schema)))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::{ArrayRef, Int64Array, StructArray};
+    use arrow_schema::{DataType as ArrowDataType, Field};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+
+    use super::*;
+    use crate::schema::{DataType, StructField, StructType};
+
+    /// Write ids 0..30 along with a struct of their squares and cubes to a parquet file, in three
+    /// row groups.
+    fn write_ids(dir: &tempfile::TempDir) -> FileMeta {
+        let powers = StructArray::from(vec![
+            (
+                Arc::new(Field::new("square", ArrowDataType::Int64, false)),
+                Arc::new(Int64Array::from_iter_values((0..30).map(|i| i * i))) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("cube", ArrowDataType::Int64, false)),
+                Arc::new(Int64Array::from_iter_values((0..30).map(|i| i * i * i))) as ArrayRef,
+            ),
+        ]);
+        let batch = RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(Int64Array::from_iter_values(0..30)) as ArrayRef,
+            ),
+            ("powers", Arc::new(powers) as ArrayRef),
+        ])
+        .unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(10)
+            .build();
+        let path = dir.path().join("ids.parquet");
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), Some(props))
+                .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        FileMeta {
+            location: Url::from_file_path(path).unwrap(),
+            last_modified: 0,
+            size: 0,
+        }
+    }
+
+    fn read(file: FileMeta, schema: StructType, predicate: Option<Expression>) -> Vec<RecordBatch> {
+        SimpleParquetHandler {}
+            .read_parquet_files(&[file], Arc::new(schema), predicate)
+            .unwrap()
+            .map_ok(|data| SimpleData::try_from_engine_data(data).unwrap().into())
+            .try_collect()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_read_nested_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = write_ids(&dir);
+
+        let schema = StructType::new(vec![
+            StructField::new(
+                "powers",
+                StructType::new(vec![
+                    StructField::new("cube", DataType::LONG, false),
+                    StructField::new("missing", DataType::STRING, true),
+                ]),
+                true,
+            ),
+            StructField::new("missing", DataType::INTEGER, true),
+        ]);
+        let batches = read(file, schema, None);
+
+        // all row groups are read
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 30);
+        let batch = &batches[0];
+        assert_eq!(batch.num_columns(), 2);
+        assert_eq!(batch.column(1).null_count(), batch.num_rows());
+        let powers = batch.column(0).as_struct();
+        assert_eq!(powers.column_names(), ["cube", "missing"]);
+        let cubes = powers.column(0).as_primitive::<Int64Type>();
+        assert_eq!(cubes.value(3), 27);
+        assert_eq!(powers.column(1).null_count(), batch.num_rows());
+    }
+
+    #[test]
+    fn test_skip_row_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = write_ids(&dir);
+
+        let schema = StructType::new(vec![StructField::new("id", DataType::LONG, false)]);
+        let predicate = Expression::column("id")
+            .lt(Expression::literal(5i64))
+            .or(Expression::column("id").ge(Expression::literal(25i64)));
+        let ids = read(file, schema, Some(predicate))
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect_vec();
+
+        // the middle row group is skipped
+        assert_eq!(ids, (0..10).chain(20..30).collect_vec());
     }
 }
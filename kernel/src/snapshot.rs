@@ -2,33 +2,54 @@
 //! has schema etc.)
 //!
 
-use std::cmp::Ordering;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use url::Url;
 
+use crate::actions::schemas::{IN_COMMIT_TIMESTAMP_FIELD, SIDECAR_FIELD};
+use crate::actions::visitors::{InCommitTimestampVisitor, SidecarVisitor};
 use crate::actions::{Metadata, Protocol};
-use crate::path::LogPath;
-use crate::schema::{Schema, SchemaRef, StructType};
+use crate::checksum::{read_version_checksum, VersionChecksum};
+use crate::path::{CheckpointKind, LogPath};
+use crate::schema::{ColumnMappingMode, Schema, SchemaRef, StructType};
+use crate::table_properties::TableProperties;
 use crate::{DeltaResult, EngineInterface, Error, FileMeta, FileSystemClient, Version};
 use crate::{EngineData, Expression};
 
-const LAST_CHECKPOINT_FILE_NAME: &str = "_last_checkpoint";
+pub(crate) const LAST_CHECKPOINT_FILE_NAME: &str = "_last_checkpoint";
 
 #[derive(Debug)]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 #[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
 struct LogSegment {
-    log_root: Url,
+    pub(crate) log_root: Url,
     /// Reverse order sorted commit files in the log segment
     pub(crate) commit_files: Vec<FileMeta>,
-    /// checkpoint files in the log segment.
+    /// checkpoint files in the log segment
     pub(crate) checkpoint_files: Vec<FileMeta>,
+    /// sidecar files of a V2 checkpoint, resolved when the log segment is first replayed
+    pub(crate) sidecar_files: OnceLock<Vec<FileMeta>>,
 }
 
 impl LogSegment {
+    /// Get the sidecar files holding the file actions of the checkpoint, reading them from the
+    /// checkpoint on first use. V2 checkpoints may also be named like classic checkpoints, so any
+    /// single file checkpoint may have sidecars.
+    fn sidecar_files(&self, engine_interface: &dyn EngineInterface) -> DeltaResult<&[FileMeta]> {
+        if let Some(files) = self.sidecar_files.get() {
+            return Ok(files);
+        }
+        let files = match self.checkpoint_files.as_slice() {
+            [file] => read_sidecar_files(engine_interface, &self.log_root, file)?,
+            _ => vec![],
+        };
+        Ok(self.sidecar_files.get_or_init(|| files))
+    }
+
     /// Read a stream of log data from this log segment.
     ///
     /// The log files will be read from most recent to oldest.
@@ -54,10 +75,28 @@ impl LogSegment {
             .read_json_files(&self.commit_files, commit_read_schema, predicate.clone())?
             .map_ok(|batch| (batch, true));
 
+        // V2 checkpoints may be stored as json, all other checkpoint and sidecar files are parquet
+        let (json_checkpoint_files, parquet_checkpoint_files): (Vec<_>, Vec<_>) = self
+            .checkpoint_files
+            .iter()
+            .chain(self.sidecar_files(engine_interface)?)
+            .cloned()
+            .partition(|f| LogPath(&f.location).extension() == Some("json"));
+        let json_checkpoint_stream = json_client.read_json_files(
+            &json_checkpoint_files,
+            checkpoint_read_schema.clone(),
+            predicate.clone(),
+        )?;
+
         let parquet_client = engine_interface.get_parquet_handler();
         // TODO change predicate to: predicate AND add.path not null
-        let checkpoint_stream = parquet_client
-            .read_parquet_files(&self.checkpoint_files, checkpoint_read_schema, predicate)?
+        let parquet_checkpoint_stream = parquet_client.read_parquet_files(
+            &parquet_checkpoint_files,
+            checkpoint_read_schema,
+            predicate,
+        )?;
+        let checkpoint_stream = json_checkpoint_stream
+            .chain(parquet_checkpoint_stream)
             .map_ok(|batch| (batch, false));
 
         let batches = commit_stream.chain(checkpoint_stream);
@@ -65,10 +104,23 @@ impl LogSegment {
         Ok(batches)
     }
 
-    fn read_metadata(
+    pub(crate) fn read_metadata(
         &self,
         engine_interface: &dyn EngineInterface,
     ) -> DeltaResult<Option<(Metadata, Protocol)>> {
+        match self.read_latest_metadata(engine_interface)? {
+            (Some(m), Some(p)) => Ok(Some((m, p))),
+            (None, Some(_)) => Err(Error::MissingMetadata),
+            (Some(_), None) => Err(Error::MissingProtocol),
+            _ => Err(Error::MissingMetadataAndProtocol),
+        }
+    }
+
+    /// Read the most recent metadata and protocol in this log segment, if any.
+    fn read_latest_metadata(
+        &self,
+        engine_interface: &dyn EngineInterface,
+    ) -> DeltaResult<(Option<Metadata>, Option<Protocol>)> {
         let schema = Arc::new(StructType::new(vec![
             crate::actions::schemas::METADATA_FIELD.clone(),
             crate::actions::schemas::PROTOCOL_FIELD.clone(),
@@ -91,12 +143,7 @@ impl LogSegment {
                 break;
             }
         }
-        match (metadata_opt, protocol_opt) {
-            (Some(m), Some(p)) => Ok(Some((m, p))),
-            (None, Some(_)) => Err(Error::MissingMetadata),
-            (Some(_), None) => Err(Error::MissingProtocol),
-            _ => Err(Error::MissingMetadataAndProtocol),
-        }
+        Ok((metadata_opt, protocol_opt))
     }
 }
 
@@ -112,6 +159,8 @@ pub struct Snapshot {
     metadata: Metadata,
     protocol: Protocol,
     schema: Schema,
+    table_properties: TableProperties,
+    checksum: Option<VersionChecksum>,
 }
 
 impl std::fmt::Debug for Snapshot {
@@ -127,6 +176,13 @@ impl std::fmt::Debug for Snapshot {
 impl Snapshot {
     /// Create a new [`Snapshot`] instance for the given version.
     ///
+    /// Fails with [`Error::Unsupported`] if the table's protocol requires a reader version or table
+    /// features the kernel does not support.
+    ///
+    /// If there is a version checksum (`.crc`) file for the version, the protocol and metadata are
+    /// read from it instead of replaying the log. The checksum is not checked against the log
+    /// unless [`Snapshot::verify_checksum`] is called.
+    ///
     /// # Parameters
     ///
     /// - `location`: url pointing at the table root (where `_delta_log` folder is located)
@@ -140,26 +196,172 @@ impl Snapshot {
         let fs_client = engine_interface.get_file_system_client();
         let log_url = LogPath(&table_root).child("_delta_log/").unwrap();
 
-        // List relevant files from log
-        let (mut commit_files, checkpoint_files) =
-            match (read_last_checkpoint(fs_client.as_ref(), &log_url)?, version) {
-                (Some(cp), Some(version)) if cp.version >= version => {
-                    list_log_files_with_checkpoint(&cp, fs_client.as_ref(), &log_url)?
-                }
-                _ => list_log_files(fs_client.as_ref(), &log_url)?,
-            };
-
-        // remove all files above requested version
-        if let Some(version) = version {
-            commit_files.retain(|meta| {
-                if let Some(v) = LogPath(&meta.location).commit_version() {
-                    v <= version
-                } else {
-                    false
-                }
-            });
+        // List relevant files from log, starting at the last checkpoint if it can be used
+        let start_version = match (read_last_checkpoint(fs_client.as_ref(), &log_url)?, version) {
+            (Some(cp), Some(version)) if cp.version > version => 0,
+            (Some(cp), _) => cp.version,
+            (None, _) => 0,
+        };
+        let (commit_files, checkpoint_files, checksum_file) =
+            list_log_files(fs_client.as_ref(), &log_url, start_version, version)?;
+        Ok(Arc::new(Self::try_new_from_log_files(
+            table_root,
+            engine_interface,
+            log_url,
+            commit_files,
+            checkpoint_files,
+            checksum_file,
+            version,
+        )?))
+    }
+
+    /// Create a new [`Snapshot`] instance for the latest version that was committed at or before
+    /// the given timestamp.
+    ///
+    /// Commit timestamps are the modification times of the commit files, adjusted to be strictly
+    /// increasing with the version. If in-commit timestamps are enabled on the table, commits made
+    /// since their enablement use the `inCommitTimestamp` recorded in their `commitInfo` instead.
+    ///
+    /// # Parameters
+    ///
+    /// - `location`: url pointing at the table root (where `_delta_log` folder is located)
+    /// - `engine_interface`: Implementation of [`EngineInterface`] apis.
+    /// - `timestamp`: target timestamp of the [`Snapshot`], in milliseconds since the unix epoch
+    pub fn try_new_at_timestamp(
+        table_root: Url,
+        engine_interface: &dyn EngineInterface,
+        timestamp: i64,
+    ) -> DeltaResult<Arc<Self>> {
+        let latest = Self::try_new(table_root.clone(), engine_interface, None)?;
+        let version = latest.version_at_timestamp(engine_interface, timestamp)?;
+        if version == latest.version() {
+            Ok(latest)
+        } else {
+            Self::try_new(table_root, engine_interface, Some(version))
+        }
+    }
+
+    /// Create a new [`Snapshot`] instance for the given version by updating an existing snapshot
+    /// of the same table. If no version is given, the snapshot is updated to the latest version.
+    ///
+    /// Only the log files after the existing snapshot's version are listed and read. Unless there
+    /// is a newer checkpoint, which the new snapshot starts from instead, the existing snapshot's
+    /// checkpoint is reused, so the cost of an update is proportional to the number of new
+    /// commits. Like [`Snapshot::try_new`], the protocol and metadata are taken from the version
+    /// checksum of the new version if there is one. If there are no new commits, the existing
+    /// snapshot is returned. A version before the existing snapshot's version cannot be reached by
+    /// an update, so a new snapshot is created for it with [`Snapshot::try_new`].
+    ///
+    /// # Parameters
+    ///
+    /// - `existing`: a snapshot of the table
+    /// - `engine_interface`: Implementation of [`EngineInterface`] apis.
+    /// - `version`: target version of the [`Snapshot`]
+    pub fn try_new_from(
+        existing: Arc<Snapshot>,
+        engine_interface: &dyn EngineInterface,
+        version: Option<Version>,
+    ) -> DeltaResult<Arc<Self>> {
+        match version {
+            Some(version) if version == existing.version => return Ok(existing),
+            Some(version) if version < existing.version => {
+                return Self::try_new(existing.table_root.clone(), engine_interface, Some(version));
+            }
+            _ => {}
         }
 
+        let fs_client = engine_interface.get_file_system_client();
+        let log_root = &existing.log_segment.log_root;
+        let (commit_files, checkpoint_files, checksum_file) =
+            list_log_files_from(fs_client.as_ref(), log_root, existing.version + 1, version)?;
+
+        // the listed commits must follow the existing snapshot, or a new checkpoint, without gaps
+        let base_version = checkpoint_files
+            .first()
+            .and_then(|file| LogPath(&file.location).commit_version())
+            .unwrap_or(existing.version);
+        let mut next_version = base_version + 1;
+        for file in commit_files.iter().rev() {
+            if LogPath(&file.location).commit_version() != Some(next_version) {
+                break;
+            }
+            next_version += 1;
+        }
+        let version_eff = next_version - 1;
+        let has_gap = commit_files.len() as u64 != version_eff - base_version;
+        if has_gap || version.is_some_and(|version| version != version_eff) {
+            let missing = log_root.join(&format!("{:020}.json", next_version))?;
+            return Err(Error::file_not_found(missing));
+        }
+        if version_eff == existing.version {
+            return Ok(existing);
+        }
+
+        // a checkpoint after the existing snapshot replaces its log segment
+        if !checkpoint_files.is_empty() {
+            return Ok(Arc::new(Self::try_new_from_log_files(
+                existing.table_root.clone(),
+                engine_interface,
+                log_root.clone(),
+                commit_files,
+                checkpoint_files,
+                checksum_file,
+                Some(version_eff),
+            )?));
+        }
+
+        // only the new commits can change the protocol and metadata, unless the version checksum
+        // provides them
+        let new_commits = LogSegment {
+            log_root: log_root.clone(),
+            commit_files,
+            checkpoint_files: vec![],
+            sidecar_files: OnceLock::new(),
+        };
+        let checksum = read_checksum(fs_client.as_ref(), checksum_file);
+        let (metadata, protocol) = match checksum {
+            Some(ref checksum) => (checksum.metadata.clone(), checksum.protocol.clone()),
+            None => {
+                let (metadata, protocol) = new_commits.read_latest_metadata(engine_interface)?;
+                (
+                    metadata.unwrap_or_else(|| existing.metadata.clone()),
+                    protocol.unwrap_or_else(|| existing.protocol.clone()),
+                )
+            }
+        };
+
+        let mut commit_files = new_commits.commit_files;
+        commit_files.extend(existing.log_segment.commit_files.iter().cloned());
+        let log_segment = LogSegment {
+            log_root: log_root.clone(),
+            commit_files,
+            checkpoint_files: existing.log_segment.checkpoint_files.clone(),
+            sidecar_files: existing.log_segment.sidecar_files.clone(),
+        };
+
+        Ok(Arc::new(Self::try_new_from_parts(
+            existing.table_root.clone(),
+            log_segment,
+            version_eff,
+            metadata,
+            protocol,
+            checksum,
+        )?))
+    }
+
+    /// Create a new [`Snapshot`] instance from the listed files of its log segment, using its
+    /// version checksum if there is one.
+    fn try_new_from_log_files(
+        table_root: Url,
+        engine_interface: &dyn EngineInterface,
+        log_root: Url,
+        commit_files: Vec<FileMeta>,
+        checkpoint_files: Vec<FileMeta>,
+        checksum_file: Option<FileMeta>,
+        version: Option<Version>,
+    ) -> DeltaResult<Self> {
+        let fs_client = engine_interface.get_file_system_client();
+
         // get the effective version from chosen files
         let version_eff = commit_files
             .first()
@@ -175,17 +377,31 @@ impl Snapshot {
         }
 
         let log_segment = LogSegment {
-            log_root: log_url,
+            log_root,
             commit_files,
             checkpoint_files,
+            sidecar_files: OnceLock::new(),
         };
 
-        Ok(Arc::new(Self::try_new_from_log_segment(
-            table_root,
-            log_segment,
-            version_eff,
-            engine_interface,
-        )?))
+        // with a version checksum, the log (including the sidecars of its checkpoint) is only
+        // replayed once the table state is needed
+        let checksum = read_checksum(fs_client.as_ref(), checksum_file);
+        Ok(match checksum {
+            Some(checksum) => Self::try_new_from_parts(
+                table_root,
+                log_segment,
+                version_eff,
+                checksum.metadata.clone(),
+                checksum.protocol.clone(),
+                Some(checksum),
+            )?,
+            None => Self::try_new_from_log_segment(
+                table_root,
+                log_segment,
+                version_eff,
+                engine_interface,
+            )?,
+        })
     }
 
     /// Create a new [`Snapshot`] instance.
@@ -198,7 +414,22 @@ impl Snapshot {
         let (metadata, protocol) = log_segment
             .read_metadata(engine_interface)?
             .ok_or(Error::MissingMetadata)?;
+        Self::try_new_from_parts(location, log_segment, version, metadata, protocol, None)
+    }
+
+    /// Create a new [`Snapshot`] instance from the protocol and metadata of its version. Fails
+    /// with [`Error::Unsupported`] if the kernel cannot read tables with the protocol.
+    fn try_new_from_parts(
+        location: Url,
+        log_segment: LogSegment,
+        version: Version,
+        metadata: Metadata,
+        protocol: Protocol,
+        checksum: Option<VersionChecksum>,
+    ) -> DeltaResult<Self> {
+        protocol.ensure_read_supported()?;
         let schema = metadata.schema()?;
+        let table_properties = TableProperties::try_from(&metadata.configuration)?;
         Ok(Self {
             table_root: location,
             log_segment,
@@ -206,9 +437,25 @@ impl Snapshot {
             metadata,
             protocol,
             schema,
+            table_properties,
+            checksum,
         })
     }
 
+    /// Write a checkpoint of this snapshot's version, and point `_last_checkpoint` at it unless
+    /// that already refers to a more recent checkpoint.
+    ///
+    /// The checkpoint holds the reconciled state of the table: its protocol and metadata, the
+    /// latest transaction of each application, the active domain metadata, all files in the
+    /// table, and the tombstones of files that were removed within the period configured by
+    /// `delta.deletedFileRetentionDuration` (one week by default).
+    ///
+    /// Fails with [`Error::Unsupported`] if the kernel cannot write to the table.
+    pub fn checkpoint(&self, engine_interface: &dyn EngineInterface) -> DeltaResult<()> {
+        self.ensure_write_supported()?;
+        crate::checkpoint::write_checkpoint(self, engine_interface)
+    }
+
     /// Log segment this snapshot uses
     #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
     fn _log_segment(&self) -> &LogSegment {
@@ -233,6 +480,103 @@ impl Snapshot {
     pub fn protocol(&self) -> &Protocol {
         &self.protocol
     }
+
+    /// The [`TableProperties`] of the table at this [`Snapshot`]s version.
+    pub fn table_properties(&self) -> &TableProperties {
+        &self.table_properties
+    }
+
+    /// The [`VersionChecksum`] of this [`Snapshot`]s version, if the snapshot was loaded from it.
+    /// It holds the size and number of files of the table, without replaying the log.
+    pub fn checksum(&self) -> Option<&VersionChecksum> {
+        self.checksum.as_ref()
+    }
+
+    /// The [`ColumnMappingMode`] of the table at this [`Snapshot`]s version.
+    pub fn column_mapping_mode(&self) -> ColumnMappingMode {
+        // the column mapping mode is checked to be valid when the snapshot is created
+        self.table_properties
+            .column_mapping_mode()
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// The first version from which commits carry an in-commit timestamp, or `None` if in-commit
+    /// timestamps are not enabled on this [`Snapshot`].
+    fn in_commit_timestamp_enablement_version(&self) -> DeltaResult<Option<Version>> {
+        let properties = &self.table_properties;
+        if !properties.enable_in_commit_timestamps()?.unwrap_or(false) {
+            return Ok(None);
+        }
+        // tables created with in-commit timestamps enabled don't record an enablement version
+        Ok(Some(
+            properties
+                .in_commit_timestamp_enablement_version()?
+                .unwrap_or(0),
+        ))
+    }
+
+    /// Find the latest version up to this [`Snapshot`]s version that was committed at or before
+    /// `timestamp`.
+    fn version_at_timestamp(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        timestamp: i64,
+    ) -> DeltaResult<Version> {
+        let fs_client = engine_interface.get_file_system_client();
+        let commits =
+            list_commit_timestamps(fs_client.as_ref(), &self.log_segment.log_root, self.version)?;
+
+        // commits since in-commit timestamp enablement must be resolved by reading the commit
+        let ict_start = match self.in_commit_timestamp_enablement_version()? {
+            Some(enablement) => commits.partition_point(|c| c.version < enablement),
+            None => commits.len(),
+        };
+        let commit_timestamp = |idx: usize| -> DeltaResult<i64> {
+            let commit = &commits[idx];
+            if idx < ict_start {
+                Ok(commit.timestamp)
+            } else {
+                read_in_commit_timestamp(engine_interface, commit)
+            }
+        };
+
+        let (earliest, latest) = match (commits.first(), commits.last()) {
+            (Some(earliest), Some(latest)) => (earliest, latest),
+            _ => return Err(Error::MissingVersion),
+        };
+        let earliest_timestamp = commit_timestamp(0)?;
+        if timestamp < earliest_timestamp {
+            return Err(Error::TimestampBeforeEarliestVersion(
+                timestamp,
+                earliest.version,
+                earliest_timestamp,
+            ));
+        }
+        let latest_timestamp = commit_timestamp(commits.len() - 1)?;
+        if timestamp > latest_timestamp {
+            return Err(Error::TimestampAfterLatestVersion(
+                timestamp,
+                latest.version,
+                latest_timestamp,
+            ));
+        }
+
+        // Binary search the last commit at or before the timestamp. File timestamps are already
+        // known, so only search the in-commit timestamps (which need a read per commit) if needed.
+        let idx = if ict_start < commits.len() && commit_timestamp(ict_start)? <= timestamp {
+            let ict_len = commits.len() - ict_start;
+            ict_start
+                + try_partition_point(ict_len, |i| {
+                    Ok(commit_timestamp(ict_start + i)? <= timestamp)
+                })?
+        } else {
+            commits[..ict_start].partition_point(|c| c.timestamp <= timestamp)
+        };
+        // the earliest commit is at or before the timestamp, so there is at least one match
+        Ok(commits[idx - 1].version)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -244,23 +588,28 @@ struct CheckpointMetadata {
     #[allow(unreachable_pub)] // used by acceptance tests (TODO make an fn accessor?)
     pub version: Version,
     /// The number of actions that are stored in the checkpoint.
-    pub(crate) size: i32,
+    pub(crate) size: i64,
     /// The number of fragments if the last checkpoint was written in multiple parts.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) parts: Option<i32>,
     /// The number of bytes of the checkpoint.
-    pub(crate) size_in_bytes: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) size_in_bytes: Option<i64>,
     /// The number of AddFile actions in the checkpoint.
-    pub(crate) num_of_add_files: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) num_of_add_files: Option<i64>,
     /// The schema of the checkpoint file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) checkpoint_schema: Option<Schema>,
     /// The checksum of the last checkpoint JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) checksum: Option<String>,
 }
 
 /// Try reading the `_last_checkpoint` file.
 ///
 /// In case the file is not found, `None` is returned.
-fn read_last_checkpoint(
+pub(crate) fn read_last_checkpoint(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
 ) -> DeltaResult<Option<CheckpointMetadata>> {
@@ -275,94 +624,318 @@ fn read_last_checkpoint(
     }
 }
 
-/// List all log files after a given checkpoint.
-fn list_log_files_with_checkpoint(
-    cp: &CheckpointMetadata,
-    fs_client: &dyn FileSystemClient,
+/// Read the `sidecar` actions of a V2 checkpoint and resolve the files they reference.
+///
+/// Sidecar paths are relative to the `_delta_log/_sidecars` directory, unless they are absolute.
+fn read_sidecar_files(
+    engine_interface: &dyn EngineInterface,
     log_root: &Url,
-) -> DeltaResult<(Vec<FileMeta>, Vec<FileMeta>)> {
-    let version_prefix = format!("{:020}", cp.version);
-    let start_from = log_root.join(&version_prefix)?;
+    checkpoint_file: &FileMeta,
+) -> DeltaResult<Vec<FileMeta>> {
+    let schema = Arc::new(StructType::new(vec![SIDECAR_FIELD.clone()]));
+    let files = std::slice::from_ref(checkpoint_file);
+    let batches = match LogPath(&checkpoint_file.location).extension() {
+        Some("json") => {
+            engine_interface
+                .get_json_handler()
+                .read_json_files(files, schema.clone(), None)?
+        }
+        _ => engine_interface.get_parquet_handler().read_parquet_files(
+            files,
+            schema.clone(),
+            None,
+        )?,
+    };
+    let mut visitor = SidecarVisitor::default();
+    for batch in batches {
+        batch?.extract(schema.clone(), &mut visitor)?;
+    }
 
-    let files = fs_client
-        .list_from(&start_from)?
-        .collect::<Result<Vec<_>, Error>>()?
+    let sidecar_root = LogPath(log_root).child("_sidecars/")?;
+    visitor
+        .sidecars
         .into_iter()
-        // TODO this filters out .crc files etc which start with "." - how do we want to use these kind of files?
-        .filter(|f| LogPath(&f.location).commit_version().is_some())
-        .collect::<Vec<_>>();
-
-    let mut commit_files = files
-        .iter()
-        .filter_map(|f| {
-            if LogPath(&f.location).is_commit_file() {
-                Some(f.clone())
-            } else {
-                None
-            }
+        .map(|sidecar| {
+            Ok(FileMeta {
+                location: LogPath(&sidecar_root).child(&sidecar.path)?,
+                last_modified: sidecar.modification_time,
+                size: sidecar.size_in_bytes.try_into().map_err(|_| {
+                    Error::generic(format!(
+                        "Invalid size {} for sidecar file {}",
+                        sidecar.size_in_bytes, sidecar.path
+                    ))
+                })?,
+            })
         })
-        .collect_vec();
-    // NOTE this will sort in reverse order
-    commit_files.sort_unstable_by(|a, b| b.location.cmp(&a.location));
+        .collect()
+}
 
-    let checkpoint_files = files
-        .iter()
-        .filter_map(|f| {
-            if LogPath(&f.location).is_checkpoint_file() {
-                Some(f.clone())
-            } else {
-                None
+/// Select the files of the most recent complete checkpoint among the given checkpoint files.
+///
+/// Classic and V2 checkpoints are complete by themselves, while multi-part checkpoints are only
+/// complete if all of their parts are present. Incomplete checkpoints, e.g. ones that are still
+/// being written, are skipped in favor of older ones.
+fn latest_complete_checkpoint(checkpoint_files: Vec<FileMeta>) -> Vec<FileMeta> {
+    let mut checkpoints_by_version: BTreeMap<Version, Vec<FileMeta>> = BTreeMap::new();
+    for file in checkpoint_files {
+        if let Some(version) = LogPath(&file.location).commit_version() {
+            checkpoints_by_version
+                .entry(version)
+                .or_default()
+                .push(file);
+        }
+    }
+
+    for (_, mut files) in checkpoints_by_version.into_iter().rev() {
+        files.sort_unstable_by(|a, b| a.location.cmp(&b.location));
+        // a version may have multiple multi-part checkpoints with differing numbers of parts
+        let mut multi_part_checkpoints: HashMap<u32, Vec<FileMeta>> = HashMap::new();
+        for file in files {
+            match LogPath(&file.location).checkpoint_kind() {
+                Some(CheckpointKind::SinglePart | CheckpointKind::V2) => return vec![file],
+                Some(CheckpointKind::MultiPart { num_parts, .. }) => {
+                    multi_part_checkpoints
+                        .entry(num_parts)
+                        .or_default()
+                        .push(file);
+                }
+                None => {}
             }
-        })
-        .collect_vec();
+        }
+        let complete = multi_part_checkpoints
+            .into_iter()
+            .find(|(num_parts, parts)| parts.len() == *num_parts as usize);
+        if let Some((_, parts)) = complete {
+            return parts;
+        }
+    }
+    vec![]
+}
 
-    // TODO raise a proper error
-    assert_eq!(checkpoint_files.len(), cp.parts.unwrap_or(1) as usize);
+/// A commit file along with the timestamp it was committed at according to the file system.
+pub(crate) struct CommitTimestamp {
+    pub(crate) version: Version,
+    pub(crate) file: FileMeta,
+    /// File modification time, adjusted to be strictly greater than that of the previous commit
+    pub(crate) timestamp: i64,
+}
 
-    Ok((commit_files, checkpoint_files))
+/// List the commits up to `max_version` with their file modification timestamps.
+///
+/// Only commits that can still be reconstructed are returned, i.e. if the start of the log has
+/// been cleaned up, commits before the earliest checkpoint are skipped. As file modification times
+/// may not be monotonic, each timestamp is bumped to be greater than that of its predecessor.
+pub(crate) fn list_commit_timestamps(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+    max_version: Version,
+) -> DeltaResult<Vec<CommitTimestamp>> {
+    let version_prefix = format!("{:020}", 0);
+    let start_from = log_root.join(&version_prefix)?;
+
+    let mut earliest_checkpoint_version = None;
+    let mut commits = Vec::new();
+    for maybe_meta in fs_client.list_from(&start_from)? {
+        let meta = maybe_meta?;
+        let log_path = LogPath(&meta.location);
+        let version = match log_path.commit_version() {
+            Some(version) if version <= max_version => version,
+            _ => continue,
+        };
+        if log_path.is_checkpoint_file() && earliest_checkpoint_version.is_none() {
+            earliest_checkpoint_version = Some(version);
+        } else if log_path.is_commit_file() {
+            commits.push(CommitTimestamp {
+                version,
+                timestamp: meta.last_modified,
+                file: meta,
+            });
+        }
+    }
+    commits.sort_unstable_by_key(|c| c.version);
+
+    if commits.first().is_some_and(|c| c.version != 0) {
+        if let Some(checkpoint_version) = earliest_checkpoint_version {
+            commits.retain(|c| c.version >= checkpoint_version);
+        }
+    }
+
+    let mut previous_timestamp = i64::MIN;
+    for commit in commits.iter_mut() {
+        if commit.timestamp <= previous_timestamp {
+            commit.timestamp = previous_timestamp + 1;
+        }
+        previous_timestamp = commit.timestamp;
+    }
+    Ok(commits)
+}
+
+/// Read the `inCommitTimestamp` from the `commitInfo` of the given commit.
+fn read_in_commit_timestamp(
+    engine_interface: &dyn EngineInterface,
+    commit: &CommitTimestamp,
+) -> DeltaResult<i64> {
+    let schema = Arc::new(StructType::new(vec![IN_COMMIT_TIMESTAMP_FIELD.clone()]));
+    let mut visitor = InCommitTimestampVisitor::default();
+    let batches = engine_interface.get_json_handler().read_json_files(
+        std::slice::from_ref(&commit.file),
+        schema.clone(),
+        None,
+    )?;
+    for batch in batches {
+        batch?.extract(schema.clone(), &mut visitor)?;
+        if visitor.in_commit_timestamp.is_some() {
+            break;
+        }
+    }
+    visitor.in_commit_timestamp.ok_or_else(|| {
+        Error::missing_data(format!(
+            "commitInfo.inCommitTimestamp in commit {}",
+            commit.version
+        ))
+    })
+}
+
+/// Returns the number of leading indexes in `0..len` for which `pred` holds, assuming `pred`
+/// holds for some prefix of the range and for none of the remaining indexes.
+fn try_partition_point(
+    len: usize,
+    mut pred: impl FnMut(usize) -> DeltaResult<bool>,
+) -> DeltaResult<usize> {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid)? {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Read the version checksum `file`, if there is one. An unreadable checksum is no reason to fail
+/// loading a snapshot, as the log can be replayed instead, so errors are only logged.
+fn read_checksum(
+    fs_client: &dyn FileSystemClient,
+    file: Option<FileMeta>,
+) -> Option<VersionChecksum> {
+    file.and_then(|file| {
+        read_version_checksum(fs_client, &file)
+            .map_err(|err| warn!("Ignoring version checksum {}: {err}", file.location))
+            .ok()
+    })
+}
+
+/// List the commit files for all versions from `start_version` to `end_version` (inclusive),
+/// failing if any of them is missing. If no end version is given, all commits from the start
+/// version on are listed.
+pub(crate) fn list_commit_files(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+    start_version: Version,
+    end_version: Option<Version>,
+) -> DeltaResult<Vec<(Version, FileMeta)>> {
+    let start_from = log_root.join(&format!("{:020}", start_version))?;
+
+    let mut commits = vec![];
+    let mut next_version = start_version;
+    for maybe_meta in fs_client.list_from(&start_from)? {
+        let meta = maybe_meta?;
+        let log_path = LogPath(&meta.location);
+        if !log_path.is_commit_file() {
+            continue;
+        }
+        match log_path.commit_version() {
+            Some(version) if end_version.is_some_and(|end_version| version > end_version) => break,
+            Some(version) if version == next_version => {
+                commits.push((version, meta));
+                next_version += 1;
+            }
+            _ => break,
+        }
+    }
+    if end_version.is_some_and(|end_version| next_version <= end_version) {
+        let missing = log_root.join(&format!("{:020}.json", next_version))?;
+        return Err(Error::file_not_found(missing));
+    }
+    Ok(commits)
 }
 
 /// List relevant log files.
 ///
-/// Relevant files are the max checkpoint found and all subsequent commits.
+/// Relevant files are the most recent complete checkpoint, all subsequent commits and the version
+/// checksum file of the latest version, if there is one. Listing
+/// starts at `start_version`, which should be the version of a known checkpoint; if no complete
+/// checkpoint is found from there on, the log is listed again from the beginning to fall back to
+/// an older checkpoint. If `max_version` is given, no files after that version are considered.
 fn list_log_files(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
-) -> DeltaResult<(Vec<FileMeta>, Vec<FileMeta>)> {
-    let version_prefix = format!("{:020}", 0);
+    start_version: Version,
+    max_version: Option<Version>,
+) -> DeltaResult<(Vec<FileMeta>, Vec<FileMeta>, Option<FileMeta>)> {
+    let files = list_log_files_from(fs_client, log_root, start_version, max_version)?;
+    if files.1.is_empty() && start_version > 0 {
+        return list_log_files_from(fs_client, log_root, 0, max_version);
+    }
+    Ok(files)
+}
+
+/// List the relevant log files from `start_version` on, like [`list_log_files`] but without
+/// falling back to older checkpoints. Without a complete checkpoint from `start_version` on, all
+/// listed commits are relevant.
+fn list_log_files_from(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+    start_version: Version,
+    max_version: Option<Version>,
+) -> DeltaResult<(Vec<FileMeta>, Vec<FileMeta>, Option<FileMeta>)> {
+    let version_prefix = format!("{:020}", start_version);
     let start_from = log_root.join(&version_prefix)?;
 
-    let mut max_checkpoint_version = -1_i64;
     let mut commit_files = Vec::new();
     let mut checkpoint_files = Vec::with_capacity(10);
+    let mut checksum_file: Option<(Version, FileMeta)> = None;
 
     for maybe_meta in fs_client.list_from(&start_from)? {
         let meta = maybe_meta?;
-        if LogPath(&meta.location).is_checkpoint_file() {
-            let version = LogPath(&meta.location).commit_version().unwrap_or(0) as i64;
-            match version.cmp(&max_checkpoint_version) {
-                Ordering::Greater => {
-                    max_checkpoint_version = version;
-                    checkpoint_files.clear();
-                    checkpoint_files.push(meta);
-                }
-                Ordering::Equal => {
-                    checkpoint_files.push(meta);
-                }
-                _ => {}
-            }
-        } else if LogPath(&meta.location).is_commit_file() {
+        let log_path = LogPath(&meta.location);
+        let Some(version) = log_path.commit_version() else {
+            continue;
+        };
+        if max_version.is_some_and(|max_version| version > max_version) {
+            continue;
+        }
+        if log_path.is_checkpoint_file() {
+            checkpoint_files.push(meta);
+        } else if log_path.is_commit_file() {
             commit_files.push(meta);
+        } else if log_path.is_checksum_file()
+            && checksum_file.as_ref().is_none_or(|(v, _)| *v < version)
+        {
+            checksum_file = Some((version, meta));
         }
     }
 
-    commit_files.retain(|f| {
-        LogPath(&f.location).commit_version().unwrap_or(0) as i64 > max_checkpoint_version
-    });
+    let checkpoint_files = latest_complete_checkpoint(checkpoint_files);
+    let checkpoint_version = checkpoint_files
+        .first()
+        .and_then(|file| LogPath(&file.location).commit_version());
+
+    commit_files.retain(|f| LogPath(&f.location).commit_version() > checkpoint_version);
     // NOTE this will sort in reverse order
     commit_files.sort_unstable_by(|a, b| b.location.cmp(&a.location));
 
-    Ok((commit_files, checkpoint_files))
+    let latest_version = commit_files
+        .first()
+        .or(checkpoint_files.first())
+        .and_then(|f| LogPath(&f.location).commit_version());
+    let checksum_file = checksum_file
+        .filter(|(version, _)| Some(*version) == latest_version)
+        .map(|(_, file)| file);
+
+    Ok((commit_files, checkpoint_files, checksum_file))
 }
 
 #[cfg(test)]
@@ -378,6 +951,7 @@ mod tests {
     use crate::filesystem::ObjectStoreFileSystemClient;
     use crate::schema::StructType;
     use crate::simple_client::SimpleClient;
+    use crate::test_utils::{add, write_test_table, METADATA, PROTOCOL};
 
     #[test]
     fn test_snapshot_read_metadata() {
@@ -463,4 +1037,562 @@ mod tests {
             Some(3)
         );
     }
+
+    fn commit_path(table_root: &std::path::Path, version: Version) -> PathBuf {
+        table_root.join(format!("_delta_log/{:020}.json", version))
+    }
+
+    fn set_commit_timestamp(table_root: &std::path::Path, version: Version, millis: u64) {
+        let file = std::fs::File::options()
+            .write(true)
+            .open(commit_path(table_root, version))
+            .unwrap();
+        let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis);
+        file.set_modified(mtime).unwrap();
+    }
+
+    /// Copy a table from the test data into a temporary directory, so file times can be modified.
+    fn copy_test_table(name: &str) -> tempfile::TempDir {
+        let source = std::fs::canonicalize(PathBuf::from("./tests/data/").join(name)).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("_delta_log")).unwrap();
+        for entry in std::fs::read_dir(source.join("_delta_log")).unwrap() {
+            let entry = entry.unwrap();
+            let target = dir.path().join("_delta_log").join(entry.file_name());
+            std::fs::copy(entry.path(), target).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_snapshot_at_timestamp() {
+        let dir = copy_test_table("with_checkpoint_no_last_checkpoint");
+        for version in 0..4 {
+            set_commit_timestamp(dir.path(), version, 1000 * (version + 1));
+        }
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+
+        let at = |timestamp| {
+            Snapshot::try_new_at_timestamp(location.clone(), &engine_interface, timestamp)
+                .map(|snapshot| snapshot.version())
+        };
+        assert_eq!(at(1000).unwrap(), 0);
+        assert_eq!(at(2500).unwrap(), 1);
+        assert_eq!(at(3000).unwrap(), 2);
+        assert_eq!(at(4000).unwrap(), 3);
+
+        assert!(matches!(
+            at(999),
+            Err(Error::TimestampBeforeEarliestVersion(999, 0, 1000))
+        ));
+        assert!(matches!(
+            at(4001),
+            Err(Error::TimestampAfterLatestVersion(4001, 3, 4000))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_at_timestamp_non_monotonic_file_times() {
+        let dir = copy_test_table("with_checkpoint_no_last_checkpoint");
+        set_commit_timestamp(dir.path(), 0, 1000);
+        set_commit_timestamp(dir.path(), 1, 3000);
+        // version 2 is treated as committed at 3001, and version 3 at 3002
+        set_commit_timestamp(dir.path(), 2, 2000);
+        set_commit_timestamp(dir.path(), 3, 3000);
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+
+        let at = |timestamp| {
+            Snapshot::try_new_at_timestamp(location.clone(), &engine_interface, timestamp)
+                .unwrap()
+                .version()
+        };
+        assert_eq!(at(2500), 0);
+        assert_eq!(at(3000), 1);
+        assert_eq!(at(3001), 2);
+        assert_eq!(at(3002), 3);
+    }
+
+    #[test]
+    fn test_snapshot_at_timestamp_in_commit_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("_delta_log")).unwrap();
+        let commits = [
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}
+{"metaData":{"id":"testId","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"value\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{},"createdTime":1677811175819}}"#,
+            r#"{"commitInfo":{"timestamp":90000,"inCommitTimestamp":5000,"operation":"SET TBLPROPERTIES"}}
+{"protocol":{"minReaderVersion":1,"minWriterVersion":7,"writerFeatures":["inCommitTimestamp"]}}
+{"metaData":{"id":"testId","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"value\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{"delta.enableInCommitTimestamps":"true","delta.inCommitTimestampEnablementVersion":"1","delta.inCommitTimestampEnablementTimestamp":"5000"},"createdTime":1677811175819}}"#,
+            r#"{"commitInfo":{"timestamp":80000,"inCommitTimestamp":6000,"operation":"WRITE"}}"#,
+            r#"{"commitInfo":{"timestamp":70000,"inCommitTimestamp":7000,"operation":"WRITE"}}"#,
+        ];
+        for (version, commit) in commits.iter().enumerate() {
+            std::fs::write(commit_path(dir.path(), version as Version), commit).unwrap();
+            // file times disagree with the in-commit timestamps, which must take precedence
+            set_commit_timestamp(
+                dir.path(),
+                version as Version,
+                1000 + 100_000 * version as u64,
+            );
+        }
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+
+        let at = |timestamp| {
+            Snapshot::try_new_at_timestamp(location.clone(), &engine_interface, timestamp)
+                .map(|snapshot| snapshot.version())
+        };
+        assert_eq!(at(1000).unwrap(), 0);
+        assert_eq!(at(4999).unwrap(), 0);
+        assert_eq!(at(5000).unwrap(), 1);
+        assert_eq!(at(6500).unwrap(), 2);
+        assert_eq!(at(7000).unwrap(), 3);
+        assert!(matches!(
+            at(500),
+            Err(Error::TimestampBeforeEarliestVersion(500, 0, 1000))
+        ));
+        assert!(matches!(
+            at(7001),
+            Err(Error::TimestampAfterLatestVersion(7001, 3, 7000))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_at_timestamp_invalid_in_commit_timestamp_properties() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = METADATA.replace(
+            r#""configuration":{}"#,
+            r#""configuration":{"delta.enableInCommitTimestamps":"true","delta.inCommitTimestampEnablementVersion":"one"}"#,
+        );
+        let location = write_test_table(dir.path(), &[PROTOCOL.into(), metadata]);
+        let engine_interface = SimpleClient::new();
+
+        // the invalid property only fails resolving timestamps, not loading the snapshot
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        assert!(snapshot
+            .table_properties()
+            .in_commit_timestamp_enablement_version()
+            .is_err());
+        let result = Snapshot::try_new_at_timestamp(location, &engine_interface, i64::MAX);
+        assert!(matches!(result, Err(Error::Generic(_))));
+    }
+
+    #[test]
+    fn test_update_snapshot() {
+        let dir = copy_test_table("with_checkpoint_no_last_checkpoint");
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+        let files = |snapshot: Arc<Snapshot>| {
+            let scan = crate::scan::ScanBuilder::new(snapshot).build();
+            let mut paths = scan
+                .files(&engine_interface)
+                .unwrap()
+                .map(|add| add.unwrap().path)
+                .collect_vec();
+            paths.sort();
+            paths
+        };
+
+        let existing = Snapshot::try_new(location.clone(), &engine_interface, Some(1)).unwrap();
+        assert!(existing.log_segment.checkpoint_files.is_empty());
+        let same = Snapshot::try_new_from(existing.clone(), &engine_interface, Some(1)).unwrap();
+        assert!(Arc::ptr_eq(&existing, &same));
+
+        let updated = Snapshot::try_new_from(existing.clone(), &engine_interface, None).unwrap();
+        let latest = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        assert_eq!(updated.version(), 3);
+        assert_eq!(updated.metadata(), latest.metadata());
+        assert_eq!(updated.protocol(), latest.protocol());
+        // the update starts from the checkpoint written after the existing snapshot
+        let checkpoint_files = &updated.log_segment.checkpoint_files;
+        assert_eq!(checkpoint_files.len(), 1);
+        assert_eq!(
+            LogPath(&checkpoint_files[0].location).commit_version(),
+            Some(2)
+        );
+        assert_eq!(updated.log_segment.commit_files.len(), 1);
+        assert_eq!(files(updated.clone()), files(latest));
+
+        // there are no commits after the latest version
+        let same = Snapshot::try_new_from(updated.clone(), &engine_interface, None).unwrap();
+        assert!(Arc::ptr_eq(&updated, &same));
+
+        let older = Snapshot::try_new_from(updated.clone(), &engine_interface, Some(0)).unwrap();
+        assert_eq!(older.version(), 0);
+
+        assert!(matches!(
+            Snapshot::try_new_from(existing, &engine_interface, Some(4)),
+            Err(Error::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_snapshot_metadata() {
+        let dir = copy_test_table("with_checkpoint_no_last_checkpoint");
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+        let existing = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        let schema = r#"{\"type\":\"struct\",\"fields\":[{\"name\":\"value\",\"type\":\"long\",\"nullable\":true,\"metadata\":{}}]}"#;
+        let commit = format!(
+            r#"{{"metaData":{{"id":"{}","format":{{"provider":"parquet","options":{{}}}},"schemaString":"{schema}","partitionColumns":[],"configuration":{{}},"createdTime":1677811175819}}}}"#,
+            existing.metadata().id
+        );
+        std::fs::write(commit_path(dir.path(), 4), commit).unwrap();
+
+        let updated = Snapshot::try_new_from(existing.clone(), &engine_interface, None).unwrap();
+        assert_eq!(updated.version(), 4);
+        assert_eq!(updated.protocol(), existing.protocol());
+        let expected = StructType::new(vec![crate::schema::StructField::new(
+            "value",
+            crate::schema::DataType::LONG,
+            true,
+        )]);
+        assert_eq!(updated.schema(), &expected);
+        assert_eq!(
+            updated.log_segment.checkpoint_files,
+            existing.log_segment.checkpoint_files
+        );
+    }
+
+    #[test]
+    fn test_snapshot_unsupported_reader_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("_delta_log")).unwrap();
+        let commit = [
+            r#"{"protocol":{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":["typeWidening"],"writerFeatures":["typeWidening"]}}"#,
+            r#"{"metaData":{"id":"testId","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"value\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{},"createdTime":1677811175819}}"#,
+        ];
+        std::fs::write(commit_path(dir.path(), 0), commit.join("\n")).unwrap();
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+
+        let result = Snapshot::try_new(location, &SimpleClient::new(), None);
+        assert!(
+            matches!(result, Err(Error::Unsupported(ref m)) if m.contains("typeWidening")),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_with_checksum() {
+        use crate::checksum::FileSizeHistogram;
+
+        let dir = copy_test_table("with_checkpoint_no_last_checkpoint");
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+        let crc_path = dir.path().join("_delta_log/00000000000000000003.crc");
+
+        let latest = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        assert!(latest.checksum().is_none());
+        let sizes: Vec<_> = crate::scan::ScanBuilder::new(latest.clone())
+            .build()
+            .files(&engine_interface)
+            .unwrap()
+            .map(|add| add.unwrap().size)
+            .collect();
+        let checksum = VersionChecksum {
+            txn_id: None,
+            table_size_bytes: sizes.iter().sum(),
+            num_files: sizes.len() as i64,
+            num_metadata: 1,
+            num_protocol: 1,
+            in_commit_timestamp: None,
+            metadata: latest.metadata().clone(),
+            protocol: latest.protocol().clone(),
+            file_size_histogram: Some(FileSizeHistogram {
+                sorted_bin_boundaries: vec![0, 1024],
+                file_counts: vec![sizes.len() as i64, 0],
+                total_bytes: vec![sizes.iter().sum(), 0],
+            }),
+            num_deleted_records: None,
+            num_deletion_vectors: None,
+            deleted_record_counts_histogram: None,
+        };
+        std::fs::write(&crc_path, serde_json::to_vec(&checksum).unwrap()).unwrap();
+
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        assert_eq!(snapshot.checksum(), Some(&checksum));
+        assert_eq!(snapshot.metadata(), latest.metadata());
+        assert_eq!(snapshot.protocol(), latest.protocol());
+        snapshot.verify_checksum(&engine_interface).unwrap();
+
+        // the checksum only describes its own version
+        let older = Snapshot::try_new(location.clone(), &engine_interface, Some(2)).unwrap();
+        assert!(older.checksum().is_none());
+
+        let wrong = VersionChecksum {
+            num_files: checksum.num_files + 1,
+            ..checksum
+        };
+        std::fs::write(&crc_path, serde_json::to_vec(&wrong).unwrap()).unwrap();
+        // the checksum is trusted unless it is verified
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        assert_eq!(snapshot.checksum(), Some(&wrong));
+        assert!(matches!(
+            snapshot.verify_checksum(&engine_interface),
+            Err(Error::ChecksumMismatch(3, _))
+        ));
+
+        // an unreadable checksum falls back to replaying the log
+        std::fs::write(&crc_path, "{").unwrap();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+        assert!(snapshot.checksum().is_none());
+        assert_eq!(snapshot.metadata(), latest.metadata());
+    }
+
+    #[test]
+    fn test_update_snapshot_with_checksum() {
+        let dir = copy_test_table("with_checkpoint_no_last_checkpoint");
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+
+        let existing = Snapshot::try_new(location.clone(), &engine_interface, Some(2)).unwrap();
+        let latest = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        let sizes: Vec<_> = crate::scan::ScanBuilder::new(latest.clone())
+            .build()
+            .files(&engine_interface)
+            .unwrap()
+            .map(|add| add.unwrap().size)
+            .collect();
+        let checksum = VersionChecksum {
+            txn_id: None,
+            table_size_bytes: sizes.iter().sum(),
+            num_files: sizes.len() as i64,
+            num_metadata: 1,
+            num_protocol: 1,
+            in_commit_timestamp: None,
+            metadata: latest.metadata().clone(),
+            protocol: latest.protocol().clone(),
+            file_size_histogram: None,
+            num_deleted_records: None,
+            num_deletion_vectors: None,
+            deleted_record_counts_histogram: None,
+        };
+        std::fs::write(
+            dir.path().join("_delta_log/00000000000000000003.crc"),
+            serde_json::to_vec(&checksum).unwrap(),
+        )
+        .unwrap();
+
+        let updated = Snapshot::try_new_from(existing.clone(), &engine_interface, None).unwrap();
+        assert_eq!(updated.version(), 3);
+        assert_eq!(updated.checksum(), Some(&checksum));
+        assert_eq!(updated.metadata(), latest.metadata());
+        assert_eq!(
+            updated.log_segment.checkpoint_files,
+            existing.log_segment.checkpoint_files
+        );
+        updated.verify_checksum(&engine_interface).unwrap();
+
+        // a gap in the new commits is not skipped over
+        std::fs::write(commit_path(dir.path(), 5), "").unwrap();
+        assert!(matches!(
+            Snapshot::try_new_from(existing, &engine_interface, None),
+            Err(Error::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_list_log_files_incomplete_multi_part_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir(&log_dir).unwrap();
+        for name in [
+            "00000000000000000000.json",
+            "00000000000000000001.json",
+            "00000000000000000002.json",
+            "00000000000000000002.checkpoint.0000000001.0000000002.parquet",
+            "00000000000000000002.checkpoint.0000000002.0000000002.parquet",
+            "00000000000000000003.json",
+            "00000000000000000004.json",
+            // part 2 of the checkpoint at version 4 is missing
+            "00000000000000000004.checkpoint.0000000001.0000000003.parquet",
+            "00000000000000000004.checkpoint.0000000003.0000000003.parquet",
+            "00000000000000000005.json",
+        ] {
+            std::fs::write(log_dir.join(name), "").unwrap();
+        }
+        let log_root = url::Url::from_directory_path(&log_dir).unwrap();
+        let engine_interface = SimpleClient::new();
+        let fs_client = engine_interface.get_file_system_client();
+        let versions = |files: &[FileMeta]| {
+            files
+                .iter()
+                .map(|f| LogPath(&f.location).commit_version().unwrap())
+                .collect_vec()
+        };
+
+        // listing from the version of the incomplete checkpoint falls back to the complete one
+        for start_version in [0, 4] {
+            let (commit_files, checkpoint_files, _) =
+                list_log_files(fs_client.as_ref(), &log_root, start_version, None).unwrap();
+            assert_eq!(versions(&checkpoint_files), vec![2, 2]);
+            assert_eq!(versions(&commit_files), vec![5, 4, 3]);
+        }
+
+        let (commit_files, checkpoint_files, _) =
+            list_log_files(fs_client.as_ref(), &log_root, 0, Some(1)).unwrap();
+        assert!(checkpoint_files.is_empty());
+        assert_eq!(versions(&commit_files), vec![1, 0]);
+    }
+
+    const V2_CHECKPOINT_PROTOCOL: &str = r#"{"protocol":{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":["v2Checkpoint"],"writerFeatures":["v2Checkpoint"]}}"#;
+
+    fn sidecar(path: &str) -> String {
+        format!(
+            r#"{{"sidecar":{{"path":"{path}","sizeInBytes":1000,"modificationTime":1587968586000}}}}"#
+        )
+    }
+
+    /// Write actions given as JSON lines to a parquet file with the given action fields.
+    fn write_parquet_actions(
+        path: &std::path::Path,
+        fields: Vec<crate::schema::StructField>,
+        actions: &[String],
+    ) {
+        let schema = Arc::new(arrow_schema::Schema::try_from(&StructType::new(fields)).unwrap());
+        let batch = arrow_json::ReaderBuilder::new(schema.clone())
+            .build(actions.join("\n").as_bytes())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn test_read_v2_checkpoint_with_sidecar() {
+        const CHECKPOINT: &str =
+            "00000000000000000001.checkpoint.80a083e8-7026-4e79-81be-64bd76c43a11.json";
+        const SIDECAR: &str = "3a0d65cd-4056-49b8-937b-95f9e3ee90e5.parquet";
+
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir_all(log_dir.join("_sidecars")).unwrap();
+
+        // the sidecar holds the file actions of the checkpoint
+        write_parquet_actions(
+            &log_dir.join("_sidecars").join(SIDECAR),
+            vec![crate::actions::schemas::ADD_FIELD.clone()],
+            &[add("a.parquet"), add("b.parquet")],
+        );
+
+        let checkpoint = [
+            r#"{"checkpointMetadata":{"version":1}}"#.to_string(),
+            V2_CHECKPOINT_PROTOCOL.to_string(),
+            METADATA.to_string(),
+            sidecar(SIDECAR),
+        ];
+        std::fs::write(log_dir.join(CHECKPOINT), checkpoint.join("\n")).unwrap();
+        std::fs::write(log_dir.join("00000000000000000002.json"), add("c.parquet")).unwrap();
+
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+
+        assert_eq!(snapshot.version(), 2);
+        let checkpoint_files = &snapshot.log_segment.checkpoint_files;
+        assert_eq!(checkpoint_files.len(), 1);
+        assert_eq!(
+            LogPath(&checkpoint_files[0].location).filename(),
+            Some(CHECKPOINT)
+        );
+        // the sidecars were resolved to read the protocol and metadata from the checkpoint
+        let sidecar_files = snapshot.log_segment.sidecar_files.get().unwrap();
+        assert_eq!(sidecar_files.len(), 1);
+        assert_eq!(
+            LogPath(&sidecar_files[0].location).filename(),
+            Some(SIDECAR)
+        );
+
+        // with a version checksum, the sidecars are only resolved to replay the file actions
+        let checksum = VersionChecksum {
+            txn_id: None,
+            table_size_bytes: 3 * 262,
+            num_files: 3,
+            num_metadata: 1,
+            num_protocol: 1,
+            in_commit_timestamp: None,
+            metadata: snapshot.metadata().clone(),
+            protocol: snapshot.protocol().clone(),
+            file_size_histogram: None,
+            num_deleted_records: None,
+            num_deletion_vectors: None,
+            deleted_record_counts_histogram: None,
+        };
+        std::fs::write(
+            log_dir.join("00000000000000000002.crc"),
+            serde_json::to_vec(&checksum).unwrap(),
+        )
+        .unwrap();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+        assert_eq!(snapshot.checksum(), Some(&checksum));
+        assert!(snapshot.log_segment.sidecar_files.get().is_none());
+
+        let scan = crate::scan::ScanBuilder::new(snapshot.clone()).build();
+        let mut paths = scan
+            .files(&engine_interface)
+            .unwrap()
+            .map(|add| add.unwrap().path)
+            .collect_vec();
+        paths.sort();
+        assert_eq!(paths, vec!["a.parquet", "b.parquet", "c.parquet"]);
+        assert!(snapshot.log_segment.sidecar_files.get().is_some());
+    }
+
+    #[test]
+    fn test_read_classic_named_v2_checkpoint_with_sidecar() {
+        use crate::actions::schemas::{ADD_FIELD, METADATA_FIELD, PROTOCOL_FIELD, SIDECAR_FIELD};
+
+        const SIDECAR: &str = "016ae953-37a9-438e-8683-9a9a4a79a395.parquet";
+
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir_all(log_dir.join("_sidecars")).unwrap();
+        write_parquet_actions(
+            &log_dir.join("_sidecars").join(SIDECAR),
+            vec![ADD_FIELD.clone()],
+            &[add("a.parquet"), add("b.parquet")],
+        );
+        // a V2 checkpoint with the name of a classic checkpoint
+        write_parquet_actions(
+            &log_dir.join("00000000000000000001.checkpoint.parquet"),
+            vec![
+                PROTOCOL_FIELD.clone(),
+                METADATA_FIELD.clone(),
+                SIDECAR_FIELD.clone(),
+            ],
+            &[
+                V2_CHECKPOINT_PROTOCOL.to_string(),
+                METADATA.to_string(),
+                sidecar(SIDECAR),
+            ],
+        );
+
+        let location = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        assert_eq!(snapshot.version(), 1);
+        assert_eq!(snapshot.log_segment.checkpoint_files.len(), 1);
+        let sidecar_files = snapshot.log_segment.sidecar_files.get().unwrap();
+        assert_eq!(sidecar_files.len(), 1);
+        assert_eq!(
+            LogPath(&sidecar_files[0].location).filename(),
+            Some(SIDECAR)
+        );
+
+        let scan = crate::scan::ScanBuilder::new(snapshot).build();
+        let mut paths = scan
+            .files(&engine_interface)
+            .unwrap()
+            .map(|add| add.unwrap().path)
+            .collect_vec();
+        paths.sort();
+        assert_eq!(paths, vec!["a.parquet", "b.parquet"]);
+    }
 }
@@ -2,8 +2,14 @@ use std::sync::Arc;
 
 use url::Url;
 
-use crate::snapshot::Snapshot;
-use crate::{DeltaResult, EngineInterface, Version};
+use crate::actions::schemas::COMMIT_INFO_FIELD;
+use crate::actions::CommitInfo;
+use crate::path::LogPath;
+use crate::scan::TableChangesBuilder;
+use crate::schema::StructType;
+use crate::snapshot::{read_last_checkpoint, Snapshot};
+use crate::transaction::Transaction;
+use crate::{DeltaResult, EngineInterface, FileMeta, Version};
 
 /// In-memory representation of a Delta table, which acts as an immutable root entity for reading
 /// the different versions (see [`Snapshot`]) of the table located in storage.
@@ -41,6 +47,117 @@ impl Table {
     ) -> DeltaResult<Arc<Snapshot>> {
         Snapshot::try_new(self.location.clone(), engine_interface, version)
     }
+
+    /// Create a [`Snapshot`] of the table as of `timestamp`, i.e. of the latest version committed
+    /// at or before the given number of milliseconds since the unix epoch.
+    ///
+    /// See [`Snapshot::try_new_at_timestamp`] for how commit timestamps are determined.
+    pub fn snapshot_at_timestamp(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        timestamp: i64,
+    ) -> DeltaResult<Arc<Snapshot>> {
+        Snapshot::try_new_at_timestamp(self.location.clone(), engine_interface, timestamp)
+    }
+
+    /// Create a [`TableChangesBuilder`] to read the changes made to the table by the commits from
+    /// `start_version` up to and including `end_version`.
+    ///
+    /// If no end version is supplied, changes up to the latest version will be read.
+    pub fn table_changes(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        start_version: Version,
+        end_version: Option<Version>,
+    ) -> DeltaResult<TableChangesBuilder> {
+        let snapshot = self.snapshot(engine_interface, end_version)?;
+        Ok(TableChangesBuilder::new(snapshot, start_version))
+    }
+
+    /// Read the [`CommitInfo`] of the commits of the table from the latest version backwards, like
+    /// `DESCRIBE HISTORY`. If a `limit` is supplied, at most that many commits are returned, and
+    /// the log is only listed from the latest checkpoint recorded in `_last_checkpoint`, or from
+    /// the first of the requested commits if that is older.
+    ///
+    /// All commits whose files are still retained in the log are included, even those older than
+    /// the latest checkpoint. Commits without a `commitInfo` action get a default [`CommitInfo`].
+    /// The `timestamp` of each returned [`CommitInfo`] is the time of its commit: the
+    /// `inCommitTimestamp` if there is one, else the `timestamp` of the `commitInfo` action, else
+    /// the modification time of the commit file.
+    pub fn history(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        limit: Option<usize>,
+    ) -> DeltaResult<Vec<(Version, CommitInfo)>> {
+        let log_root = LogPath(&self.location).child("_delta_log/")?;
+        let fs_client = engine_interface.get_file_system_client();
+        let list_commits = |start_version: Version| -> DeltaResult<Vec<(Version, FileMeta)>> {
+            let start_from = log_root.join(&format!("{:020}", start_version))?;
+            let mut commits = vec![];
+            for maybe_meta in fs_client.list_from(&start_from)? {
+                let meta = maybe_meta?;
+                let log_path = LogPath(&meta.location);
+                match log_path.commit_version() {
+                    Some(version) if log_path.is_commit_file() => commits.push((version, meta)),
+                    _ => {}
+                }
+            }
+            Ok(commits)
+        };
+
+        let mut commits = match limit {
+            Some(limit) => {
+                // the latest checkpoint tells where the latest version can be found
+                let checkpoint_version = read_last_checkpoint(fs_client.as_ref(), &log_root)?
+                    .map_or(0, |checkpoint| checkpoint.version);
+                let commits = list_commits(checkpoint_version)?;
+                let start_version = commits
+                    .iter()
+                    .map(|(version, _)| *version)
+                    .max()
+                    .map_or(0, |latest| (latest + 1).saturating_sub(limit as Version));
+                if start_version < checkpoint_version {
+                    list_commits(start_version)?
+                } else {
+                    commits
+                }
+            }
+            None => list_commits(0)?,
+        };
+        commits.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+        commits.truncate(limit.unwrap_or(commits.len()));
+
+        let json_handler = engine_interface.get_json_handler();
+        let schema = Arc::new(StructType::new(vec![COMMIT_INFO_FIELD.clone()]));
+        commits
+            .into_iter()
+            .map(|(version, file)| {
+                let last_modified = file.last_modified;
+                let batches = json_handler.read_json_files(&[file], schema.clone(), None)?;
+                let mut commit_info = None;
+                for batch in batches {
+                    commit_info = CommitInfo::try_new_from_data(batch?.as_ref())?;
+                    if commit_info.is_some() {
+                        break;
+                    }
+                }
+                let mut commit_info = commit_info.unwrap_or_default();
+                commit_info.timestamp = commit_info
+                    .in_commit_timestamp
+                    .or(commit_info.timestamp)
+                    .or(Some(last_modified));
+                Ok((version, commit_info))
+            })
+            .collect()
+    }
+
+    /// Start a [`Transaction`] to commit changes on top of the latest version of the table.
+    pub fn new_transaction(
+        &self,
+        engine_interface: &dyn EngineInterface,
+    ) -> DeltaResult<Transaction> {
+        Ok(Transaction::new(self.snapshot(engine_interface, None)?))
+    }
 }
 
 #[cfg(test)]
@@ -49,6 +166,7 @@ mod tests {
 
     use super::*;
     use crate::simple_client::SimpleClient;
+    use crate::test_utils::{add, write_commit, write_test_table, METADATA, PROTOCOL};
 
     #[test]
     fn test_table() {
@@ -60,4 +178,130 @@ mod tests {
         let snapshot = table.snapshot(&engine_interface, None).unwrap();
         assert_eq!(snapshot.version(), 1)
     }
+
+    #[test]
+    fn test_history() {
+        let path = std::fs::canonicalize(PathBuf::from(
+            "./tests/data/with_checkpoint_no_last_checkpoint/",
+        ))
+        .unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine_interface = SimpleClient::new();
+        let table = Table::new(url);
+
+        // commits before the checkpoint at version 2 are included
+        let history = table.history(&engine_interface, None).unwrap();
+        let versions: Vec<_> = history.iter().map(|(version, _)| *version).collect();
+        assert_eq!(versions, [3, 2, 1, 0]);
+
+        let (_, create) = &history[3];
+        assert_eq!(create.timestamp, Some(1674611455099));
+        assert_eq!(create.operation.as_deref(), Some("CREATE TABLE"));
+        // null operation parameters are left out
+        assert_eq!(create.operation_parameters.get("description"), None);
+        assert_eq!(create.operation_parameters["partitionBy"], "[]");
+        assert!(create.operation_metrics.is_empty());
+        assert_eq!(create.isolation_level.as_deref(), Some("Serializable"));
+        assert_eq!(create.is_blind_append, Some(true));
+        assert_eq!(
+            create.engine_info.as_deref(),
+            Some("Apache-Spark/3.3.1 Delta-Lake/2.1.1")
+        );
+
+        let history = table.history(&engine_interface, Some(2)).unwrap();
+        let (version, latest) = &history[0];
+        assert_eq!(history.len(), 2);
+        assert_eq!(*version, 3);
+        assert_eq!(latest.read_version, Some(2));
+        assert_eq!(latest.operation_metrics["numOutputBytes"], "1010");
+    }
+
+    #[test]
+    fn test_history_commit_info_without_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(
+            dir.path(),
+            &[
+                r#"{"commitInfo":{"operation":"WRITE","engineInfo":"test"}}"#.into(),
+                PROTOCOL.into(),
+                METADATA.into(),
+            ],
+        );
+        write_commit(dir.path(), 1, &[add("a.parquet")]);
+        let history = Table::new(location)
+            .history(&SimpleClient::new(), None)
+            .unwrap();
+
+        // commits without a commitInfo action or its timestamp get that of the commit file
+        let (version, commit_info) = &history[0];
+        assert_eq!(*version, 1);
+        assert_eq!(
+            commit_info,
+            &CommitInfo {
+                timestamp: Some(file_modification_time(dir.path(), 1)),
+                ..Default::default()
+            }
+        );
+        let (version, commit_info) = &history[1];
+        assert_eq!(*version, 0);
+        assert_eq!(
+            commit_info.timestamp,
+            Some(file_modification_time(dir.path(), 0))
+        );
+        assert_eq!(commit_info.operation.as_deref(), Some("WRITE"));
+        assert_eq!(commit_info.engine_info.as_deref(), Some("test"));
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(
+            dir.path(),
+            &[
+                r#"{"commitInfo":{"timestamp":1000,"inCommitTimestamp":5000}}"#.into(),
+                PROTOCOL.into(),
+                METADATA.into(),
+            ],
+        );
+        write_commit(
+            dir.path(),
+            1,
+            &[
+                r#"{"commitInfo":{"timestamp":2000}}"#.into(),
+                add("a.parquet"),
+            ],
+        );
+        write_commit(dir.path(), 2, &[add("b.parquet")]);
+        std::fs::write(
+            dir.path().join("_delta_log/_last_checkpoint"),
+            r#"{"version":1,"size":3}"#,
+        )
+        .unwrap();
+        let table = Table::new(location);
+        let engine_interface = SimpleClient::new();
+        let timestamps = |limit| {
+            table
+                .history(&engine_interface, Some(limit))
+                .unwrap()
+                .into_iter()
+                .map(|(version, commit_info)| (version, commit_info.timestamp.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        let latest = (2, file_modification_time(dir.path(), 2));
+        assert_eq!(timestamps(1), [latest]);
+        assert_eq!(timestamps(2), [latest, (1, 2000)]);
+        // the in-commit timestamp takes precedence, and commits before the checkpoint are listed
+        assert_eq!(timestamps(3), [latest, (1, 2000), (0, 5000)]);
+        assert_eq!(timestamps(10), timestamps(3));
+    }
+
+    fn file_modification_time(table_root: &std::path::Path, version: Version) -> i64 {
+        let path = table_root.join(format!("_delta_log/{version:020}.json"));
+        let modified = std::fs::metadata(path).unwrap().modified().unwrap();
+        modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+    }
 }
@@ -0,0 +1,391 @@
+//! Table features, and checks that the kernel supports the features of a table's [`Protocol`].
+//!
+//! Readers and writers must support the features a table's protocol requires for reading and
+//! writing respectively. With reader version 3 and writer version 7, the protocol lists the
+//! required features by name; older protocol versions imply a fixed set of features instead.
+
+use std::fmt::{Display, Formatter};
+
+use crate::actions::Protocol;
+use crate::schema::{ColumnMetadataKey, DataType, StructType};
+use crate::snapshot::Snapshot;
+use crate::{DeltaResult, Error};
+
+/// A feature of a Delta table, as named in the `readerFeatures` and `writerFeatures` of its
+/// protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TableFeature {
+    /// `appendOnly`: files may not be removed from the table
+    AppendOnly,
+    /// `invariants`: the values of columns must satisfy the SQL expressions of their invariants
+    Invariants,
+    /// `checkConstraints`: rows must satisfy the table's CHECK constraints
+    CheckConstraints,
+    /// `changeDataFeed`: changes to the table may be recorded as change data files
+    ChangeDataFeed,
+    /// `generatedColumns`: the values of columns are generated from other columns
+    GeneratedColumns,
+    /// `columnMapping`: columns have physical names in the data files differing from their names
+    ColumnMapping,
+    /// `identityColumns`: columns get unique, increasing values on insertion
+    IdentityColumns,
+    /// `deletionVectors`: rows of files may be deleted by deletion vectors
+    DeletionVectors,
+    /// `rowTracking`: rows have stable ids across commits
+    RowTracking,
+    /// `timestampNtz`: columns may have the timestamp without time zone type
+    TimestampWithoutTimezone,
+    /// `domainMetadata`: the log may hold domain metadata actions
+    DomainMetadata,
+    /// `v2Checkpoint`: checkpoints may be V2 checkpoints, with sidecar files
+    V2Checkpoint,
+    /// `icebergCompatV1`: the table is kept readable as an Iceberg table (V1)
+    IcebergCompatV1,
+    /// `icebergCompatV2`: the table is kept readable as an Iceberg table (V2)
+    IcebergCompatV2,
+    /// `clustering`: the data files are clustered by the table's clustering columns
+    Clustering,
+    /// `vacuumProtocolCheck`: vacuum must check that it supports the table's protocol
+    VacuumProtocolCheck,
+    /// `inCommitTimestamp`: commits record their timestamp in their `commitInfo`
+    InCommitTimestamp,
+    /// `typeWidening`: the types of columns may have been widened since files were written
+    TypeWidening,
+    /// `typeWidening-preview`: the preview version of `typeWidening`
+    TypeWideningPreview,
+    /// `variantType`: columns may have the variant type
+    VariantType,
+    /// `variantType-preview`: the preview version of `variantType`
+    VariantTypePreview,
+    /// A feature unknown to the kernel
+    Unknown(String),
+}
+
+impl AsRef<str> for TableFeature {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::AppendOnly => "appendOnly",
+            Self::Invariants => "invariants",
+            Self::CheckConstraints => "checkConstraints",
+            Self::ChangeDataFeed => "changeDataFeed",
+            Self::GeneratedColumns => "generatedColumns",
+            Self::ColumnMapping => "columnMapping",
+            Self::IdentityColumns => "identityColumns",
+            Self::DeletionVectors => "deletionVectors",
+            Self::RowTracking => "rowTracking",
+            Self::TimestampWithoutTimezone => "timestampNtz",
+            Self::DomainMetadata => "domainMetadata",
+            Self::V2Checkpoint => "v2Checkpoint",
+            Self::IcebergCompatV1 => "icebergCompatV1",
+            Self::IcebergCompatV2 => "icebergCompatV2",
+            Self::Clustering => "clustering",
+            Self::VacuumProtocolCheck => "vacuumProtocolCheck",
+            Self::InCommitTimestamp => "inCommitTimestamp",
+            Self::TypeWidening => "typeWidening",
+            Self::TypeWideningPreview => "typeWidening-preview",
+            Self::VariantType => "variantType",
+            Self::VariantTypePreview => "variantType-preview",
+            Self::Unknown(name) => name,
+        }
+    }
+}
+
+impl From<&str> for TableFeature {
+    fn from(name: &str) -> Self {
+        match name {
+            "appendOnly" => Self::AppendOnly,
+            "invariants" => Self::Invariants,
+            "checkConstraints" => Self::CheckConstraints,
+            "changeDataFeed" => Self::ChangeDataFeed,
+            "generatedColumns" => Self::GeneratedColumns,
+            "columnMapping" => Self::ColumnMapping,
+            "identityColumns" => Self::IdentityColumns,
+            "deletionVectors" => Self::DeletionVectors,
+            "rowTracking" => Self::RowTracking,
+            "timestampNtz" => Self::TimestampWithoutTimezone,
+            "domainMetadata" => Self::DomainMetadata,
+            "v2Checkpoint" => Self::V2Checkpoint,
+            "icebergCompatV1" => Self::IcebergCompatV1,
+            "icebergCompatV2" => Self::IcebergCompatV2,
+            "clustering" => Self::Clustering,
+            "vacuumProtocolCheck" => Self::VacuumProtocolCheck,
+            "inCommitTimestamp" => Self::InCommitTimestamp,
+            "typeWidening" => Self::TypeWidening,
+            "typeWidening-preview" => Self::TypeWideningPreview,
+            "variantType" => Self::VariantType,
+            "variantType-preview" => Self::VariantTypePreview,
+            name => Self::Unknown(name.to_string()),
+        }
+    }
+}
+
+impl Display for TableFeature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+/// The highest reader version the kernel supports.
+const MAX_READER_VERSION: i32 = 3;
+
+/// The writer versions the kernel supports. Writer versions 3 to 6 imply features the kernel does
+/// not support, e.g. check constraints.
+const SUPPORTED_WRITER_VERSIONS: [i32; 3] = [1, 2, 7];
+
+/// The features the kernel supports when reading tables.
+const SUPPORTED_READER_FEATURES: [TableFeature; 4] = [
+    TableFeature::ColumnMapping,
+    TableFeature::DeletionVectors,
+    TableFeature::V2Checkpoint,
+    TableFeature::VacuumProtocolCheck,
+];
+
+/// The features the kernel supports when writing to tables. Invariants are only supported as long
+/// as no column has any (see [`Snapshot::ensure_write_supported`]).
+const SUPPORTED_WRITER_FEATURES: [TableFeature; 6] = [
+    TableFeature::AppendOnly,
+    TableFeature::Invariants,
+    TableFeature::ColumnMapping,
+    TableFeature::DeletionVectors,
+    TableFeature::DomainMetadata,
+    TableFeature::VacuumProtocolCheck,
+];
+
+impl Protocol {
+    /// The features a client must support to read the table. Only listed with reader version 3.
+    pub fn reader_table_features(&self) -> impl Iterator<Item = TableFeature> + '_ {
+        let features = self.reader_features.iter().flatten();
+        features.map(|feature| feature.as_str().into())
+    }
+
+    /// The features a client must support to write to the table. Only listed with writer
+    /// version 7.
+    pub fn writer_table_features(&self) -> impl Iterator<Item = TableFeature> + '_ {
+        let features = self.writer_features.iter().flatten();
+        features.map(|feature| feature.as_str().into())
+    }
+
+    /// Check that the kernel supports reading tables with this protocol, failing with
+    /// [`Error::Unsupported`] if it does not.
+    pub fn ensure_read_supported(&self) -> DeltaResult<()> {
+        if self.min_reader_version > MAX_READER_VERSION {
+            return Err(Error::unsupported(format!(
+                "reader version {} of the table protocol",
+                self.min_reader_version
+            )));
+        }
+        let unsupported = self
+            .reader_table_features()
+            .filter(|feature| !SUPPORTED_READER_FEATURES.contains(feature))
+            .collect::<Vec<_>>();
+        ensure_features_supported(&unsupported, "reading")
+    }
+
+    /// Check that the kernel supports writing to tables with this protocol, failing with
+    /// [`Error::Unsupported`] if it does not.
+    pub fn ensure_write_supported(&self) -> DeltaResult<()> {
+        self.ensure_read_supported()?;
+        if !SUPPORTED_WRITER_VERSIONS.contains(&self.min_writer_version) {
+            return Err(Error::unsupported(format!(
+                "writer version {} of the table protocol",
+                self.min_writer_version
+            )));
+        }
+        let unsupported = self
+            .writer_table_features()
+            .filter(|feature| !SUPPORTED_WRITER_FEATURES.contains(feature))
+            .collect::<Vec<_>>();
+        ensure_features_supported(&unsupported, "writing")
+    }
+
+    /// Whether the table may have column invariants, which writer version 2 implies.
+    fn has_invariants(&self) -> bool {
+        match self.min_writer_version {
+            7 => self
+                .writer_table_features()
+                .any(|feature| feature == TableFeature::Invariants),
+            version => version >= 2,
+        }
+    }
+}
+
+fn ensure_features_supported(unsupported: &[TableFeature], operation: &str) -> DeltaResult<()> {
+    match unsupported {
+        [] => Ok(()),
+        [feature] => Err(Error::unsupported(format!(
+            "table feature {feature} required for {operation}"
+        ))),
+        features => Err(Error::unsupported(format!(
+            "table features {} required for {operation}",
+            features
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Whether any (possibly nested) field of the schema has an invariant.
+fn schema_has_invariants(schema: &StructType) -> bool {
+    fn type_has_invariants(data_type: &DataType) -> bool {
+        match data_type {
+            DataType::Struct(structure) => schema_has_invariants(structure),
+            DataType::Array(array) => type_has_invariants(&array.element_type),
+            DataType::Map(map) => {
+                type_has_invariants(&map.key_type) || type_has_invariants(&map.value_type)
+            }
+            DataType::Primitive(_) => false,
+        }
+    }
+    schema.fields().any(|field| {
+        field
+            .get_config_value(&ColumnMetadataKey::Invariants)
+            .is_some()
+            || type_has_invariants(field.data_type())
+    })
+}
+
+impl Snapshot {
+    /// Check that the kernel supports writing to the table at this snapshot, failing with
+    /// [`Error::Unsupported`] if it does not. Besides the protocol, the kernel does not support
+    /// enforcing column invariants, so writing to tables with any is not supported.
+    pub(crate) fn ensure_write_supported(&self) -> DeltaResult<()> {
+        self.protocol().ensure_write_supported()?;
+        if self.protocol().has_invariants() && schema_has_invariants(self.schema()) {
+            return Err(Error::unsupported(
+                "writing to tables with column invariants",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::StructField;
+
+    fn protocol(
+        min_reader_version: i32,
+        min_writer_version: i32,
+        reader_features: Option<&[&str]>,
+        writer_features: Option<&[&str]>,
+    ) -> Protocol {
+        let features = |features: &[&str]| features.iter().map(ToString::to_string).collect();
+        Protocol {
+            min_reader_version,
+            min_writer_version,
+            reader_features: reader_features.map(features),
+            writer_features: writer_features.map(features),
+        }
+    }
+
+    #[test]
+    fn test_table_feature_names() {
+        let features = [
+            TableFeature::AppendOnly,
+            TableFeature::TimestampWithoutTimezone,
+            TableFeature::TypeWideningPreview,
+            TableFeature::VariantType,
+            TableFeature::Unknown("someFeature".to_string()),
+        ];
+        for feature in features {
+            assert_eq!(TableFeature::from(feature.as_ref()), feature);
+        }
+        assert_eq!(
+            TableFeature::TypeWideningPreview.to_string(),
+            "typeWidening-preview"
+        );
+    }
+
+    #[test]
+    fn test_ensure_read_supported() {
+        let supported = [
+            protocol(1, 2, None, None),
+            protocol(2, 5, None, None),
+            protocol(3, 7, Some(&["deletionVectors"]), Some(&["deletionVectors"])),
+            protocol(3, 7, Some(&[]), Some(&["inCommitTimestamp", "someFeature"])),
+        ];
+        for protocol in supported {
+            assert!(protocol.ensure_read_supported().is_ok(), "{protocol:?}");
+        }
+
+        let unsupported = [
+            (
+                protocol(4, 7, None, None),
+                "reader version 4 of the table protocol",
+            ),
+            (
+                protocol(3, 7, Some(&["typeWidening"]), None),
+                "table feature typeWidening required for reading",
+            ),
+            (
+                protocol(
+                    3,
+                    7,
+                    Some(&["columnMapping", "variantType", "someFeature"]),
+                    None,
+                ),
+                "table features variantType, someFeature required for reading",
+            ),
+        ];
+        for (protocol, message) in unsupported {
+            assert!(
+                matches!(protocol.ensure_read_supported(), Err(Error::Unsupported(m)) if m == message),
+                "{protocol:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ensure_write_supported() {
+        let supported = [
+            protocol(1, 2, None, None),
+            protocol(
+                3,
+                7,
+                Some(&["deletionVectors"]),
+                Some(&["deletionVectors", "appendOnly"]),
+            ),
+        ];
+        for protocol in supported {
+            assert!(protocol.ensure_write_supported().is_ok(), "{protocol:?}");
+        }
+
+        let unsupported = [
+            protocol(1, 4, None, None),
+            protocol(1, 7, None, Some(&["inCommitTimestamp"])),
+            protocol(3, 7, Some(&["v2Checkpoint"]), Some(&["v2Checkpoint"])),
+            protocol(3, 7, Some(&["typeWidening"]), Some(&[])),
+        ];
+        for protocol in unsupported {
+            assert!(
+                matches!(
+                    protocol.ensure_write_supported(),
+                    Err(Error::Unsupported(_))
+                ),
+                "{protocol:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_has_invariants() {
+        let invariant = (
+            ColumnMetadataKey::Invariants.as_ref(),
+            r#"{"expression":{"expression":"value > 0"}}"#.to_string(),
+        );
+        let plain = StructType::new(vec![StructField::new("value", DataType::INTEGER, true)]);
+        assert!(!schema_has_invariants(&plain));
+
+        let nested = StructType::new(vec![StructField::new(
+            "nested",
+            StructType::new(vec![
+                StructField::new("value", DataType::INTEGER, true).with_metadata([invariant])
+            ]),
+            true,
+        )]);
+        assert!(schema_has_invariants(&nested));
+    }
+}
@@ -0,0 +1,491 @@
+//! Typed access to the table properties defined by the Delta protocol.
+//!
+//! Table properties are stored as strings in the `configuration` of a table's [`Metadata`]. The
+//! [`TableProperties`] of a [`Snapshot`] parse the values of all properties the protocol defines,
+//! while any other properties are kept as they are. An invalid value is reported as an error by
+//! the accessor of its property, so it only fails the code that uses it, unless it affects how
+//! the table is read.
+//!
+//! [`Metadata`]: crate::actions::Metadata
+//! [`Snapshot`]: crate::snapshot::Snapshot
+
+use std::collections::HashMap;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::expressions::ColumnName;
+use crate::schema::ColumnMappingMode;
+use crate::{DeltaResult, Error, Version};
+
+macro_rules! table_properties {
+    ($($(#[$attr:meta])* $field:ident: $ty:ty = $key:literal,)+) => {
+        /// The table properties defined by the Delta protocol, parsed from the configuration of a
+        /// table's metadata. Properties which are not set are `None`, so that their defaults are
+        /// left to the code using them.
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct TableProperties {
+            $($field: Option<Result<$ty, String>>,)+
+            unknown_properties: HashMap<String, String>,
+        }
+
+        impl TableProperties {
+            $(
+                $(#[$attr])*
+                ///
+                /// Fails if the value of the property is invalid.
+                pub fn $field(&self) -> DeltaResult<Option<$ty>> {
+                    property_value($key, &self.$field)
+                }
+            )+
+
+            /// Parse `value` into the field of the table property `key`, returning `false` if the
+            /// property is not defined by the protocol. Invalid values are kept as they are.
+            fn parse_property(&mut self, key: &str, value: &str) -> bool {
+                match key {
+                    $($key => {
+                        self.$field =
+                            Some(ParseProperty::parse_property(value).ok_or_else(|| value.into()))
+                    })+
+                    _ => return false,
+                }
+                true
+            }
+        }
+    };
+}
+
+table_properties! {
+    /// `delta.appendOnly`: files may not be removed from the table
+    append_only: bool = "delta.appendOnly",
+    /// `delta.checkpointInterval`: the number of commits between checkpoints
+    checkpoint_interval: NonZeroU64 = "delta.checkpointInterval",
+    /// `delta.checkpoint.writeStatsAsJson`: whether checkpoints store file statistics as JSON
+    checkpoint_write_stats_as_json: bool = "delta.checkpoint.writeStatsAsJson",
+    /// `delta.checkpoint.writeStatsAsStruct`: whether checkpoints store file statistics as a struct
+    checkpoint_write_stats_as_struct: bool = "delta.checkpoint.writeStatsAsStruct",
+    /// `delta.checkpointPolicy`: the kind of checkpoints to write
+    checkpoint_policy: CheckpointPolicy = "delta.checkpointPolicy",
+    /// `delta.columnMapping.mode`: how logical columns map to the columns of data files
+    column_mapping_mode: ColumnMappingMode = "delta.columnMapping.mode",
+    /// `delta.dataSkippingNumIndexedCols`: the number of leading columns to collect statistics on
+    data_skipping_num_indexed_cols: DataSkippingNumIndexedCols = "delta.dataSkippingNumIndexedCols",
+    /// `delta.dataSkippingStatsColumns`: the columns to collect statistics on, which takes
+    /// precedence over `delta.dataSkippingNumIndexedCols`
+    data_skipping_stats_columns: Vec<ColumnName> = "delta.dataSkippingStatsColumns",
+    /// `delta.deletedFileRetentionDuration`: how long tombstones of removed files are kept
+    deleted_file_retention_duration: Duration = "delta.deletedFileRetentionDuration",
+    /// `delta.enableChangeDataFeed`: whether writers record change data files
+    enable_change_data_feed: bool = "delta.enableChangeDataFeed",
+    /// `delta.enableDeletionVectors`: whether writers may delete rows with deletion vectors
+    enable_deletion_vectors: bool = "delta.enableDeletionVectors",
+    /// `delta.enableExpiredLogCleanup`: whether commits older than the log retention are removed
+    enable_expired_log_cleanup: bool = "delta.enableExpiredLogCleanup",
+    /// `delta.enableInCommitTimestamps`: whether commits record their timestamp in `commitInfo`
+    enable_in_commit_timestamps: bool = "delta.enableInCommitTimestamps",
+    /// `delta.enableRowTracking`: whether writers assign row ids and commit versions to rows
+    enable_row_tracking: bool = "delta.enableRowTracking",
+    /// `delta.enableTypeWidening`: whether the types of columns may be widened
+    enable_type_widening: bool = "delta.enableTypeWidening",
+    /// `delta.inCommitTimestampEnablementVersion`: the first version with an in-commit timestamp
+    in_commit_timestamp_enablement_version: Version = "delta.inCommitTimestampEnablementVersion",
+    /// `delta.inCommitTimestampEnablementTimestamp`: the in-commit timestamp of that version, in
+    /// milliseconds since the epoch
+    in_commit_timestamp_enablement_timestamp: i64 = "delta.inCommitTimestampEnablementTimestamp",
+    /// `delta.isolationLevel`: the isolation level of transactions
+    isolation_level: IsolationLevel = "delta.isolationLevel",
+    /// `delta.logRetentionDuration`: how long the commits of the log are kept
+    log_retention_duration: Duration = "delta.logRetentionDuration",
+    /// `delta.randomizeFilePrefixes`: whether data files are written under random prefixes
+    randomize_file_prefixes: bool = "delta.randomizeFilePrefixes",
+    /// `delta.randomPrefixLength`: the number of characters of random file prefixes
+    random_prefix_length: NonZeroU64 = "delta.randomPrefixLength",
+    /// `delta.setTransactionRetentionDuration`: how long transactions of applications are kept
+    set_transaction_retention_duration: Duration = "delta.setTransactionRetentionDuration",
+    /// `delta.targetFileSize`: the target size of data files in bytes
+    target_file_size: NonZeroU64 = "delta.targetFileSize",
+}
+
+impl TableProperties {
+    /// All properties which are not defined by the protocol, by key
+    pub fn unknown_properties(&self) -> &HashMap<String, String> {
+        &self.unknown_properties
+    }
+}
+
+fn property_value<T: Clone>(
+    key: &str,
+    value: &Option<Result<T, String>>,
+) -> DeltaResult<Option<T>> {
+    match value {
+        Some(Ok(value)) => Ok(Some(value.clone())),
+        Some(Err(value)) => Err(Error::generic(format!(
+            "Invalid value '{value}' for table property {key}"
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// The number of leading columns of the schema to collect statistics on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSkippingNumIndexedCols {
+    /// Collect statistics on all columns, set as `-1`
+    AllColumns,
+    /// Collect statistics on this many leading columns
+    NumColumns(u64),
+}
+
+impl FromStr for DataSkippingNumIndexedCols {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i64>() {
+            Ok(-1) => Ok(Self::AllColumns),
+            Ok(n) if n >= 0 => Ok(Self::NumColumns(n as u64)),
+            _ => Err(Error::generic(format!(
+                "Invalid number of indexed columns: {s}"
+            ))),
+        }
+    }
+}
+
+/// The isolation level of transactions on the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// Transactions behave as if they were executed one after the other
+    Serializable,
+    /// Only writes are serializable, reads may observe a state that never existed
+    WriteSerializable,
+    /// Transactions read from a snapshot and only conflict on concurrent writes
+    SnapshotIsolation,
+}
+
+impl FromStr for IsolationLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "serializable" => Ok(Self::Serializable),
+            "writeserializable" => Ok(Self::WriteSerializable),
+            "snapshotisolation" => Ok(Self::SnapshotIsolation),
+            _ => Err(Error::generic(format!("Invalid isolation level: {s}"))),
+        }
+    }
+}
+
+/// The kind of checkpoints to write for the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointPolicy {
+    /// Classic checkpoints, which may be single-part or multi-part
+    Classic,
+    /// V2 checkpoints, which may reference sidecar files
+    V2,
+}
+
+impl FromStr for CheckpointPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "classic" => Ok(Self::Classic),
+            "v2" => Ok(Self::V2),
+            _ => Err(Error::generic(format!("Invalid checkpoint policy: {s}"))),
+        }
+    }
+}
+
+/// Parse the table properties of a metadata `configuration`. Properties without a value are
+/// ignored. Invalid values are only reported when their property is used, except for
+/// `delta.columnMapping.mode`, which must be valid to read the table at all.
+impl TryFrom<&HashMap<String, Option<String>>> for TableProperties {
+    type Error = Error;
+
+    fn try_from(configuration: &HashMap<String, Option<String>>) -> DeltaResult<Self> {
+        let mut properties = TableProperties::default();
+        for (key, value) in configuration {
+            let Some(value) = value else {
+                continue;
+            };
+            if !properties.parse_property(key, value) {
+                properties
+                    .unknown_properties
+                    .insert(key.clone(), value.clone());
+            }
+        }
+        properties.column_mapping_mode()?;
+        Ok(properties)
+    }
+}
+
+/// Parse the value of a table property, or `None` if it is invalid.
+trait ParseProperty: Sized {
+    fn parse_property(value: &str) -> Option<Self>;
+}
+
+macro_rules! impl_parse_property_from_str {
+    ($($ty:ty),+) => {
+        $(
+            impl ParseProperty for $ty {
+                fn parse_property(value: &str) -> Option<Self> {
+                    value.parse().ok()
+                }
+            }
+        )+
+    };
+}
+
+impl_parse_property_from_str!(
+    NonZeroU64,
+    u64,
+    i64,
+    CheckpointPolicy,
+    ColumnMappingMode,
+    DataSkippingNumIndexedCols,
+    IsolationLevel
+);
+
+impl ParseProperty for bool {
+    fn parse_property(value: &str) -> Option<Self> {
+        parse_bool(value)
+    }
+}
+
+impl ParseProperty for Duration {
+    fn parse_property(value: &str) -> Option<Self> {
+        parse_interval(value)
+    }
+}
+
+/// A comma separated list of column names
+impl ParseProperty for Vec<ColumnName> {
+    fn parse_property(value: &str) -> Option<Self> {
+        value
+            .split(',')
+            .map(|column| column.trim().parse().ok())
+            .collect()
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Parse an interval such as `interval 1 week`, the format used by duration table properties.
+fn parse_interval(value: &str) -> Option<Duration> {
+    let mut parts = value.split_whitespace();
+    if !parts.next()?.eq_ignore_ascii_case("interval") {
+        return None;
+    }
+    let number: u64 = parts.next()?.parse().ok()?;
+    let unit = parts.next()?.to_ascii_lowercase();
+    if parts.next().is_some() {
+        return None;
+    }
+    let duration = match unit.trim_end_matches('s') {
+        "nanosecond" => Duration::from_nanos(number),
+        "microsecond" => Duration::from_micros(number),
+        "millisecond" => Duration::from_millis(number),
+        "second" => Duration::from_secs(number),
+        "minute" => Duration::from_secs(number * 60),
+        "hour" => Duration::from_secs(number * 60 * 60),
+        "day" => Duration::from_secs(number * 24 * 60 * 60),
+        "week" => Duration::from_secs(number * 7 * 24 * 60 * 60),
+        _ => return None,
+    };
+    Some(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configuration(properties: &[(&str, &str)]) -> HashMap<String, Option<String>> {
+        properties
+            .iter()
+            .map(|(key, value)| (key.to_string(), Some(value.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_table_properties() {
+        let config = configuration(&[
+            ("delta.appendOnly", "true"),
+            ("delta.checkpointInterval", "10"),
+            ("delta.checkpoint.writeStatsAsJson", "FALSE"),
+            ("delta.checkpoint.writeStatsAsStruct", "true"),
+            ("delta.checkpointPolicy", "v2"),
+            ("delta.columnMapping.mode", "name"),
+            ("delta.dataSkippingNumIndexedCols", "-1"),
+            ("delta.dataSkippingStatsColumns", "a, b.c,`d.e`"),
+            ("delta.deletedFileRetentionDuration", "interval 2 days"),
+            ("delta.enableChangeDataFeed", "true"),
+            ("delta.enableDeletionVectors", "true"),
+            ("delta.enableExpiredLogCleanup", "false"),
+            ("delta.enableInCommitTimestamps", "true"),
+            ("delta.enableRowTracking", "true"),
+            ("delta.enableTypeWidening", "false"),
+            ("delta.inCommitTimestampEnablementVersion", "5"),
+            (
+                "delta.inCommitTimestampEnablementTimestamp",
+                "1712345678000",
+            ),
+            ("delta.isolationLevel", "WriteSerializable"),
+            ("delta.logRetentionDuration", "interval 30 days"),
+            ("delta.randomizeFilePrefixes", "true"),
+            ("delta.randomPrefixLength", "4"),
+            ("delta.setTransactionRetentionDuration", "interval 1 hour"),
+            ("delta.targetFileSize", "1048576"),
+            ("custom.property", "value"),
+        ]);
+        let day = Duration::from_secs(24 * 60 * 60);
+        let properties = TableProperties::try_from(&config).unwrap();
+        assert_eq!(properties.append_only().unwrap(), Some(true));
+        assert_eq!(
+            properties.checkpoint_interval().unwrap(),
+            NonZeroU64::new(10)
+        );
+        assert_eq!(
+            properties.checkpoint_write_stats_as_json().unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            properties.checkpoint_write_stats_as_struct().unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            properties.checkpoint_policy().unwrap(),
+            Some(CheckpointPolicy::V2)
+        );
+        assert_eq!(
+            properties.column_mapping_mode().unwrap(),
+            Some(ColumnMappingMode::Name)
+        );
+        assert_eq!(
+            properties.data_skipping_num_indexed_cols().unwrap(),
+            Some(DataSkippingNumIndexedCols::AllColumns)
+        );
+        assert_eq!(
+            properties.data_skipping_stats_columns().unwrap(),
+            Some(vec![
+                ColumnName::new(["a"]),
+                ColumnName::new(["b", "c"]),
+                ColumnName::new(["d.e"]),
+            ])
+        );
+        assert_eq!(
+            properties.deleted_file_retention_duration().unwrap(),
+            Some(2 * day)
+        );
+        assert_eq!(properties.enable_change_data_feed().unwrap(), Some(true));
+        assert_eq!(properties.enable_deletion_vectors().unwrap(), Some(true));
+        assert_eq!(
+            properties.enable_expired_log_cleanup().unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            properties.enable_in_commit_timestamps().unwrap(),
+            Some(true)
+        );
+        assert_eq!(properties.enable_row_tracking().unwrap(), Some(true));
+        assert_eq!(properties.enable_type_widening().unwrap(), Some(false));
+        assert_eq!(
+            properties.in_commit_timestamp_enablement_version().unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            properties
+                .in_commit_timestamp_enablement_timestamp()
+                .unwrap(),
+            Some(1712345678000)
+        );
+        assert_eq!(
+            properties.isolation_level().unwrap(),
+            Some(IsolationLevel::WriteSerializable)
+        );
+        assert_eq!(properties.log_retention_duration().unwrap(), Some(30 * day));
+        assert_eq!(properties.randomize_file_prefixes().unwrap(), Some(true));
+        assert_eq!(
+            properties.random_prefix_length().unwrap(),
+            NonZeroU64::new(4)
+        );
+        assert_eq!(
+            properties.set_transaction_retention_duration().unwrap(),
+            Some(Duration::from_secs(60 * 60))
+        );
+        assert_eq!(
+            properties.target_file_size().unwrap(),
+            NonZeroU64::new(1048576)
+        );
+        assert_eq!(
+            properties.unknown_properties(),
+            &HashMap::from([("custom.property".into(), "value".into())])
+        );
+
+        let mut config = configuration(&[("delta.dataSkippingNumIndexedCols", "32")]);
+        config.insert("delta.appendOnly".to_string(), None);
+        let properties = TableProperties::try_from(&config).unwrap();
+        assert_eq!(
+            properties.data_skipping_num_indexed_cols().unwrap(),
+            Some(DataSkippingNumIndexedCols::NumColumns(32))
+        );
+        assert_eq!(properties.append_only().unwrap(), None);
+        assert!(properties.unknown_properties().is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_table_properties() {
+        let config = configuration(&[
+            ("delta.appendOnly", "yes"),
+            ("delta.checkpointInterval", "0"),
+            ("delta.checkpointPolicy", "v3"),
+            ("delta.dataSkippingNumIndexedCols", "-2"),
+            ("delta.dataSkippingStatsColumns", "a,`b"),
+            ("delta.deletedFileRetentionDuration", "7 days"),
+            ("delta.inCommitTimestampEnablementVersion", "-1"),
+            ("delta.isolationLevel", "ReadCommitted"),
+            ("delta.targetFileSize", "1MB"),
+            ("delta.enableChangeDataFeed", "true"),
+        ]);
+        let properties = TableProperties::try_from(&config).unwrap();
+        assert!(properties.append_only().is_err());
+        assert!(properties.checkpoint_interval().is_err());
+        assert!(properties.checkpoint_policy().is_err());
+        assert!(properties.data_skipping_num_indexed_cols().is_err());
+        assert!(properties.data_skipping_stats_columns().is_err());
+        assert!(properties.deleted_file_retention_duration().is_err());
+        assert!(properties.in_commit_timestamp_enablement_version().is_err());
+        assert!(properties.isolation_level().is_err());
+        assert!(properties.target_file_size().is_err());
+        // valid properties are not affected by invalid ones
+        assert_eq!(properties.enable_change_data_feed().unwrap(), Some(true));
+        assert!(properties.unknown_properties().is_empty());
+
+        // the column mapping mode is needed to read the table at all
+        let config = configuration(&[("delta.columnMapping.mode", "position")]);
+        assert!(TableProperties::try_from(&config).is_err());
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(
+            parse_interval("interval 1 week"),
+            Some(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_interval("INTERVAL 30 days"),
+            Some(Duration::from_secs(30 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_interval("interval 5 seconds"),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(parse_interval("1 week"), None);
+        assert_eq!(parse_interval("interval 1 fortnight"), None);
+    }
+}
@@ -0,0 +1,48 @@
+//! Fixtures shared by the unit tests of the crate.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use url::Url;
+
+use crate::actions::Add;
+use crate::Version;
+
+pub(crate) const PROTOCOL: &str = r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#;
+pub(crate) const METADATA: &str = r#"{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"value\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{},"createdTime":1587968585495}}"#;
+
+/// An add action of an unpartitioned file with one row.
+pub(crate) fn add_file(path: &str) -> Add {
+    Add {
+        path: path.into(),
+        partition_values: HashMap::new(),
+        size: 262,
+        modification_time: 1587968586000,
+        data_change: true,
+        stats: Some(r#"{"numRecords":1}"#.into()),
+        tags: HashMap::new(),
+        deletion_vector: None,
+        base_row_id: None,
+        default_row_commit_version: None,
+        clustering_provider: None,
+    }
+}
+
+/// The json line of the [`add_file`] action of `path`.
+pub(crate) fn add(path: &str) -> String {
+    serde_json::json!({ "add": add_file(path) }).to_string()
+}
+
+/// Write the given json actions as the commit of `version`.
+pub(crate) fn write_commit(table_root: &Path, version: Version, actions: &[String]) {
+    let path = table_root.join(format!("_delta_log/{version:020}.json"));
+    std::fs::write(path, actions.join("\n")).unwrap();
+}
+
+/// Create a table at `table_root` whose first commit holds the given json actions, returning its
+/// location.
+pub(crate) fn write_test_table(table_root: &Path, actions: &[String]) -> Url {
+    std::fs::create_dir_all(table_root.join("_delta_log")).unwrap();
+    write_commit(table_root, 0, actions);
+    Url::from_directory_path(table_root).unwrap()
+}
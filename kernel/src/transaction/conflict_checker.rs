@@ -0,0 +1,219 @@
+//! Detection of conflicts between a [`Transaction`] and the commits that won the race for the
+//! versions it tried to commit.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+use lazy_static::lazy_static;
+use tracing::debug;
+
+use super::Transaction;
+use crate::engine_data::{GetData, TypedGetData};
+use crate::expressions::{ColumnName, Scalar};
+use crate::scan::parse_partition_value;
+use crate::schema::{DataType, MapType, SchemaRef, StructField, StructType};
+use crate::snapshot::LogSegment;
+use crate::{DataVisitor, DeltaResult, EngineInterface, Error, FileMeta, Version};
+
+lazy_static! {
+    /// The columns of a winning commit needed to check it for conflicts.
+    static ref CONFLICT_SCHEMA: SchemaRef = Arc::new(StructType::new(vec![
+        StructField::new(
+            "add",
+            StructType::new(vec![
+                StructField::new("path", DataType::STRING, true),
+                StructField::new(
+                    "partitionValues",
+                    MapType::new(DataType::STRING, DataType::STRING, true),
+                    true,
+                ),
+                StructField::new("dataChange", DataType::BOOLEAN, true),
+            ]),
+            true,
+        ),
+        StructField::new(
+            "remove",
+            StructType::new(vec![
+                StructField::new("path", DataType::STRING, true),
+                StructField::new("dataChange", DataType::BOOLEAN, true),
+            ]),
+            true,
+        ),
+        StructField::new(
+            "metaData",
+            StructType::new(vec![StructField::new("id", DataType::STRING, true)]),
+            true,
+        ),
+        StructField::new(
+            "protocol",
+            StructType::new(vec![StructField::new(
+                "minReaderVersion",
+                DataType::INTEGER,
+                true,
+            )]),
+            true,
+        ),
+        StructField::new(
+            "txn",
+            StructType::new(vec![StructField::new("appId", DataType::STRING, true)]),
+            true,
+        ),
+    ]));
+}
+
+/// The actions of a winning commit which may conflict with a transaction.
+#[derive(Default)]
+struct WinningCommitVisitor {
+    /// Paths and partition values of the files added by the commit
+    added_files: Vec<(String, HashMap<String, Option<String>>)>,
+    /// Paths of the files removed by the commit
+    removed_files: Vec<String>,
+    metadata_changed: bool,
+    protocol_changed: bool,
+    app_ids: Vec<String>,
+}
+
+impl DataVisitor for WinningCommitVisitor {
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            if let Some(path) = getters[0].get_opt(i, "add.path")? {
+                let data_change: Option<bool> = getters[2].get_opt(i, "add.dataChange")?;
+                if data_change.unwrap_or(true) {
+                    let partition_values: Option<HashMap<_, _>> =
+                        getters[1].get_opt(i, "add.partitionValues")?;
+                    self.added_files
+                        .push((path, partition_values.unwrap_or_default()));
+                }
+                continue;
+            }
+            if let Some(path) = getters[3].get_opt(i, "remove.path")? {
+                let data_change: Option<bool> = getters[4].get_opt(i, "remove.dataChange")?;
+                if data_change.unwrap_or(true) {
+                    self.removed_files.push(path);
+                }
+                continue;
+            }
+            let metadata_id: Option<String> = getters[5].get_opt(i, "metaData.id")?;
+            self.metadata_changed |= metadata_id.is_some();
+            let min_reader_version: Option<i32> =
+                getters[6].get_opt(i, "protocol.minReaderVersion")?;
+            self.protocol_changed |= min_reader_version.is_some();
+            if let Some(app_id) = getters[7].get_opt(i, "txn.appId")? {
+                self.app_ids.push(app_id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether a [`Transaction`] can be committed after the commits which were made since its
+/// read version, i.e. whether the transaction would have had the same outcome had it read them.
+pub(crate) struct ConflictChecker<'a> {
+    transaction: &'a Transaction,
+    read_files: HashSet<&'a str>,
+    /// Physical name and type of each partition column, by logical name
+    partition_columns: HashMap<&'a str, (&'a str, &'a DataType)>,
+}
+
+impl<'a> ConflictChecker<'a> {
+    pub(crate) fn try_new(transaction: &'a Transaction) -> DeltaResult<Self> {
+        let snapshot = &transaction.read_snapshot;
+        let partition_columns = snapshot
+            .metadata()
+            .partition_columns
+            .iter()
+            .map(|column| {
+                let field = snapshot.schema().field(column).ok_or_else(|| {
+                    Error::generic(format!("Partition column {column} not found in schema"))
+                })?;
+                let physical_name = field.physical_name(snapshot.column_mapping_mode())?;
+                Ok((column.as_str(), (physical_name, field.data_type())))
+            })
+            .collect::<DeltaResult<_>>()?;
+        Ok(Self {
+            transaction,
+            read_files: transaction.read_files.iter().map(String::as_str).collect(),
+            partition_columns,
+        })
+    }
+
+    /// Check the commit of `version` for conflicts with the transaction, returning the error
+    /// describing the first conflict found.
+    pub(crate) fn check_commit(
+        &self,
+        engine_interface: &dyn EngineInterface,
+        version: Version,
+        commit: FileMeta,
+    ) -> DeltaResult<()> {
+        debug!("Checking commit {} for conflicts", version);
+        let log_segment = LogSegment {
+            log_root: self.transaction.read_snapshot.log_segment.log_root.clone(),
+            commit_files: vec![commit],
+            checkpoint_files: vec![],
+            sidecar_files: OnceLock::new(),
+        };
+        let mut visitor = WinningCommitVisitor::default();
+        let batches = log_segment.replay(
+            engine_interface,
+            CONFLICT_SCHEMA.clone(),
+            CONFLICT_SCHEMA.clone(),
+            None,
+        )?;
+        for batch in batches {
+            let (batch, _) = batch?;
+            batch.extract(CONFLICT_SCHEMA.clone(), &mut visitor)?;
+        }
+
+        if visitor.protocol_changed {
+            return Err(Error::ProtocolChanged(version));
+        }
+        if visitor.metadata_changed {
+            return Err(Error::MetadataChanged(version));
+        }
+        if let Some(predicate) = &self.transaction.read_predicate {
+            for (path, partition_values) in &visitor.added_files {
+                let resolve = |column: &ColumnName| self.partition_value(partition_values, column);
+                // the file may hold matching rows unless the predicate is known not to hold
+                let matches = !matches!(
+                    predicate.evaluate_scalar(&resolve),
+                    Ok(Some(Scalar::Boolean(false) | Scalar::Null(_)))
+                );
+                if matches {
+                    debug!(
+                        "File {} added by commit {} matches {}",
+                        path, version, predicate
+                    );
+                    return Err(Error::ConcurrentAppend(version));
+                }
+            }
+        }
+        if let Some(path) = visitor
+            .removed_files
+            .into_iter()
+            .find(|path| self.read_files.contains(path.as_str()))
+        {
+            return Err(Error::ConcurrentDeleteRead(path, version));
+        }
+        if let Some(txn) = &self.transaction.set_transaction {
+            if visitor.app_ids.contains(&txn.app_id) {
+                return Err(Error::ConcurrentTransaction(txn.app_id.clone(), version));
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of `column` for a file with the given partition values, or `None` if it is not a
+    /// partition column, so its value is unknown.
+    fn partition_value(
+        &self,
+        partition_values: &HashMap<String, Option<String>>,
+        column: &ColumnName,
+    ) -> Option<Scalar> {
+        // partition columns are top-level columns
+        let [name] = column.path() else {
+            return None;
+        };
+        let (physical_name, data_type) = self.partition_columns.get(name.as_str())?;
+        parse_partition_value(partition_values.get(*physical_name), data_type).ok()
+    }
+}
@@ -0,0 +1,482 @@
+//! A [`Transaction`] commits new data files to a table, on top of the version of a [`Snapshot`].
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::debug;
+
+use self::conflict_checker::ConflictChecker;
+use crate::actions::{Add, CommitInfo, SetTransaction};
+use crate::path::LogPath;
+use crate::snapshot::{list_commit_files, Snapshot};
+use crate::{DeltaResult, EngineInterface, Error, Expression, Version};
+
+mod conflict_checker;
+
+const KERNEL_VERSION: &str = concat!("v", env!("CARGO_PKG_VERSION"));
+/// How often a commit is retried on top of newer versions by default
+const DEFAULT_MAX_RETRIES: usize = 10;
+
+/// The actions written to a commit file, one per line.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum CommitAction<'a> {
+    CommitInfo(&'a CommitInfo),
+    Txn(&'a SetTransaction),
+    Add(&'a Add),
+}
+
+/// A set of changes to a table, which is committed atomically as the version following that of the
+/// snapshot it was started from.
+///
+/// Data files are written by the engine, and their metadata handed to the transaction as [`Add`]
+/// actions. [`Transaction::commit`] then writes them to the log as a new commit. If another
+/// writer committed that version first, the transaction is checked for conflicts with the winning
+/// commits, and committed as the next version if there are none.
+pub struct Transaction {
+    read_snapshot: Arc<Snapshot>,
+    commit_info: CommitInfo,
+    adds: Vec<Add>,
+    read_predicate: Option<Expression>,
+    read_files: Vec<String>,
+    set_transaction: Option<SetTransaction>,
+    max_retries: usize,
+}
+
+impl std::fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transaction")
+            .field("read_version", &self.read_snapshot.version())
+            .field("commit_info", &self.commit_info)
+            .field("num_adds", &self.adds.len())
+            .field("read_predicate", &self.read_predicate)
+            .field("num_read_files", &self.read_files.len())
+            .field("set_transaction", &self.set_transaction)
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
+impl Transaction {
+    /// Start a new transaction on top of `read_snapshot`.
+    pub fn new(read_snapshot: Arc<Snapshot>) -> Self {
+        Self {
+            read_snapshot,
+            commit_info: CommitInfo::default(),
+            adds: Vec::new(),
+            read_predicate: None,
+            read_files: Vec::new(),
+            set_transaction: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// The snapshot this transaction is based on.
+    pub fn read_snapshot(&self) -> &Arc<Snapshot> {
+        &self.read_snapshot
+    }
+
+    /// Set the `commitInfo` to write with the commit. The `timestamp`, `readVersion` and
+    /// `kernelVersion` fields are always filled in when committing, and the `operation` defaults
+    /// to `WRITE`.
+    pub fn with_commit_info(mut self, commit_info: CommitInfo) -> Self {
+        self.commit_info = commit_info;
+        self
+    }
+
+    /// Record that the transaction read the data of the table matching `predicate`, so that it
+    /// conflicts with concurrent commits adding files which may hold such data. Use a literal
+    /// `true` predicate if the transaction read the whole table.
+    ///
+    /// Only the values of partition columns are known for added files, so files are assumed to
+    /// match predicates on other columns.
+    pub fn with_read_predicate(mut self, predicate: Expression) -> Self {
+        self.read_predicate = Some(predicate);
+        self
+    }
+
+    /// Record that the transaction read the given files, by their paths as found in the log, so
+    /// that it conflicts with concurrent commits removing any of them.
+    pub fn add_read_files(&mut self, paths: impl IntoIterator<Item = String>) {
+        self.read_files.extend(paths);
+    }
+
+    /// Commit a `txn` action with the given application id and version, so that the transaction
+    /// conflicts with concurrent commits of the same application.
+    pub fn with_transaction_id(mut self, app_id: impl Into<String>, version: i64) -> Self {
+        self.set_transaction = Some(SetTransaction {
+            app_id: app_id.into(),
+            version,
+            last_updated: None,
+        });
+        self
+    }
+
+    /// Set how often the commit is retried on top of newer versions of the table when other
+    /// writers have committed since the read version. Defaults to 10.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Add data files, which the engine has already written, to the table.
+    pub fn add_files(&mut self, adds: impl IntoIterator<Item = Add>) {
+        self.adds.extend(adds);
+    }
+
+    /// Commit the transaction, returning the version that was created.
+    ///
+    /// The commit file is written with put-if-absent semantics. If another writer has already
+    /// committed the next version, the commits made since the read version are checked for
+    /// conflicts with this transaction, which fails with the error describing the conflict if
+    /// there is one (e.g. [`Error::ConcurrentAppend`]). Otherwise the commit is retried as the
+    /// version after the latest one, until the retries are exhausted and this fails with
+    /// [`Error::VersionAlreadyExists`]. Fails with [`Error::Unsupported`] if the kernel cannot write
+    /// to the table.
+    pub fn commit(self, engine_interface: &dyn EngineInterface) -> DeltaResult<Version> {
+        self.read_snapshot.ensure_write_supported()?;
+        self.validate_partition_values()?;
+
+        let read_version = self.read_snapshot.version();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(Error::generic_err)?;
+        let mut commit_info = self.commit_info.clone();
+        commit_info.timestamp = Some(timestamp.as_millis() as i64);
+        commit_info.operation.get_or_insert_with(|| "WRITE".into());
+        commit_info.read_version = Some(read_version as i64);
+        commit_info
+            .is_blind_append
+            .get_or_insert(self.read_predicate.is_none() && self.read_files.is_empty());
+        commit_info.kernel_version = Some(KERNEL_VERSION.into());
+
+        let mut data = Vec::new();
+        let actions = std::iter::once(CommitAction::CommitInfo(&commit_info))
+            .chain(self.set_transaction.iter().map(CommitAction::Txn))
+            .chain(self.adds.iter().map(CommitAction::Add));
+        for action in actions {
+            serde_json::to_writer(&mut data, &action)?;
+            data.push(b'\n');
+        }
+        let data = bytes::Bytes::from(data);
+
+        let log_root = &self.read_snapshot.log_segment.log_root;
+        let fs_client = engine_interface.get_file_system_client();
+        let mut conflict_checker = None;
+        let mut version = read_version + 1;
+        let mut retries = 0;
+        loop {
+            let location = LogPath(log_root).child(format!("{version:020}.json"))?;
+            debug!("Committing version {} to {}", version, location);
+            match fs_client.write_file(&location, data.clone(), false) {
+                Ok(()) => return Ok(version),
+                Err(Error::FileAlreadyExists(_)) if retries < self.max_retries => {}
+                Err(Error::FileAlreadyExists(_)) => {
+                    return Err(Error::VersionAlreadyExists(version))
+                }
+                Err(err) => return Err(err),
+            }
+
+            let checker = match conflict_checker {
+                Some(ref checker) => checker,
+                None => conflict_checker.insert(ConflictChecker::try_new(&self)?),
+            };
+            let winning_commits = list_commit_files(fs_client.as_ref(), log_root, version, None)?;
+            for (winning_version, commit) in winning_commits {
+                checker.check_commit(engine_interface, winning_version, commit)?;
+                version = winning_version + 1;
+            }
+            retries += 1;
+        }
+    }
+
+    /// Check that every added file has a value for exactly the partition columns of the table,
+    /// keyed by their physical names.
+    fn validate_partition_values(&self) -> DeltaResult<()> {
+        let snapshot = &self.read_snapshot;
+        let partition_columns = snapshot
+            .metadata()
+            .partition_columns
+            .iter()
+            .map(|column| {
+                snapshot
+                    .schema()
+                    .field(column)
+                    .ok_or_else(|| {
+                        Error::generic(format!("Partition column {column} not found in schema"))
+                    })?
+                    .physical_name(snapshot.column_mapping_mode())
+            })
+            .collect::<DeltaResult<HashSet<_>>>()?;
+        for add in &self.adds {
+            let keys: HashSet<_> = add.partition_values.keys().map(String::as_str).collect();
+            if keys != partition_columns {
+                return Err(Error::generic(format!(
+                    "Partition values of {} do not match the partition columns {:?}",
+                    add.path, partition_columns
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use url::Url;
+
+    use super::*;
+    use crate::scan::ScanBuilder;
+    use crate::simple_client::SimpleClient;
+    use crate::test_utils::{self, add_file, PROTOCOL};
+
+    const PARTITIONED_METADATA: &str = r#"{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"value\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}},{\"name\":\"part\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":["part"],"configuration":{},"createdTime":1587968585495}}"#;
+
+    fn write_test_table(table_root: &Path) -> Url {
+        test_utils::write_test_table(table_root, &[PROTOCOL.into(), PARTITIONED_METADATA.into()])
+    }
+
+    fn add(path: &str, part: &str) -> Add {
+        Add {
+            partition_values: HashMap::from([("part".to_string(), Some(part.to_string()))]),
+            ..add_file(path)
+        }
+    }
+
+    #[test]
+    fn test_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+
+        let mut txn = Transaction::new(snapshot).with_commit_info(CommitInfo {
+            engine_info: Some("test".into()),
+            ..Default::default()
+        });
+        txn.add_files([add("part=a/1.parquet", "a"), add("part=b/2.parquet", "b")]);
+        assert_eq!(txn.commit(&engine_interface).unwrap(), 1);
+
+        let commit =
+            std::fs::read_to_string(dir.path().join("_delta_log/00000000000000000001.json"))
+                .unwrap();
+        let commit_info: serde_json::Value =
+            serde_json::from_str(commit.lines().next().unwrap()).unwrap();
+        let commit_info: CommitInfo =
+            serde_json::from_value(commit_info["commitInfo"].clone()).unwrap();
+        assert_eq!(commit_info.operation.as_deref(), Some("WRITE"));
+        assert_eq!(commit_info.engine_info.as_deref(), Some("test"));
+        assert_eq!(commit_info.read_version, Some(0));
+        assert_eq!(commit_info.kernel_version.as_deref(), Some(KERNEL_VERSION));
+
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+        assert_eq!(snapshot.version(), 1);
+        let mut files: Vec<_> = ScanBuilder::new(snapshot)
+            .build()
+            .files(&engine_interface)
+            .unwrap()
+            .map(|add| add.unwrap())
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            files,
+            vec![add("part=a/1.parquet", "a"), add("part=b/2.parquet", "b")]
+        );
+    }
+
+    #[test]
+    fn test_commit_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        let mut first = Transaction::new(snapshot.clone());
+        first.add_files([add("part=a/1.parquet", "a")]);
+        let mut second = Transaction::new(snapshot).with_max_retries(0);
+        second.add_files([add("part=a/2.parquet", "a")]);
+
+        assert_eq!(first.commit(&engine_interface).unwrap(), 1);
+        assert!(matches!(
+            second.commit(&engine_interface),
+            Err(Error::VersionAlreadyExists(1))
+        ));
+    }
+
+    /// Commit a transaction adding a file to partition `a` on top of `snapshot`.
+    fn commit_append(engine_interface: &dyn EngineInterface, snapshot: Arc<Snapshot>, path: &str) {
+        let mut txn = Transaction::new(snapshot);
+        txn.add_files([add(path, "a")]);
+        txn.commit(engine_interface).unwrap();
+    }
+
+    #[test]
+    fn test_commit_rebase() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+
+        commit_append(&engine_interface, snapshot.clone(), "part=a/1.parquet");
+        commit_append(&engine_interface, snapshot.clone(), "part=a/2.parquet");
+
+        // blind appends and reads of other partitions don't conflict with the appends
+        let mut txn = Transaction::new(snapshot.clone());
+        txn.add_files([add("part=c/3.parquet", "c")]);
+        assert_eq!(txn.commit(&engine_interface).unwrap(), 3);
+        let mut txn = Transaction::new(snapshot)
+            .with_read_predicate(Expression::column("part").eq(Expression::literal("b")));
+        txn.add_files([add("part=b/4.parquet", "b")]);
+        assert_eq!(txn.commit(&engine_interface).unwrap(), 4);
+
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+        assert_eq!(snapshot.version(), 4);
+        let files = ScanBuilder::new(snapshot)
+            .build()
+            .files(&engine_interface)
+            .unwrap()
+            .count();
+        assert_eq!(files, 4);
+    }
+
+    #[test]
+    fn test_commit_concurrent_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+        commit_append(&engine_interface, snapshot.clone(), "part=a/1.parquet");
+
+        let predicates = [
+            Expression::literal(true),
+            Expression::column("part").eq(Expression::literal("a")),
+            // the values of data columns are unknown
+            Expression::column("value").gt(Expression::literal(1)),
+        ];
+        for predicate in predicates {
+            let mut txn = Transaction::new(snapshot.clone()).with_read_predicate(predicate);
+            txn.add_files([add("part=b/2.parquet", "b")]);
+            assert!(matches!(
+                txn.commit(&engine_interface),
+                Err(Error::ConcurrentAppend(1))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_commit_concurrent_delete_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location.clone(), &engine_interface, None).unwrap();
+        commit_append(&engine_interface, snapshot, "part=a/1.parquet");
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        std::fs::write(
+            dir.path().join("_delta_log/00000000000000000002.json"),
+            r#"{"remove":{"path":"part=a/1.parquet","deletionTimestamp":1587968586000,"dataChange":true}}"#,
+        )
+        .unwrap();
+        let mut txn = Transaction::new(snapshot);
+        txn.add_read_files(["part=a/1.parquet".to_string()]);
+        txn.add_files([add("part=a/2.parquet", "a")]);
+        assert!(matches!(
+            txn.commit(&engine_interface),
+            Err(Error::ConcurrentDeleteRead(path, 2)) if path == "part=a/1.parquet"
+        ));
+    }
+
+    #[test]
+    fn test_commit_unsupported_writer_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        std::fs::write(
+            dir.path().join("_delta_log/00000000000000000001.json"),
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":7,"writerFeatures":["inCommitTimestamp"]}}"#,
+        )
+        .unwrap();
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        let mut txn = Transaction::new(snapshot);
+        txn.add_files([add("part=a/1.parquet", "a")]);
+        assert!(matches!(
+            txn.commit(&engine_interface),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(!dir
+            .path()
+            .join("_delta_log/00000000000000000002.json")
+            .exists());
+    }
+
+    #[test]
+    fn test_commit_metadata_and_protocol_changes() {
+        let cases = [
+            (PARTITIONED_METADATA, Error::MetadataChanged(1)),
+            (PROTOCOL, Error::ProtocolChanged(1)),
+        ];
+        for (action, expected) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            let location = write_test_table(dir.path());
+            let engine_interface = SimpleClient::new();
+            let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+            std::fs::write(
+                dir.path().join("_delta_log/00000000000000000001.json"),
+                action,
+            )
+            .unwrap();
+            let mut txn = Transaction::new(snapshot);
+            txn.add_files([add("part=a/1.parquet", "a")]);
+            let err = txn.commit(&engine_interface).unwrap_err();
+            assert_eq!(err.to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_commit_concurrent_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        let mut first = Transaction::new(snapshot.clone()).with_transaction_id("app", 1);
+        first.add_files([add("part=a/1.parquet", "a")]);
+        assert_eq!(first.commit(&engine_interface).unwrap(), 1);
+
+        let mut other_app = Transaction::new(snapshot.clone()).with_transaction_id("other", 1);
+        other_app.add_files([add("part=a/2.parquet", "a")]);
+        assert_eq!(other_app.commit(&engine_interface).unwrap(), 2);
+
+        let mut same_app = Transaction::new(snapshot).with_transaction_id("app", 2);
+        same_app.add_files([add("part=a/3.parquet", "a")]);
+        assert!(matches!(
+            same_app.commit(&engine_interface),
+            Err(Error::ConcurrentTransaction(app_id, 1)) if app_id == "app"
+        ));
+    }
+
+    #[test]
+    fn test_commit_invalid_partition_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = write_test_table(dir.path());
+        let engine_interface = SimpleClient::new();
+        let snapshot = Snapshot::try_new(location, &engine_interface, None).unwrap();
+
+        let mut txn = Transaction::new(snapshot);
+        let mut unpartitioned = add("1.parquet", "a");
+        unpartitioned.partition_values.clear();
+        txn.add_files([unpartitioned]);
+        assert!(txn.commit(&engine_interface).is_err());
+        assert!(!dir
+            .path()
+            .join("_delta_log/00000000000000000001.json")
+            .exists());
+    }
+}
@@ -7,8 +7,9 @@ use arrow::record_batch::RecordBatch;
 use arrow_select::concat::concat_batches;
 use deltakernel::client::DefaultTableClient;
 use deltakernel::executor::tokio::TokioBackgroundExecutor;
-use deltakernel::expressions::{BinaryOperator, Expression};
+use deltakernel::expressions::{BinaryOperator, ColumnName, Expression, Scalar};
 use deltakernel::scan::ScanBuilder;
+use deltakernel::schema::DataType;
 use deltakernel::simple_client::data::SimpleData;
 use deltakernel::{EngineData, Table};
 use object_store::{memory::InMemory, path::Path, ObjectStore};
@@ -104,7 +105,9 @@ async fn single_commit_two_add_files() -> Result<(), Box<dyn std::error::Error>>
     );
 
     let table = Table::new(location);
-    let expected_data = vec![batch.clone(), batch];
+    // only the columns of the table schema are read
+    let expected = batch.project(&[0])?;
+    let expected_data = vec![expected.clone(), expected];
 
     let snapshot = table.snapshot(&engine_interface, None)?;
     let scan = ScanBuilder::new(snapshot).build();
@@ -158,7 +161,9 @@ async fn two_commits() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let table = Table::new(location);
-    let expected_data = vec![batch.clone(), batch];
+    // only the columns of the table schema are read
+    let expected = batch.project(&[0])?;
+    let expected_data = vec![expected.clone(), expected];
 
     let snapshot = table.snapshot(&engine_interface, None).unwrap();
     let scan = ScanBuilder::new(snapshot).build();
@@ -216,7 +221,8 @@ async fn remove_action() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let table = Table::new(location);
-    let expected_data = vec![batch];
+    // only the columns of the table schema are read
+    let expected_data = vec![batch.project(&[0])?];
 
     let snapshot = table.snapshot(&engine_interface, None)?;
     let scan = ScanBuilder::new(snapshot).build();
@@ -356,13 +362,134 @@ async fn stats() -> Result<(), Box<dyn std::error::Error>> {
         for (batch, expected) in stream {
             let raw_data = batch.raw_data?;
             files_scanned += 1;
-            assert_eq!(into_record_batch(raw_data), expected.clone());
+            // only the columns of the table schema are read
+            assert_eq!(into_record_batch(raw_data), expected.project(&[0])?);
         }
         assert_eq!(expected_files, files_scanned);
     }
     Ok(())
 }
 
+#[tokio::test]
+async fn null_count_stats() -> Result<(), Box<dyn std::error::Error>> {
+    fn add(path: &str, null_count: i64, min_max: Option<(i64, i64)>) -> String {
+        let min_max = min_max.map_or(String::new(), |(min, max)| {
+            format!(r#",\"minValues\":{{\"id\":{min}}},\"maxValues\":{{\"id\":{max}}}"#)
+        });
+        format!(
+            r#"{{"add":{{"path":"{path}","partitionValues":{{}},"size":262,"modificationTime":1587968586000,"dataChange":true,"stats":"{{\"numRecords\":3,\"nullCount\":{{\"id\":{null_count}}}{min_max}}}"}}}}"#
+        )
+    }
+
+    let storage = Arc::new(InMemory::new());
+    let actions = [
+        METADATA.to_string(),
+        add("no_nulls", 0, Some((1, 3))),
+        add("some_nulls", 1, Some((1, 3))),
+        add("all_nulls", 3, None),
+    ];
+    add_commit(storage.as_ref(), 0, actions.join("\n")).await?;
+
+    let location = Url::parse("memory:///")?;
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+    let table = Table::new(location);
+    let snapshot = table.snapshot(&engine_interface, None)?;
+
+    let id = Expression::column("id");
+    let test_cases = [
+        (id.clone().is_null(), vec!["some_nulls", "all_nulls"]),
+        (!id.clone().is_null(), vec!["no_nulls", "some_nulls"]),
+        (
+            id.clone().gt(Expression::literal(0i64)),
+            vec!["no_nulls", "some_nulls"],
+        ),
+        (id.clone().gt(Expression::literal(3i64)), vec![]),
+        (
+            id.clone().is_null().or(id.gt(Expression::literal(3i64))),
+            vec!["some_nulls", "all_nulls"],
+        ),
+    ];
+    for (predicate, expected) in test_cases {
+        let scan = ScanBuilder::new(snapshot.clone())
+            .with_predicate(predicate.clone())
+            .build();
+        let mut paths: Vec<_> = scan
+            .files(&engine_interface)?
+            .map(|add| add.map(|add| add.path))
+            .collect::<Result<_, _>>()?;
+        paths.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(paths, expected, "{predicate}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn nested_column_stats() -> Result<(), Box<dyn std::error::Error>> {
+    const NESTED_METADATA: &str = r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}
+{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"payload\",\"type\":{\"type\":\"struct\",\"fields\":[{\"name\":\"device\",\"type\":{\"type\":\"struct\",\"fields\":[{\"name\":\"os\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}]},\"nullable\":true,\"metadata\":{}}]},\"nullable\":true,\"metadata\":{}},{\"name\":\"a.b\",\"type\":\"long\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{},"createdTime":1587968585495}}"#;
+    fn add(path: &str, os: &str, a_b: i64) -> String {
+        let values = format!(r#"{{\"payload\":{{\"device\":{{\"os\":\"{os}\"}}}},\"a.b\":{a_b}}}"#);
+        let null_count = r#"{\"payload\":{\"device\":{\"os\":0}},\"a.b\":0}"#;
+        format!(
+            r#"{{"add":{{"path":"{path}","partitionValues":{{}},"size":262,"modificationTime":1587968586000,"dataChange":true,"stats":"{{\"numRecords\":3,\"nullCount\":{null_count},\"minValues\":{values},\"maxValues\":{values}}}"}}}}"#
+        )
+    }
+
+    let storage = Arc::new(InMemory::new());
+    let actions = [
+        NESTED_METADATA.to_string(),
+        add("ios", "ios", 1),
+        add("android", "android", 2),
+    ];
+    add_commit(storage.as_ref(), 0, actions.join("\n")).await?;
+
+    let location = Url::parse("memory:///")?;
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+    let table = Table::new(location);
+    let snapshot = table.snapshot(&engine_interface, None)?;
+
+    let os = Expression::column("payload.device.os");
+    let a_b = Expression::column(ColumnName::new(["a.b"]));
+    let test_cases = [
+        (os.clone().eq(Expression::literal("ios")), vec!["ios"]),
+        (os.like("and%"), vec!["android"]),
+        (a_b.clone().gt(Expression::literal(1i64)), vec!["android"]),
+        (
+            Expression::column("`a.b`").lt(Expression::literal(2i64)),
+            vec!["ios"],
+        ),
+        (a_b.clone().in_list([Expression::literal(3i64)]), vec![]),
+        // the literal is widened to the type of the column
+        (a_b.clone().eq(Expression::literal(2)), vec!["android"]),
+        (
+            a_b.cast(DataType::DOUBLE)
+                .lt(Expression::literal(Scalar::Double(1.5))),
+            vec!["ios"],
+        ),
+    ];
+    for (predicate, expected) in test_cases {
+        let scan = ScanBuilder::new(snapshot.clone())
+            .with_predicate(predicate.clone())
+            .build();
+        let paths: Vec<_> = scan
+            .files(&engine_interface)?
+            .map(|add| add.map(|add| add.path))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(paths, expected, "{predicate}");
+    }
+    Ok(())
+}
+
 macro_rules! assert_batches_sorted_eq {
     ($EXPECTED_LINES: expr, $CHUNKS: expr) => {
         let mut expected_lines: Vec<String> = $EXPECTED_LINES.iter().map(|&s| s.into()).collect();
@@ -440,3 +567,373 @@ fn data() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+const CDF_METADATA: &str = r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":4}}
+{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}},{\"name\":\"val\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{"delta.enableChangeDataFeed":"true"},"createdTime":1587968585495}}"#;
+
+fn commit_info(in_commit_timestamp: i64) -> String {
+    format!(
+        r#"{{"commitInfo":{{"timestamp":1587968586154,"inCommitTimestamp":{in_commit_timestamp},"operation":"WRITE"}}}}"#
+    )
+}
+
+fn file_action(action: &str, path: &str) -> String {
+    format!(
+        r#"{{"{action}":{{"path":"{path}","partitionValues":{{}},"size":262,"modificationTime":1587968586000,"dataChange":true}}}}"#
+    )
+}
+
+fn read_table_changes(
+    engine_interface: &dyn deltakernel::EngineInterface,
+    start_version: u64,
+    end_version: Option<u64>,
+) -> Result<Vec<RecordBatch>, Box<dyn std::error::Error>> {
+    let table = Table::new(Url::parse("memory:///")?);
+    let table_changes = table
+        .table_changes(engine_interface, start_version, end_version)?
+        .build()?;
+    let mut batches = vec![];
+    for result in table_changes.execute(engine_interface)? {
+        let batch = into_record_batch(result.raw_data?);
+        batches.push(match result.mask {
+            Some(mask) => arrow::compute::filter_record_batch(&batch, &mask.into())?,
+            None => batch,
+        });
+    }
+    Ok(batches)
+}
+
+#[tokio::test]
+async fn table_changes_from_add_and_remove() -> Result<(), Box<dyn std::error::Error>> {
+    let batch = generate_simple_batch()?;
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [
+            commit_info(1000),
+            CDF_METADATA.into(),
+            file_action("add", PARQUET_FILE1),
+        ]
+        .join("\n"),
+    )
+    .await?;
+    add_commit(
+        storage.as_ref(),
+        1,
+        [commit_info(2000), file_action("add", PARQUET_FILE2)].join("\n"),
+    )
+    .await?;
+    add_commit(
+        storage.as_ref(),
+        2,
+        [commit_info(3000), file_action("remove", PARQUET_FILE1)].join("\n"),
+    )
+    .await?;
+    storage
+        .put(&Path::from(PARQUET_FILE1), load_parquet(&batch).into())
+        .await?;
+    storage
+        .put(&Path::from(PARQUET_FILE2), load_parquet(&batch).into())
+        .await?;
+
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+
+    let expected = vec![
+        "+----+-----+--------------+-----------------+---------------------+",
+        "| id | val | _change_type | _commit_version | _commit_timestamp   |",
+        "+----+-----+--------------+-----------------+---------------------+",
+        "| 1  | a   | insert       | 1               | 1970-01-01T00:00:02 |",
+        "| 2  | b   | insert       | 1               | 1970-01-01T00:00:02 |",
+        "| 3  | c   | insert       | 1               | 1970-01-01T00:00:02 |",
+        "| 1  | a   | delete       | 2               | 1970-01-01T00:00:03 |",
+        "| 2  | b   | delete       | 2               | 1970-01-01T00:00:03 |",
+        "| 3  | c   | delete       | 2               | 1970-01-01T00:00:03 |",
+        "+----+-----+--------------+-----------------+---------------------+",
+    ];
+    let batches = read_table_changes(&engine_interface, 1, None)?;
+    assert_batches_sorted_eq!(expected, &batches);
+
+    let batches = read_table_changes(&engine_interface, 0, Some(0))?;
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].num_rows(), 3);
+
+    assert!(read_table_changes(&engine_interface, 2, Some(1)).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn table_changes_from_cdc_files() -> Result<(), Box<dyn std::error::Error>> {
+    const CDC_FILE: &str =
+        "_change_data/cdc-00000-a72b1fb3-f2df-41fe-a8f0-e65b746382dd.c000.snappy.parquet";
+    let batch = generate_simple_batch()?;
+    let cdc_batch = RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int32Array::from(vec![2, 2])) as ArrayRef),
+        (
+            "val",
+            Arc::new(StringArray::from(vec!["b", "x"])) as ArrayRef,
+        ),
+        (
+            "_change_type",
+            Arc::new(StringArray::from(vec![
+                "update_preimage",
+                "update_postimage",
+            ])) as ArrayRef,
+        ),
+    ])?;
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [
+            commit_info(1000),
+            CDF_METADATA.into(),
+            file_action("add", PARQUET_FILE1),
+        ]
+        .join("\n"),
+    )
+    .await?;
+    // the rewrite of the file must be ignored in favor of the cdc file
+    add_commit(
+        storage.as_ref(),
+        1,
+        [
+            commit_info(2000),
+            file_action("remove", PARQUET_FILE1),
+            file_action("add", PARQUET_FILE2),
+            format!(
+                r#"{{"cdc":{{"path":"{CDC_FILE}","partitionValues":{{}},"size":262,"dataChange":false}}}}"#
+            ),
+        ]
+        .join("\n"),
+    )
+    .await?;
+    storage
+        .put(&Path::from(PARQUET_FILE1), load_parquet(&batch).into())
+        .await?;
+    storage
+        .put(&Path::from(CDC_FILE), load_parquet(&cdc_batch).into())
+        .await?;
+
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+
+    let expected = vec![
+        "+----+-----+------------------+-----------------+---------------------+",
+        "| id | val | _change_type     | _commit_version | _commit_timestamp   |",
+        "+----+-----+------------------+-----------------+---------------------+",
+        "| 1  | a   | insert           | 0               | 1970-01-01T00:00:01 |",
+        "| 2  | b   | insert           | 0               | 1970-01-01T00:00:01 |",
+        "| 3  | c   | insert           | 0               | 1970-01-01T00:00:01 |",
+        "| 2  | b   | update_preimage  | 1               | 1970-01-01T00:00:02 |",
+        "| 2  | x   | update_postimage | 1               | 1970-01-01T00:00:02 |",
+        "+----+-----+------------------+-----------------+---------------------+",
+    ];
+    let batches = read_table_changes(&engine_interface, 0, None)?;
+    assert_batches_sorted_eq!(expected, &batches);
+    Ok(())
+}
+
+#[tokio::test]
+async fn table_changes_require_change_data_feed() -> Result<(), Box<dyn std::error::Error>> {
+    let disable_cdf = CDF_METADATA.replace(
+        r#""delta.enableChangeDataFeed":"true""#,
+        r#""delta.enableChangeDataFeed":"false""#,
+    );
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [commit_info(1000), CDF_METADATA.into()].join("\n"),
+    )
+    .await?;
+    add_commit(
+        storage.as_ref(),
+        1,
+        [commit_info(2000), file_action("add", PARQUET_FILE1)].join("\n"),
+    )
+    .await?;
+    add_commit(
+        storage.as_ref(),
+        2,
+        [commit_info(3000), disable_cdf].join("\n"),
+    )
+    .await?;
+    add_commit(
+        storage.as_ref(),
+        3,
+        [commit_info(4000), file_action("add", PARQUET_FILE2)].join("\n"),
+    )
+    .await?;
+    storage
+        .put(
+            &Path::from(PARQUET_FILE1),
+            load_parquet(&generate_simple_batch()?).into(),
+        )
+        .await?;
+
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+
+    assert!(read_table_changes(&engine_interface, 0, Some(1)).is_ok());
+    for (start_version, end_version, disabled_version) in
+        [(1, Some(2), 2), (0, None, 3), (3, None, 3)]
+    {
+        let err = read_table_changes(&engine_interface, start_version, end_version).unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<deltakernel::Error>(),
+                Some(deltakernel::Error::ChangeDataFeedNotEnabled(version)) if *version == disabled_version
+            ),
+            "unexpected error: {err}"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn table_changes_across_schema_change() -> Result<(), Box<dyn std::error::Error>> {
+    let add_column = CDF_METADATA.replace(
+        r#"{\"name\":\"val\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}"#,
+        r#"{\"name\":\"val\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}},{\"name\":\"other\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}"#,
+    );
+    assert_ne!(add_column, CDF_METADATA);
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [commit_info(1000), CDF_METADATA.into()].join("\n"),
+    )
+    .await?;
+    add_commit(
+        storage.as_ref(),
+        1,
+        [commit_info(2000), add_column].join("\n"),
+    )
+    .await?;
+
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+
+    assert!(read_table_changes(&engine_interface, 0, Some(0)).is_ok());
+    assert!(read_table_changes(&engine_interface, 1, Some(1)).is_ok());
+    let err = read_table_changes(&engine_interface, 0, Some(1)).unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<deltakernel::Error>(),
+            Some(deltakernel::Error::Unsupported(_))
+        ),
+        "unexpected error: {err}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn column_mapping_name_mode() -> Result<(), Box<dyn std::error::Error>> {
+    const METADATA: &str = r#"{"protocol":{"minReaderVersion":2,"minWriterVersion":5}}
+{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":1,\"delta.columnMapping.physicalName\":\"col-1\"}},{\"name\":\"info\",\"type\":{\"type\":\"struct\",\"fields\":[{\"name\":\"name\",\"type\":\"string\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":3,\"delta.columnMapping.physicalName\":\"col-3\"}}]},\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":2,\"delta.columnMapping.physicalName\":\"col-2\"}},{\"name\":\"part\",\"type\":\"string\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":4,\"delta.columnMapping.physicalName\":\"col-4\"}}]}","partitionColumns":["part"],"configuration":{"delta.columnMapping.mode":"name","delta.columnMapping.maxColumnId":"4"},"createdTime":1587968585495}}"#;
+
+    fn add_action(path: &str, part: &str, min: i32, max: i32) -> String {
+        format!(
+            r#"{{"add":{{"path":"{path}","partitionValues":{{"col-4":"{part}"}},"size":262,"modificationTime":1587968586000,"dataChange":true,"stats":"{{\"numRecords\":2,\"nullCount\":{{\"col-1\":0}},\"minValues\":{{\"col-1\":{min}}},\"maxValues\":{{\"col-1\":{max}}}}}"}}}}"#
+        )
+    }
+
+    fn physical_batch(ids: Vec<i32>, names: Vec<&str>) -> Result<RecordBatch, ArrowError> {
+        let info = arrow::array::StructArray::from(vec![(
+            Arc::new(arrow::datatypes::Field::new(
+                "col-3",
+                arrow::datatypes::DataType::Utf8,
+                true,
+            )),
+            Arc::new(StringArray::from(names)) as ArrayRef,
+        )]);
+        RecordBatch::try_from_iter(vec![
+            ("col-1", Arc::new(Int32Array::from(ids)) as ArrayRef),
+            ("col-2", Arc::new(info) as ArrayRef),
+        ])
+    }
+
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [
+            METADATA.into(),
+            add_action(PARQUET_FILE1, "x", 1, 2),
+            add_action(PARQUET_FILE2, "y", 5, 6),
+        ]
+        .join("\n"),
+    )
+    .await?;
+    storage
+        .put(
+            &Path::from(PARQUET_FILE1),
+            load_parquet(&physical_batch(vec![1, 2], vec!["a", "b"])?).into(),
+        )
+        .await?;
+    storage
+        .put(
+            &Path::from(PARQUET_FILE2),
+            load_parquet(&physical_batch(vec![5, 6], vec!["e", "f"])?).into(),
+        )
+        .await?;
+
+    let engine_interface = DefaultTableClient::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    );
+    let table = Table::new(Url::parse("memory:///")?);
+    let snapshot = table.snapshot(&engine_interface, None)?;
+
+    let read =
+        |predicate: Option<Expression>| -> Result<Vec<RecordBatch>, Box<dyn std::error::Error>> {
+            let mut builder = ScanBuilder::new(snapshot.clone());
+            if let Some(predicate) = predicate {
+                builder = builder.with_predicate(predicate);
+            }
+            let mut batches = vec![];
+            for result in builder.build().execute(&engine_interface)? {
+                batches.push(into_record_batch(result.raw_data?));
+            }
+            Ok(batches)
+        };
+
+    let expected = [
+        "+----+-----------+------+",
+        "| id | info      | part |",
+        "+----+-----------+------+",
+        "| 1  | {name: a} | x    |",
+        "| 2  | {name: b} | x    |",
+        "| 5  | {name: e} | y    |",
+        "| 6  | {name: f} | y    |",
+        "+----+-----------+------+",
+    ];
+    assert_batches_sorted_eq!(expected, &read(None)?);
+
+    let expected = [
+        "+----+-----------+------+",
+        "| id | info      | part |",
+        "+----+-----------+------+",
+        "| 5  | {name: e} | y    |",
+        "| 6  | {name: f} | y    |",
+        "+----+-----------+------+",
+    ];
+    let predicate = Expression::column("id").gt(Expression::literal(4));
+    assert_batches_sorted_eq!(expected, &read(Some(predicate))?);
+    Ok(())
+}